@@ -1,6 +1,7 @@
 use super::{Direction, InputType, OutputType, PointOffset, RelativeAabb};
 use crate::impl_dyn;
-use crate::simulator::{Component, ExternalType, GenerateIr, Property, SetProperty};
+use crate::simulator::{ir::MAX_WORDS, Component, ExternalType, GenerateIr, Property, SetProperty};
+use core::num::NonZeroU8;
 use std::error::Error;
 
 /// A component with fixed input & output locations
@@ -48,6 +49,8 @@ impl_dyn! {
 		ref generate_ir(gen: GenerateIr) -> usize;
 		ref properties() -> Box<[Property]>;
 		ref external_type() -> Option<ExternalType>;
+		ref delay() -> NonZeroU8;
+		ref input_masks() -> Box<[[usize; MAX_WORDS]]>;
 		mut set_property(name: &str, value: SetProperty) -> Result<(), Box<dyn Error>>;
 	}
 }
@@ -0,0 +1,646 @@
+//! # Components
+//!
+//! The placement of the inputs & outputs of the components in [`crate::simulator`].
+
+use super::{CircuitComponent, Direction, PointOffset, RelativeAabb};
+use crate::simulator::*;
+use core::num::NonZeroU16;
+
+const IN_NOT: &[PointOffset] = &[PointOffset::new(-1, 0)];
+const OUT: &[PointOffset] = &[PointOffset::new(1, 0)];
+const CENTER: &[PointOffset] = &[PointOffset::new(0, 0)];
+
+macro_rules! impl_cc {
+	($name:ident, $in:expr, $out:expr, (($min_x:literal, $min_y:literal), ($max_x:literal, $max_y:literal))) => {
+		#[typetag::serde]
+		impl CircuitComponent for $name {
+			fn input_points(&self) -> Box<[PointOffset]> {
+				let i = i16::from(self.inputs.get());
+				(-i / 2..0)
+					.chain((i % 2 != 0).then(|| 0))
+					.chain(1..=i / 2)
+					.map(|y| PointOffset::new(-1, y.try_into().unwrap()))
+					.collect()
+			}
+
+			fn output_points(&self) -> Box<[PointOffset]> {
+				$out.into()
+			}
+
+			fn input_name(&self, index: usize) -> Box<str> {
+				assert!(index < usize::from(self.inputs.get()));
+				format!("Input {}", index).into()
+			}
+
+			fn output_name(&self, index: usize) -> Box<str> {
+				assert!(index < 1);
+				"Output".into()
+			}
+
+			fn aabb(&self, dir: Direction) -> RelativeAabb {
+				dir * RelativeAabb::new(
+					PointOffset::new($min_x, $min_y),
+					PointOffset::new($max_x, $max_y),
+				)
+			}
+		}
+	};
+}
+
+impl_cc!(AndGate, IN, OUT, ((-1, -1), (1, 1)));
+
+impl_cc!(OrGate, IN, OUT, ((-1, -1), (1, 1)));
+
+impl_cc!(XorGate, IN, OUT, ((-1, -1), (1, 1)));
+
+#[typetag::serde]
+impl CircuitComponent for NotGate {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		IN_NOT.into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		OUT.into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Input".into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-1, 0), PointOffset::new(1, 0))
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for TriStateBuffer {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(-1, 0), PointOffset::new(0, 1)].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		OUT.into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		["Input", "Enable"][index].into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-1, 0), PointOffset::new(1, 1))
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for In {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		CENTER.into()
+	}
+
+	fn external_input(&self) -> Option<usize> {
+		Some(self.index)
+	}
+
+	fn input_name(&self, _: usize) -> Box<str> {
+		panic!()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		self.name.clone()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_in_out(self.bits, dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Out {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		CENTER.into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[].into()
+	}
+
+	fn external_output(&self) -> Option<usize> {
+		Some(self.index)
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		self.name.clone()
+	}
+
+	fn output_name(&self, _: usize) -> Box<str> {
+		panic!()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_in_out(self.bits, dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Splitter {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		IN_NOT.into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		(0..self.outputs().len().try_into().unwrap())
+			.map(|y| PointOffset::new(1, y))
+			.collect()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Input".into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		self.outputs[index].to_string().into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_merger_splitter(&*self.input_points(), &*self.output_points(), dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Merger {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		(0..self.inputs().len().try_into().unwrap())
+			.map(|y| PointOffset::new(-1, y))
+			.collect()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		OUT.into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		self.inputs[index].to_string().into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_merger_splitter(&*self.input_points(), &*self.output_points(), dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Constant {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		CENTER.into()
+	}
+
+	fn input_name(&self, _: usize) -> Box<str> {
+		panic!()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(0, 0), PointOffset::new(0, 0))
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Clock {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		CENTER.into()
+	}
+
+	fn input_name(&self, _: usize) -> Box<str> {
+		panic!()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-2, -1), PointOffset::new(0, 1))
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for ReadOnlyMemory {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(-4, 0)].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(4, 0)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Address".into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Value".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-4, -3), PointOffset::new(4, 3))
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for RandomAccessMemory {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		(-1..=2).map(|y| PointOffset::new(-4, y)).collect()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(4, 0)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		["Address", "Data", "Write enable", "Clock"][index].into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Data".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-4, -3), PointOffset::new(4, 3))
+	}
+}
+
+/// Flip-flops have their data inputs on the left above the clock, set at the top and reset at
+/// the bottom.
+macro_rules! impl_flip_flop_cc {
+	($name:ident, [$($input:literal),*]) => {
+		#[typetag::serde]
+		impl CircuitComponent for $name {
+			fn input_points(&self) -> Box<[PointOffset]> {
+				let data = [$($input),*].len().try_into().unwrap();
+				(0..data)
+					.map(|y| PointOffset::new(-2, y - 1))
+					.chain([
+						PointOffset::new(-2, 1),
+						PointOffset::new(0, -2),
+						PointOffset::new(0, 2),
+					])
+					.collect()
+			}
+
+			fn output_points(&self) -> Box<[PointOffset]> {
+				[PointOffset::new(2, -1), PointOffset::new(2, 1)].into()
+			}
+
+			fn input_name(&self, index: usize) -> Box<str> {
+				[$($input,)* "Clock", "Set", "Reset"][index].into()
+			}
+
+			fn output_name(&self, index: usize) -> Box<str> {
+				["Q", "Not Q"][index].into()
+			}
+
+			fn aabb(&self, dir: Direction) -> RelativeAabb {
+				dir * RelativeAabb::new(PointOffset::new(-2, -2), PointOffset::new(2, 2))
+			}
+		}
+	};
+}
+
+impl_flip_flop_cc!(DFlipFlop, ["D"]);
+
+impl_flip_flop_cc!(TFlipFlop, ["T"]);
+
+impl_flip_flop_cc!(JkFlipFlop, ["J", "K"]);
+
+impl_flip_flop_cc!(SrFlipFlop, ["S", "R"]);
+
+#[typetag::serde]
+impl CircuitComponent for Register {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		[(-3, -1), (-3, 1), (-3, 0), (0, -2), (0, 2)]
+			.map(|(x, y)| PointOffset::new(x, y))
+			.into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(3, 0)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		["Data", "Clock", "Enable", "Set", "Reset"][index].into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Data".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-3, -2), PointOffset::new(3, 2))
+	}
+}
+
+/// Points of `n` ports spread evenly above and below the center at `x`. The center is skipped if
+/// `n` is even.
+fn spread(n: usize, x: i8) -> impl Iterator<Item = PointOffset> {
+	let n = i8::try_from(n).unwrap();
+	(-n / 2..0)
+		.chain((n % 2 != 0).then(|| 0))
+		.chain(1..=n / 2)
+		.map(move |y| PointOffset::new(x, y))
+}
+
+/// The bounds of a plexer with `n` data ports, which has its select input at the bottom.
+fn aabb_plexer(n: usize, dir: Direction) -> RelativeAabb {
+	let h = i8::try_from(n / 2 + 1).unwrap();
+	dir * RelativeAabb::new(PointOffset::new(-2, -h), PointOffset::new(2, h))
+}
+
+#[typetag::serde]
+impl CircuitComponent for Multiplexer {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		let n = self.data_inputs();
+		let select = PointOffset::new(0, i8::try_from(n / 2 + 1).unwrap());
+		spread(n, -2).chain([select]).collect()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(2, 0)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		if index < self.data_inputs() {
+			format!("Input {}", index).into()
+		} else {
+			"Select".into()
+		}
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Output".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_plexer(self.data_inputs(), dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Demultiplexer {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		let select = i8::try_from(self.data_outputs() / 2 + 1).unwrap();
+		[PointOffset::new(-2, 0), PointOffset::new(0, select)].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		spread(self.data_outputs(), 2).collect()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		["Input", "Select"][index].into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < self.data_outputs());
+		format!("Output {}", index).into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_plexer(self.data_outputs(), dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for Decoder {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		let select = i8::try_from(self.outputs().len() / 2 + 1).unwrap();
+		[PointOffset::new(0, select)].into()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		spread(self.outputs().len(), 2).collect()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Select".into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < self.outputs().len());
+		format!("Output {}", index).into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_plexer(self.outputs().len(), dir)
+	}
+}
+
+#[typetag::serde]
+impl CircuitComponent for PriorityEncoder {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		spread(self.inputs().len(), -2).collect()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(2, 0), PointOffset::new(2, 1)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		assert!(index < self.inputs().len());
+		format!("Input {}", index).into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		["Index", "Valid"][index].into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		aabb_plexer(self.inputs().len(), dir)
+	}
+}
+
+macro_rules! impl_arithmetic_cc {
+	($name:ident, $in:expr, $out:expr) => {
+		/// The operands are on the left and the carry at the top, if any. The outputs are on the
+		/// right.
+		#[typetag::serde]
+		impl CircuitComponent for $name {
+			fn input_points(&self) -> Box<[PointOffset]> {
+				let carry = PointOffset::new(0, -2);
+				spread(2, -2).chain([carry]).take($in.len()).collect()
+			}
+
+			fn output_points(&self) -> Box<[PointOffset]> {
+				spread($out.len(), 2).collect()
+			}
+
+			fn input_name(&self, index: usize) -> Box<str> {
+				$in[index].into()
+			}
+
+			fn output_name(&self, index: usize) -> Box<str> {
+				$out[index].into()
+			}
+
+			fn aabb(&self, dir: Direction) -> RelativeAabb {
+				dir * RelativeAabb::new(PointOffset::new(-2, -2), PointOffset::new(2, 2))
+			}
+		}
+	};
+}
+
+impl_arithmetic_cc!(
+	Adder,
+	["A", "B", "Carry in"],
+	["Sum", "Carry out", "Overflow"]
+);
+impl_arithmetic_cc!(
+	Subtractor,
+	["A", "B", "Borrow in"],
+	["Difference", "Borrow out", "Overflow"]
+);
+impl_arithmetic_cc!(
+	Multiplier,
+	["A", "B", "Carry in"],
+	["Product", "Carry out", "Overflow"]
+);
+impl_arithmetic_cc!(
+	Divider,
+	["Dividend", "Divisor"],
+	["Quotient", "Remainder", "Overflow"]
+);
+impl_arithmetic_cc!(Comparator, ["A", "B"], ["Less", "Equal", "Greater"]);
+
+#[typetag::serde]
+impl CircuitComponent for Shifter {
+	fn input_points(&self) -> Box<[PointOffset]> {
+		spread(2, -2).collect()
+	}
+
+	fn output_points(&self) -> Box<[PointOffset]> {
+		[PointOffset::new(2, 0)].into()
+	}
+
+	fn input_name(&self, index: usize) -> Box<str> {
+		["Data", "Amount"][index].into()
+	}
+
+	fn output_name(&self, index: usize) -> Box<str> {
+		assert!(index < 1);
+		"Data".into()
+	}
+
+	fn aabb(&self, dir: Direction) -> RelativeAabb {
+		dir * RelativeAabb::new(PointOffset::new(-2, -2), PointOffset::new(2, 2))
+	}
+}
+
+macro_rules! impl_unary_cc {
+	($name:ident, $out:literal) => {
+		#[typetag::serde]
+		impl CircuitComponent for $name {
+			fn input_points(&self) -> Box<[PointOffset]> {
+				[PointOffset::new(-2, 0)].into()
+			}
+
+			fn output_points(&self) -> Box<[PointOffset]> {
+				[PointOffset::new(2, 0)].into()
+			}
+
+			fn input_name(&self, index: usize) -> Box<str> {
+				assert!(index < 1);
+				"Data".into()
+			}
+
+			fn output_name(&self, index: usize) -> Box<str> {
+				assert!(index < 1);
+				$out.into()
+			}
+
+			fn aabb(&self, dir: Direction) -> RelativeAabb {
+				dir * RelativeAabb::new(PointOffset::new(-2, -1), PointOffset::new(2, 1))
+			}
+		}
+	};
+}
+
+impl_unary_cc!(BitReverse, "Data");
+impl_unary_cc!(PopCount, "Count");
+impl_unary_cc!(LeadingZeros, "Count");
+impl_unary_cc!(Extender, "Data");
+
+/// The width and the amount of rows of an input or output. Values wider than 32 bits are shown
+/// in hexadecimal with 64 bits per row.
+pub fn in_out_size(bits: u16) -> (i8, usize) {
+	match bits {
+		1 => (1, 1),
+		2..=32 => (4, usize::from(bits - 1) / 8 + 1),
+		_ => (8, usize::from(bits - 1) / 64 + 1),
+	}
+}
+
+fn aabb_in_out(bits: NonZeroU16, dir: Direction) -> RelativeAabb {
+	let (w, rows) = in_out_size(bits.get());
+	let h = match dir {
+		Direction::Left | Direction::Right => [0, 1, 1, 2][rows - 1],
+		Direction::Up | Direction::Down => [1, 2, 3, 4][rows - 1],
+	};
+	let ((ax, ay), (bx, by)) = match dir {
+		Direction::Right => ((0, -h), (w, h)),
+		Direction::Left => ((-w, -h), (0, h)),
+		Direction::Up => ((-w / 2, -h), (w / 2, 0)),
+		Direction::Down => ((-w / 2, 0), (w / 2, h)),
+	};
+	RelativeAabb::new(PointOffset::new(ax, ay), PointOffset::new(bx, by))
+}
+
+fn aabb_merger_splitter(
+	inputs: &[PointOffset],
+	outputs: &[PointOffset],
+	dir: Direction,
+) -> RelativeAabb {
+	let mut aabb = RelativeAabb::new(inputs[0], outputs[0]);
+	inputs.iter().for_each(|&o| aabb = aabb.expand(o));
+	outputs.iter().for_each(|&o| aabb = aabb.expand(o));
+	dir * aabb
+}
@@ -1,13 +1,15 @@
 use super::*;
-use crate::simulator::{GenerateIr, IrOp, Program};
+use crate::simulator::{ir::words, GenerateIr, IrOp, Program};
+use core::cell::RefCell;
 use core::cmp::Ordering;
+use core::num::NonZeroU16;
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 use std::collections::{BinaryHeap, HashMap};
 use std::error::Error;
 use std::fmt;
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 lazy_static::lazy_static! {
@@ -19,6 +21,29 @@ lazy_static::lazy_static! {
 	static ref ICS: Mutex<HashMap<Arc<Path>, Ic>> = Default::default();
 }
 
+thread_local! {
+	/// The directory relative paths are resolved against, if not the working directory.
+	static DIRECTORY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with relative paths of ICs and of files written by tests resolved against `dir`
+/// instead of the working directory.
+pub fn with_directory<R>(dir: &Path, f: impl FnOnce() -> R) -> R {
+	let prev = DIRECTORY.with(|d| d.replace(Some(dir.into())));
+	let r = f();
+	DIRECTORY.with(|d| *d.borrow_mut() = prev);
+	r
+}
+
+/// Resolve a path against the directory set with [`with_directory`], if any.
+pub(super) fn resolve_path(path: &Path) -> PathBuf {
+	DIRECTORY.with(|d| {
+		d.borrow()
+			.as_ref()
+			.map_or_else(|| path.into(), |d| d.join(path))
+	})
+}
+
 struct Inner {
 	program: Program,
 	inputs: Box<[PointOffset]>,
@@ -76,7 +101,8 @@ impl Inner {
 			output_names.push(n);
 		}
 
-		let program = circuit.generate_ir();
+		let mut program = circuit.generate_ir();
+		program.optimize();
 
 		Self {
 			program,
@@ -111,21 +137,23 @@ impl fmt::Display for LoadError {
 pub struct Ic(Arc<Inner>);
 
 impl Ic {
-	/// Get an IC from cache or from disk.
+	/// Get an IC from cache or from disk. Relative paths are resolved against the directory set
+	/// with [`with_directory`], if any.
 	pub fn get_ic(path: impl Into<Arc<Path>>) -> Result<Self, LoadError> {
 		let mut ics = ICS.lock().unwrap();
 		let path = path.into();
-		if let Some(ic) = ics.get(&path) {
+		let full = Arc::<Path>::from(resolve_path(&path));
+		if let Some(ic) = ics.get(&full) {
 			Ok(ic.clone())
 		} else {
-			let file = File::open(&path).map_err(LoadError::Io)?;
+			let file = File::open(&full).map_err(LoadError::Io)?;
 			drop(ics);
 			let circuit: Circuit<Box<dyn CircuitComponent>> =
 				ron::de::from_reader(file).map_err(LoadError::Serde)?;
 			ics = ICS.lock().unwrap();
-			let ic = Self(Arc::new(Inner::from_circuit(circuit, path.clone())));
+			let ic = Self(Arc::new(Inner::from_circuit(circuit, path)));
 			Ok(ics
-				.try_insert(path, ic)
+				.try_insert(full, ic)
 				.unwrap_or_else(|_| unreachable!())
 				.clone())
 		}
@@ -135,13 +163,58 @@ impl Ic {
 		&self.0.path
 	}
 
-	/// Translate a memory address of an IC to another address for use in a larger circuit.
+	/// Translate a memory address of an IC to an address in a larger circuit.
+	fn translate_address(&self, a: usize, gen: &GenerateIr, max_mem: &mut usize) -> usize {
+		let f = |a: &[(usize, u16)], k, b: &[usize], c: &[usize]| {
+			a.iter()
+				.position(|&(e, bits)| (e..e.saturating_add(words(bits))).contains(&k))
+				.and_then(|i| Some((b.iter().position(|&e| e == i)?, k - a[i].0)))
+				.map(|(i, offset)| c[i].saturating_add(offset))
+				.filter(|&v| v != usize::MAX)
+		};
+		let Program { input_map, output_map, .. } = &self.0.program;
+		if let Some(v) = f(input_map, a, &self.0.input_map, gen.inputs) {
+			v
+		} else if let Some(v) = f(output_map, a, &self.0.output_map, gen.outputs) {
+			v
+		} else {
+			*max_mem = (*max_mem).max(a + 1);
+			gen.memory_size + a
+		}
+	}
+
+	/// Translate the memory addresses of an op of an IC for use in a larger circuit.
 	fn translate_mem_op(
 		&self,
 		op: &IrOp,
 		gen: &mut GenerateIr,
 		max_mem: &mut usize,
 	) -> Option<IrOp> {
+		// Regions are always internal memory.
+		if let Some((base, len)) = op.region() {
+			*max_mem = (*max_mem).max(base + len);
+		}
+		let base = |base| gen.memory_size + base;
+		let mut tr = |a| {
+			let a = self.translate_address(a, gen, max_mem);
+			(a != usize::MAX).then(|| a)
+		};
+		match *op {
+			IrOp::Fetch { base: b, len } => return Some(IrOp::Fetch { base: base(b), len }),
+			IrOp::Store { a, index, base: b, len } => {
+				let (a, index) = (tr(a)?, tr(index)?);
+				return Some(IrOp::Store { a, index, base: base(b), len });
+			}
+			IrOp::SaveIf { a, out } => return Some(IrOp::SaveIf { a: tr(a)?, out: tr(out)? }),
+			IrOp::SetIf { out, value, mask } => {
+				return Some(IrOp::SetIf { out: tr(out)?, value, mask });
+			}
+			IrOp::Edge { a, prev, falling } => {
+				let (a, prev) = (tr(a)?, tr(prev)?);
+				return Some(IrOp::Edge { a, prev, falling });
+			}
+			_ => (),
+		}
 		let ad = match op {
 			IrOp::CheckDirty { a, .. }
 			| IrOp::Save { out: a }
@@ -149,44 +222,64 @@ impl Ic {
 			| IrOp::Or { a }
 			| IrOp::Xor { a }
 			| IrOp::Copy { a }
-			| IrOp::SaveB { out: a } => {
-				let f = |a: &[_], &k, b: &[_], c: &[_]| {
-					a.iter()
-						.position(|&(e, _)| e == k)
-						.and_then(|i| b.iter().position(|&e| e == i))
-						.map(|i| c[i])
-						.filter(|&v| v != usize::MAX)
-				};
-				let Program { input_map, output_map, .. } = &self.0.program;
-				if let Some(v) = f(input_map, a, &self.0.input_map, &gen.inputs) {
-					v
-				} else if let Some(v) = f(&output_map, a, &self.0.output_map, &gen.outputs) {
-					v
-				} else {
-					*max_mem = (*max_mem).max(*a + 1);
-					gen.memory_size + *a
-				}
-			}
+			| IrOp::SaveB { out: a }
+			| IrOp::Resolve { a }
+			| IrOp::Enable { a }
+			| IrOp::Add { a, .. }
+			| IrOp::Sub { a, .. }
+			| IrOp::Mul { a, .. }
+			| IrOp::Div { a, .. }
+			| IrOp::Rem { a, .. }
+			| IrOp::Lt { a, .. }
+			| IrOp::Eq { a, .. }
+			| IrOp::Sll { a, .. }
+			| IrOp::Srl { a, .. }
+			| IrOp::Sra { a, .. }
+			| IrOp::Rotl { a, .. }
+			| IrOp::Rotr { a, .. } => self.translate_address(*a, gen, max_mem),
 			IrOp::Andi { .. }
 			| IrOp::Xori { .. }
+			| IrOp::Eqi { .. }
 			| IrOp::Slli { .. }
 			| IrOp::Srli { .. }
+			| IrOp::Srai { .. }
+			| IrOp::Popcnt { .. }
+			| IrOp::Clz { .. }
 			| IrOp::Load { .. }
 			| IrOp::Read { .. }
 			| IrOp::OrB => return Some(op.clone()),
+			IrOp::Fetch { .. }
+			| IrOp::Store { .. }
+			| IrOp::SaveIf { .. }
+			| IrOp::SetIf { .. }
+			| IrOp::Edge { .. } => unreachable!(),
 		};
 		if ad == usize::MAX {
 			return None;
 		}
 		let mut op = op.clone();
 		match &mut op {
-			IrOp::CheckDirty { a, node } => (*a, *node) = (ad, gen.nodes + *node),
+			IrOp::CheckDirty { a, node, .. } => (*a, *node) = (ad, gen.nodes + *node),
 			IrOp::Save { out: a }
 			| IrOp::And { a }
 			| IrOp::Or { a }
 			| IrOp::Xor { a }
 			| IrOp::Copy { a }
-			| IrOp::SaveB { out: a } => *a = ad,
+			| IrOp::SaveB { out: a }
+			| IrOp::Resolve { a }
+			| IrOp::Enable { a }
+			| IrOp::Add { a, .. }
+			| IrOp::Sub { a, .. }
+			| IrOp::Mul { a, .. }
+			| IrOp::Div { a, .. }
+			| IrOp::Rem { a, .. }
+			| IrOp::Lt { a, .. }
+			| IrOp::Eq { a, .. }
+			| IrOp::Sll { a, .. }
+			| IrOp::Srl { a, .. }
+			| IrOp::Sra { a, .. }
+			| IrOp::Rotl { a, .. }
+			| IrOp::Rotr { a, .. } => *a = ad,
 			_ => unreachable!(),
 		}
 		Some(op)
@@ -215,27 +308,38 @@ impl<'a> Deserialize<'a> for Ic {
 
 impl Component for Ic {
 	fn inputs(&self) -> Box<[InputType]> {
-		// TODO don't hardcode bits.
-		(0..self.0.inputs.len())
-			.map(|_| InputType { bits: core::num::NonZeroU8::new(1).unwrap() })
+		let map = &self.0.program.input_map;
+		let bits = |&i: &usize| map.get(i).and_then(|&(_, b)| NonZeroU16::new(b));
+		self.0
+			.input_map
+			.iter()
+			.map(|i| InputType { bits: bits(i).unwrap_or(NonZeroU16::new(1).unwrap()) })
 			.collect()
 	}
 
 	fn outputs(&self) -> Box<[OutputType]> {
-		// TODO don't hardcode bits.
-		(0..self.0.outputs.len())
-			.map(|_| OutputType { bits: core::num::NonZeroU8::new(1).unwrap() })
+		let map = &self.0.program.output_map;
+		let bits = |&i: &usize| map.get(i).and_then(|&(_, b)| NonZeroU16::new(b));
+		self.0
+			.output_map
+			.iter()
+			.map(|i| OutputType { bits: bits(i).unwrap_or(NonZeroU16::new(1).unwrap()) })
 			.collect()
 	}
 
 	fn generate_ir(&self, mut gen: GenerateIr) -> usize {
 		let mut ms = 0;
-		for n in self.0.program.nodes.iter() {
+		for (i, n) in self.0.program.nodes.iter().enumerate() {
 			let ir =
 				n.ir.iter()
 					.filter_map(|op| self.translate_mem_op(op, &mut gen, &mut ms))
 					.collect();
 			(gen.out)(ir);
+			(gen.set_delay)(i, n.delay);
+		}
+		for c in self.0.program.clocks.iter() {
+			let out = self.translate_address(c.out, &gen, &mut ms);
+			(gen.clock)(out, c.low, c.high);
 		}
 		ms
 	}
@@ -1,24 +1,29 @@
 mod aabb;
 mod circuit_component;
+mod components;
 mod direction;
 mod ic;
 mod point;
 mod point_offset;
 mod relative_aabb;
+mod report;
 mod script;
 mod wire;
 
 pub use aabb::*;
 pub use circuit_component::*;
+pub use components::in_out_size;
 pub use direction::*;
 pub use ic::*;
 pub use point::*;
 pub use point_offset::*;
 pub use relative_aabb::*;
+pub use report::*;
 pub use script::*;
 pub use wire::*;
 
 use super::simulator::{
+	ir::{self, Breakpoint, Oscillation, Watch},
 	Component, Graph, GraphIter, GraphNodeHandle, InputType, NexusHandle, OutputType, Port,
 	Program, Property, RemoveError, SetProperty,
 };
@@ -43,6 +48,8 @@ where
 	graph: Graph<C, (Point, Direction), Vec<WireHandle>>,
 	/// The source of the attached script, if any.
 	pub script_source: String,
+	/// Labels of wires by a point on the wire. Labels that aren't on any wire are ignored.
+	wire_labels: Vec<(Point, String)>,
 }
 
 impl<C> Circuit<C>
@@ -197,6 +204,36 @@ where
 		}
 	}
 
+	/// The label of a wire, if any.
+	pub fn wire_label(&self, handle: WireHandle) -> Option<&str> {
+		let (w, _) = self.wire(handle)?;
+		let mut labels = self.wire_labels.iter();
+		labels
+			.find(|(p, _)| w.intersect_point(*p))
+			.map(|(_, l)| &**l)
+	}
+
+	/// Label a wire, or remove its label if the label is empty.
+	pub fn set_wire_label(&mut self, handle: WireHandle, label: &str) {
+		let w = match self.wire(handle) {
+			Some((w, _)) => w,
+			None => return,
+		};
+		self.wire_labels.retain(|(p, _)| !w.intersect_point(*p));
+		if !label.is_empty() {
+			let (p, _): (Point, Point) = w.into();
+			self.wire_labels.push((p, label.into()));
+		}
+	}
+
+	/// All labels that are on a wire, with their position and the nexus of the wire.
+	pub fn wire_labels(&self) -> impl Iterator<Item = (Point, &str, NexusHandle)> + '_ {
+		self.wire_labels.iter().filter_map(|(p, l)| {
+			let (.., nexus) = self.wires(Aabb::new(*p, *p)).next()?;
+			Some((*p, &**l, nexus))
+		})
+	}
+
 	pub fn wire_endpoints(&self, point: Point) -> WireEndpointIter<C> {
 		WireEndpointIter {
 			iter: self.wires.iter(),
@@ -284,6 +321,88 @@ where
 		self.graph.generate_ir()
 	}
 
+	/// Describe the components involved in an oscillation by their label and position.
+	pub fn describe_oscillation(&self, oscillation: &Oscillation) -> String {
+		let mut s = format!(
+			"circuit oscillates with a period of {} steps across {} nexuses, caused by",
+			oscillation.period,
+			oscillation.nexuses.len()
+		);
+		for (i, &h) in oscillation.components.iter().enumerate() {
+			if let Some((c, p, _)) = self.component(h) {
+				s += if i == 0 { " " } else { ", " };
+				if let Some(l) = c.label() {
+					s += &format!("'{}' ", l);
+				}
+				s += &format!("({}, {})", p.x, p.y);
+			}
+		}
+		s
+	}
+
+	/// The signals to trace in a VCD file: all labelled inputs, outputs and wires.
+	pub fn vcd_signals(&self) -> Vec<(String, Watch)> {
+		let mut signals = Vec::new();
+		for (c, ..) in self.components(Aabb::ALL) {
+			match (c.label(), c.external_input(), c.external_output()) {
+				(Some(l), Some(i), _) if !l.is_empty() => signals.push((l.into(), Watch::Input(i))),
+				(Some(l), _, Some(o)) if !l.is_empty() => {
+					signals.push((l.into(), Watch::Output(o)))
+				}
+				_ => (),
+			}
+		}
+		for (_, l, nexus) in self.wire_labels() {
+			signals.push((l.into(), Watch::Nexus(nexus)));
+		}
+		signals
+	}
+
+	/// Describe what a breakpoint watches and when it fires.
+	pub fn describe_breakpoint(&self, breakpoint: &Breakpoint) -> String {
+		let condition = match breakpoint.condition {
+			ir::Condition::Equals(ir::Value::Set(v)) => format!("equals {}", v),
+			ir::Condition::Equals(v) => format!("equals {:?}", v),
+			ir::Condition::Changes => "changes".into(),
+			ir::Condition::Rises => "rises".into(),
+			ir::Condition::Falls => "falls".into(),
+		};
+		format!("{} {}", self.describe_watch(breakpoint.watch), condition)
+	}
+
+	/// Describe a watched value by the label of its input, output or wire, or else by its
+	/// position.
+	pub fn describe_watch(&self, watch: Watch) -> String {
+		match watch {
+			Watch::Output(i) => {
+				let mut c = self.components(Aabb::ALL);
+				match c.find(|(c, ..)| c.external_output() == Some(i)) {
+					Some((c, ..)) => format!("output '{}'", c.label().unwrap_or_default()),
+					None => format!("output {}", i),
+				}
+			}
+			Watch::Input(i) => {
+				let mut c = self.components(Aabb::ALL);
+				match c.find(|(c, ..)| c.external_input() == Some(i)) {
+					Some((c, ..)) => format!("input '{}'", c.label().unwrap_or_default()),
+					None => format!("input {}", i),
+				}
+			}
+			Watch::Nexus(h) => {
+				if let Some((_, l, _)) = self.wire_labels().find(|&(.., n)| n == h) {
+					return format!("wire '{}'", l);
+				}
+				match self.wires.iter().find(|(_, &(_, n))| n == h) {
+					Some((_, &(w, _))) => {
+						let (p, _): (Point, Point) = w.into();
+						format!("wire at ({}, {})", p.x, p.y)
+					}
+					None => format!("nexus {}", h.index()),
+				}
+			}
+		}
+	}
+
 	fn find_ports_at_internal<'a, F, G>(
 		&'a self,
 		pos: Point,
@@ -369,6 +488,7 @@ where
 			wires: Default::default(),
 			graph: Graph::new(),
 			script_source: Default::default(),
+			wire_labels: Default::default(),
 		}
 	}
 }
@@ -398,6 +518,9 @@ where
 		if !self.script_source.is_empty() {
 			circuit.serialize_field("script", &self.script_source)?;
 		}
+		if !self.wire_labels.is_empty() {
+			circuit.serialize_field("labels", &self.wire_labels)?;
+		}
 		circuit.end()
 	}
 }
@@ -416,6 +539,7 @@ where
 			Wires,
 			Components,
 			Script,
+			Labels,
 		}
 
 		struct CircuitVisitor<C>(core::marker::PhantomData<C>);
@@ -436,7 +560,7 @@ where
 			{
 				let mut s = Circuit::default();
 				let (mut handled_wires, mut handled_components) = (false, false);
-				let mut handled_script = false;
+				let (mut handled_script, mut handled_labels) = (false, false);
 
 				while let Some(key) = map.next_key()? {
 					match key {
@@ -465,6 +589,13 @@ where
 							handled_script = true;
 							s.script_source = map.next_value::<String>()?;
 						}
+						Field::Labels => {
+							if handled_labels {
+								Err(de::Error::duplicate_field("labels"))?;
+							}
+							handled_labels = true;
+							s.wire_labels = map.next_value::<Vec<(Point, String)>>()?;
+						}
 					}
 				}
 
@@ -474,7 +605,7 @@ where
 
 		deserializer.deserialize_struct(
 			stringify!(Circuit),
-			&["wires", "components", "script"],
+			&["wires", "components", "script", "labels"],
 			CircuitVisitor(core::marker::PhantomData),
 		)
 	}
@@ -573,9 +704,11 @@ pub enum MoveError {
 mod test {
 	use super::*;
 	use crate::simulator::{
-		ir, AndGate as And, In, NonZeroOneU8, NotGate as Not, OrGate as Or, Out, XorGate as Xor,
+		ir, AndGate as And, Builder, Clock, In, NonZeroOneU8, NotGate as Not, OrGate as Or, Out,
+		XorGate as Xor,
 	};
-	use core::num::NonZeroU8;
+	use core::num::{NonZeroU16, NonZeroU32};
+	use std::path::Path;
 	use std::sync::Arc;
 
 	/// ```
@@ -607,7 +740,7 @@ mod test {
 	fn manual_xor() {
 		let mut circuit = Box::<Circuit<Box<dyn CircuitComponent>>>::default();
 
-		let bits = NonZeroU8::new(4).unwrap();
+		let bits = NonZeroU16::new(4).unwrap();
 		let inputs = NonZeroOneU8::new(2).unwrap();
 		let i0 = Box::new(In::new("I0", bits, 0));
 		let i1 = Box::new(In::new("I1", bits, 1));
@@ -662,4 +795,80 @@ mod test {
 		state.read_outputs(&mut out);
 		assert_eq!(out, [ir::Value::Set(a ^ b); 2]);
 	}
+
+	#[test]
+	fn wire_labels() {
+		let mut circuit = Circuit::<Box<dyn CircuitComponent>>::default();
+		let bits = NonZeroU16::new(1).unwrap();
+		circuit.add_component(
+			Box::new(In::new("a", bits, 0)),
+			Point::new(0, 0),
+			Direction::Right,
+		);
+		circuit.add_component(
+			Box::new(Out::new("", bits, 0)),
+			Point::new(4, 0),
+			Direction::Right,
+		);
+		circuit.add_wire(Wire::new(Point::new(0, 0), Point::new(4, 0)));
+		let (_, wire, nexus) = circuit.wire_endpoints(Point::new(4, 0)).next().unwrap();
+		circuit.set_wire_label(wire, "w");
+		assert_eq!(circuit.wire_label(wire), Some("w"));
+
+		// Splitting the wire keeps the label on the same nexus.
+		circuit.add_wire(Wire::new(Point::new(2, 0), Point::new(2, 3)));
+		let signals = [
+			("a".into(), Watch::Input(0)),
+			("w".into(), Watch::Nexus(nexus)),
+		];
+		assert_eq!(circuit.vcd_signals(), signals);
+
+		let (_, wire, _) = circuit.wire_endpoints(Point::new(0, 0)).next().unwrap();
+		circuit.set_wire_label(wire, "");
+		assert_eq!(circuit.wire_labels().count(), 0);
+	}
+
+	/// The directory with the circuits of a 4-bit counter.
+	fn counter_dir() -> std::path::PathBuf {
+		Path::new(env!("CARGO_MANIFEST_DIR")).join("../test/counter")
+	}
+
+	#[test]
+	fn counter_4() {
+		let dir = counter_dir();
+		let file = std::fs::File::open(dir.join("counter_4.logimu")).unwrap();
+		let mut circuit: Circuit<Box<dyn CircuitComponent>> =
+			with_directory(&dir, || ron::de::from_reader(file)).unwrap();
+		let mut state = Arc::new(circuit.generate_ir()).new_state();
+		// The counter starts at 0 and advances on each falling edge of the clock.
+		for i in 0..40 {
+			for clock in [1, 0] {
+				state.write_inputs(&[ir::Value::Set(clock), ir::Value::Set(1)]);
+				assert!(state.run(1024).is_settled());
+			}
+			let mut out = [ir::Value::Floating];
+			state.read_outputs(&mut out);
+			assert_eq!(out[0], ir::Value::Set((i + 1) % 16));
+		}
+	}
+
+	#[test]
+	fn clocked_counter_4() {
+		let counter = with_directory(&counter_dir(), || Ic::get_ic(Path::new("counter_4.logimu")));
+		let mut b = Builder::new();
+		let period = NonZeroU32::new(32).unwrap();
+		let clock = b.add(Clock::new(period, period), &[])[0];
+		let enable = b.constant(1, 1);
+		let out = b.add(counter.unwrap(), &[clock, enable])[0];
+		b.output("Q", out);
+		let mut state = Arc::new(b.generate_ir()).new_state();
+		// The counter advances on the falling edge, so check halfway the low phase.
+		state.advance(16);
+		for i in 1..=20 {
+			state.advance(64);
+			let mut out = [ir::Value::Floating];
+			state.read_outputs(&mut out);
+			assert_eq!(out[0], ir::Value::Set(i % 16), "after {} periods", i);
+		}
+	}
 }
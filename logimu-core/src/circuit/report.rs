@@ -0,0 +1,247 @@
+//! # Test reports
+//!
+//! The results of running the tests of one or more circuits, which can be written as JUnit XML or
+//! TAP for use by other tools.
+
+use super::TestError;
+use core::time::Duration;
+use std::io::{self, Write};
+
+/// The outcome of a single test.
+#[derive(Debug)]
+pub struct Outcome {
+	pub name: String,
+	pub result: Result<(), TestError>,
+	/// The output of `print`.
+	pub log: String,
+	pub duration: Duration,
+}
+
+/// The outcomes of the tests of a single circuit.
+#[derive(Debug)]
+pub struct Suite {
+	pub name: String,
+	pub outcomes: Vec<Outcome>,
+	/// The error that prevented the tests from running, if any.
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+	pub suites: Vec<Suite>,
+}
+
+impl Suite {
+	/// The amount of tests that failed.
+	pub fn failures(&self) -> usize {
+		self.outcomes.iter().filter(|o| o.result.is_err()).count()
+	}
+
+	/// The total duration of all tests.
+	pub fn duration(&self) -> Duration {
+		self.outcomes.iter().map(|o| o.duration).sum()
+	}
+}
+
+impl Report {
+	/// Write the report as JUnit XML, with a `testsuite` for each circuit.
+	pub fn write_junit(&self, mut out: impl Write) -> io::Result<()> {
+		writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+		writeln!(out, "<testsuites>")?;
+		for s in self.suites.iter() {
+			writeln!(
+				out,
+				r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+				xml_escape(&s.name),
+				s.outcomes.len() + usize::from(s.error.is_some()),
+				s.failures(),
+				usize::from(s.error.is_some()),
+				s.duration().as_secs_f64(),
+			)?;
+			if let Some(e) = &s.error {
+				let name = xml_escape(&s.name);
+				writeln!(
+					out,
+					r#"    <testcase name="{}" classname="{}">"#,
+					name, name
+				)?;
+				writeln!(out, r#"      <error message="{}"/>"#, xml_escape(e))?;
+				writeln!(out, "    </testcase>")?;
+			}
+			for o in s.outcomes.iter() {
+				write!(
+					out,
+					r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+					xml_escape(&o.name),
+					xml_escape(&s.name),
+					o.duration.as_secs_f64(),
+				)?;
+				if o.result.is_ok() && o.log.is_empty() {
+					writeln!(out, "/>")?;
+					continue;
+				}
+				writeln!(out, ">")?;
+				if let Err(e) = &o.result {
+					let e = xml_escape(&e.to_string());
+					writeln!(out, r#"      <failure message="{}">{}</failure>"#, e, e)?;
+				}
+				if !o.log.is_empty() {
+					writeln!(out, "      <system-out>{}</system-out>", xml_escape(&o.log))?;
+				}
+				writeln!(out, "    </testcase>")?;
+			}
+			writeln!(out, "  </testsuite>")?;
+		}
+		writeln!(out, "</testsuites>")
+	}
+
+	/// Write the report as TAP version 13. The failure, duration & output of each test are written
+	/// as a YAML block.
+	pub fn write_tap(&self, mut out: impl Write) -> io::Result<()> {
+		let count = self.suites.iter().map(|s| s.outcomes.len().max(1));
+		writeln!(out, "TAP version 13")?;
+		writeln!(out, "1..{}", count.sum::<usize>())?;
+		let mut i = 0;
+		for s in self.suites.iter() {
+			if s.outcomes.is_empty() {
+				i += 1;
+				match &s.error {
+					Some(e) => {
+						writeln!(out, "not ok {} - {}", i, s.name)?;
+						writeln!(out, "  ---")?;
+						yaml_block(&mut out, "message", e)?;
+						writeln!(out, "  ...")?;
+					}
+					None => writeln!(out, "ok {} - {} # SKIP no tests", i, s.name)?,
+				}
+				continue;
+			}
+			for o in s.outcomes.iter() {
+				i += 1;
+				let ok = if o.result.is_ok() { "ok" } else { "not ok" };
+				// `#` would start a directive.
+				let name = o.name.replace('#', "\\#");
+				writeln!(out, "{} {} - {} '{}'", ok, i, s.name, name)?;
+				writeln!(out, "  ---")?;
+				if let Err(e) = &o.result {
+					yaml_block(&mut out, "message", &e.to_string())?;
+				}
+				writeln!(out, "  duration_ms: {:.3}", o.duration.as_secs_f64() * 1e3)?;
+				if !o.log.is_empty() {
+					yaml_block(&mut out, "output", &o.log)?;
+				}
+				writeln!(out, "  ...")?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Escape text for use in XML attributes & elements. Characters that aren't allowed in XML are
+/// replaced.
+fn xml_escape(s: &str) -> String {
+	let mut e = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => e += "&amp;",
+			'<' => e += "&lt;",
+			'>' => e += "&gt;",
+			'"' => e += "&quot;",
+			'\'' => e += "&apos;",
+			'\t' | '\n' | '\r' => e.push(c),
+			c if c.is_control() => e.push(char::REPLACEMENT_CHARACTER),
+			c => e.push(c),
+		}
+	}
+	e
+}
+
+/// Write a YAML literal block, which needs no escaping.
+fn yaml_block(mut out: impl Write, key: &str, text: &str) -> io::Result<()> {
+	writeln!(out, "  {}: |", key)?;
+	text.lines().try_for_each(|l| writeln!(out, "    {}", l))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn report() -> Report {
+		let ms = Duration::from_millis;
+		let outcomes = vec![
+			Outcome {
+				name: "invert".into(),
+				result: Ok(()),
+				log: "0\n".into(),
+				duration: ms(2),
+			},
+			Outcome {
+				name: "a < b".into(),
+				result: Err(TestError::RunError(
+					"assertion failed: `(= a \"b\")`".into(),
+				)),
+				log: String::new(),
+				duration: ms(1500),
+			},
+		];
+		let error = Some("invalid circuit".into());
+		Report {
+			suites: vec![
+				Suite { name: "not.ron".into(), outcomes, error: None },
+				Suite { name: "bad.ron".into(), outcomes: Vec::new(), error },
+			],
+		}
+	}
+
+	#[test]
+	fn junit() {
+		let mut out = Vec::new();
+		report().write_junit(&mut out).unwrap();
+		let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites>
+  <testsuite name="not.ron" tests="2" failures="1" errors="0" time="1.502">
+    <testcase name="invert" classname="not.ron" time="0.002">
+      <system-out>0
+</system-out>
+    </testcase>
+    <testcase name="a &lt; b" classname="not.ron" time="1.500">
+      <failure message="assertion failed: `(= a &quot;b&quot;)`">assertion failed: `(= a &quot;b&quot;)`</failure>
+    </testcase>
+  </testsuite>
+  <testsuite name="bad.ron" tests="1" failures="0" errors="1" time="0.000">
+    <testcase name="bad.ron" classname="bad.ron">
+      <error message="invalid circuit"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#;
+		assert_eq!(String::from_utf8(out).unwrap(), expected);
+	}
+
+	#[test]
+	fn tap() {
+		let mut out = Vec::new();
+		report().write_tap(&mut out).unwrap();
+		let expected = r#"TAP version 13
+1..3
+ok 1 - not.ron 'invert'
+  ---
+  duration_ms: 2.000
+  output: |
+    0
+  ...
+not ok 2 - not.ron 'a < b'
+  ---
+  message: |
+    assertion failed: `(= a "b")`
+  duration_ms: 1500.000
+  ...
+not ok 3 - bad.ron
+  ---
+  message: |
+    invalid circuit
+  ...
+"#;
+		assert_eq!(String::from_utf8(out).unwrap(), expected);
+	}
+}
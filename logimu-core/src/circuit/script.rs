@@ -0,0 +1,417 @@
+use super::*;
+use crate::script::*;
+use crate::simulator::{self, ir};
+use core::cell::{Cell, RefCell};
+use core::fmt;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+impl<C> Circuit<C>
+where
+	C: CircuitComponent,
+{
+	pub fn tests(&mut self) -> Result<Vec<Test<C>>, ParseError> {
+		let mut src = self.script_source.trim_start();
+		let mut tests = Vec::new();
+		while let Some((script, s)) = SExpr::parse(src)? {
+			if script.get(0).and_then(Arg::as_symbol) == Some("test") {
+				// Ensure name exists and is valid.
+				script
+					.get(1)
+					.and_then(Arg::to_value)
+					.and_then(Value::into_string)
+					.map(|_| tests.push(Test { circuit: self, script }));
+			}
+			src = s.trim_start();
+		}
+		Ok(tests)
+	}
+}
+
+/// The maximum amount of inputs of a circuit for `(exhaustive ...)`.
+const MAX_EXHAUSTIVE_INPUTS: usize = 16;
+
+/// The maximum amount of steps to wait for the circuit to settle.
+const MAX_ITERATIONS: usize = 1024;
+
+pub struct Test<'a, C>
+where
+	C: CircuitComponent,
+{
+	circuit: &'a Circuit<C>,
+	script: SExpr,
+}
+
+impl<C> Test<'_, C>
+where
+	C: CircuitComponent,
+{
+	pub fn name(&self) -> &str {
+		match self.script.get(1) {
+			Some(Arg::Str(s)) => &**s,
+			_ => unreachable!(),
+		}
+	}
+
+	/// Run the test. Breakpoints and traces of the state are disabled while the test runs, except
+	/// those set by the test itself.
+	pub fn run(
+		&self,
+		state: &mut simulator::State,
+		inputs: &mut [ir::Value],
+		outputs: &mut [ir::Value],
+		log: impl core::fmt::Write,
+	) -> Result<(), TestError> {
+		let breakpoints = state.breakpoints().copied().collect::<Vec<_>>();
+		state.set_breakpoints([]);
+		let vcd = state.set_vcd(None);
+		let r = self.run_script(state, inputs, outputs, log);
+		state.set_breakpoints(breakpoints);
+		let trace = state.set_vcd(vcd).map_or(Ok(()), |t| t.finish());
+		r?;
+		trace.map_err(|e| TestError::RunError(format!("failed to write trace: {}", e).into()))
+	}
+
+	fn run_script(
+		&self,
+		state: &mut simulator::State,
+		inputs: &mut [ir::Value],
+		outputs: &mut [ir::Value],
+		log: impl core::fmt::Write,
+	) -> Result<(), TestError> {
+		let (state, inputs, outputs) = (RefCell::new(state), Cell::new(inputs), Cell::new(outputs));
+		let log = Cell::new(Some(log));
+		let settled = |r: ir::RunResult| -> Result<(), Box<dyn Error>> {
+			match r {
+				ir::RunResult::Settled { .. } => Ok(()),
+				ir::RunResult::Oscillated(o) => Err(self.circuit.describe_oscillation(&o).into()),
+				ir::RunResult::Limit => {
+					Err(format!("circuit didn't settle within {} steps", MAX_ITERATIONS).into())
+				}
+				ir::RunResult::Break { breakpoint, .. } => {
+					Err(format!("breakpoint {} fired", breakpoint).into())
+				}
+			}
+		};
+		let r = Runner::new(
+			|r, s, f, e| {
+				let get_value = |i| -> Result<_, Box<dyn Error>> {
+					Ok(match e.get(i).ok_or(RunError::ExpectedArgument)? {
+						Arg::SExpr(s) => r.handle(s)?,
+						Arg::Symbol(n) => s.get(n).ok_or(RunError::SymbolNotDefined)?,
+						v => v.to_value().unwrap(),
+					})
+				};
+				match f {
+					"in" => {
+						let label = get_value(1)?.into_string().ok_or(RunError::ExpectedStr)?;
+						// Without a value the current value of the input is returned.
+						if e.len() == 2 {
+							for (c, ..) in self.circuit.components(Aabb::ALL) {
+								if let Some(i) = c.external_input() {
+									if c.label() == Some(&label) {
+										let inp = inputs.take();
+										let value = inp[i];
+										inputs.set(inp);
+										return match value {
+											ir::Value::Set(v) => Ok(Value::Int(v as i64)),
+											ir::Value::Floating => Ok(Value::None),
+											_ => {
+												Err(format!("input '{}' is not set", label).into())
+											}
+										};
+									}
+								}
+							}
+							return Err(format!("input '{}' not found", label).into());
+						}
+						// `none` makes the input floating.
+						let value = match get_value(2)? {
+							Value::None => ir::Value::Floating,
+							v => ir::Value::Set(v.as_int().ok_or(RunError::ExpectedInt)? as usize),
+						};
+						for (c, ..) in self.circuit.components(Aabb::ALL) {
+							if let Some(i) = c.external_input() {
+								if c.label() == Some(&label) {
+									let inp = inputs.take();
+									inp[i] = value;
+									inputs.set(inp);
+									return Ok(Value::None);
+								}
+							}
+						}
+						Err(format!("input '{}' not found", label).into())
+					}
+					"out" => {
+						let label = get_value(1)?.into_string().ok_or(RunError::ExpectedStr)?;
+						for (c, ..) in self.circuit.components(Aabb::ALL) {
+							if let Some(i) = c.external_output() {
+								if c.label() == Some(&label) {
+									let outp = outputs.take();
+									let value = outp[i];
+									outputs.set(outp);
+									// Floating outputs are `none`, any other undefined bits are an error.
+									return match value {
+										ir::Value::Set(o) => Ok(Value::Int(o as i64)),
+										ir::Value::Floating => Ok(Value::None),
+										ir::Value::Short => {
+											Err(format!("output '{}' is shorted", label).into())
+										}
+										ir::Value::Mixed { .. } => Err(format!(
+											"output '{}' has floating or shorted bits",
+											label
+										)
+										.into()),
+										ir::Value::Wide { .. } => Err(format!(
+											"output '{}' doesn't fit in an integer",
+											label
+										)
+										.into()),
+									};
+								}
+							}
+						}
+						Err(format!("output '{}' not found", label).into())
+					}
+					"run" => {
+						let (inp, outp) = (inputs.take(), outputs.take());
+						let mut s = state.borrow_mut();
+						s.write_inputs(inp);
+						let r = s.run(MAX_ITERATIONS);
+						s.read_outputs(outp);
+						(inputs.set(inp), outputs.set(outp));
+						// The breakpoint that fired is returned, if any.
+						if let ir::RunResult::Break { breakpoint, .. } = r {
+							return Ok(Value::Int(breakpoint as i64));
+						}
+						settled(r)?;
+						Ok(Value::None)
+					}
+					"break" => {
+						// Watch an output, returning the index of the breakpoint.
+						let label = get_value(1)?.into_string().ok_or(RunError::ExpectedStr)?;
+						let condition = match e.get(2).and_then(Arg::as_symbol) {
+							Some("changes") => ir::Condition::Changes,
+							Some("rises") => ir::Condition::Rises,
+							Some("falls") => ir::Condition::Falls,
+							Some("equals") => {
+								let v = get_value(3)?.as_int().ok_or(RunError::ExpectedInt)?;
+								ir::Condition::Equals(ir::Value::Set(v as usize))
+							}
+							_ => Err(RunError::ExpectedKeyword(&[
+								"changes", "rises", "falls", "equals",
+							]))?,
+						};
+						for (c, ..) in self.circuit.components(Aabb::ALL) {
+							if let Some(i) = c.external_output() {
+								if c.label() == Some(&label) {
+									let mut s = state.borrow_mut();
+									let mut b = s.breakpoints().copied().collect::<Vec<_>>();
+									b.push(ir::Breakpoint::new(ir::Watch::Output(i), condition));
+									s.set_breakpoints(b);
+									return Ok(Value::Int(s.breakpoints().count() as i64 - 1));
+								}
+							}
+						}
+						Err(format!("output '{}' not found", label).into())
+					}
+					"dump-vcd" => {
+						// Trace the inputs, outputs & labelled wires for the rest of the test.
+						// Relative paths are resolved like those of ICs.
+						let path = get_value(1)?.into_string().ok_or(RunError::ExpectedStr)?;
+						let full = resolve_path(Path::new(&*path));
+						let vcd = ir::Vcd::create(full, self.circuit.vcd_signals())
+							.map_err(|e| format!("failed to create '{}': {}", path, e))?;
+						if let Some(prev) = state.borrow_mut().set_vcd(Some(vcd)) {
+							prev.finish()?;
+						}
+						Ok(Value::None)
+					}
+					"exhaustive" => {
+						// Simulate all combinations of inputs in batch mode first, then run the
+						// body for each combination with the inputs and outputs set accordingly.
+						let program = state.borrow().program().clone();
+						if program.input_map.len() > MAX_EXHAUSTIVE_INPUTS {
+							Err(format!(
+								"exhaustive tests support up to {} inputs",
+								MAX_EXHAUSTIVE_INPUTS
+							))?;
+						}
+						let table = program
+							.truth_table(MAX_ITERATIONS)
+							.ok_or("exhaustive tests only support 1-bit inputs and outputs")?;
+						let table = match table {
+							Ok(table) => table,
+							Err(r) => return settled(r).map(|_| Value::None),
+						};
+						for (combination, row) in table.iter().enumerate() {
+							let (inp, outp) = (inputs.take(), outputs.take());
+							for (i, v) in inp.iter_mut().enumerate() {
+								*v = ir::Value::Set(combination >> i & 1);
+							}
+							outp.iter_mut().zip(row.iter()).for_each(|(o, v)| *o = *v);
+							inputs.set(inp);
+							outputs.set(outp);
+							for a in &e[1..] {
+								if let Some(a) = a.as_sexpr() {
+									r.handle(a)?;
+								}
+							}
+						}
+						Ok(Value::None)
+					}
+					"print" => {
+						let mut l = log.take().unwrap();
+						let res = print_args(&mut l, r, s as &dyn Storage<_>, &e[1..])
+							.and_then(|()| l.write_char('\n').map_err(PrintError::FmtError));
+						log.set(Some(l));
+						match res {
+							Err(PrintError::RunError(e)) => Err(e)?,
+							Err(PrintError::FmtError(e)) => Err(RunError::FmtError(e))?,
+							Ok(()) => Ok(Value::None),
+						}
+					}
+					f => Err(RunError::FunctionNotDefined(f))?,
+				}
+			},
+			Cell::new(HashMap::<Box<str>, _>::default()),
+		);
+		for e in &self.script[2..] {
+			match e {
+				Arg::SExpr(s) => {
+					r.handle(s)
+						.map_err(|e| TestError::RunError(e.to_string().into()))?;
+				}
+				Arg::Symbol(n) => {
+					r.storage
+						.get(n)
+						.ok_or_else(|| TestError::RunError(Box::new(RunError::SymbolNotDefined)))?;
+				}
+				_ => (),
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub enum TestError {
+	RunError(Box<dyn Error>),
+}
+
+impl fmt::Display for TestError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::RunError(e) => e.fmt(f),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{In, NotGate, Out};
+	use core::num::NonZeroU16;
+	use std::sync::Arc;
+
+	/// A circuit inverting input "a" to output "y" with the given script.
+	fn not(script: &str) -> Circuit<Box<dyn CircuitComponent>> {
+		let mut circuit = Circuit::<Box<dyn CircuitComponent>>::default();
+		let bits = NonZeroU16::new(1).unwrap();
+		let mut not = NotGate::new();
+		not.bits = bits;
+		circuit.add_component(
+			Box::new(In::new("a", bits, 0)),
+			Point::new(0, 0),
+			Direction::Right,
+		);
+		circuit.add_component(Box::new(not), Point::new(2, 0), Direction::Right);
+		circuit.add_component(
+			Box::new(Out::new("y", bits, 0)),
+			Point::new(4, 0),
+			Direction::Right,
+		);
+		circuit.add_wire(Wire::new(Point::new(0, 0), Point::new(1, 0)));
+		circuit.add_wire(Wire::new(Point::new(3, 0), Point::new(4, 0)));
+		circuit.script_source = script.into();
+		circuit
+	}
+
+	/// Run all tests of a circuit, returning the result and log of each.
+	fn run(circuit: &mut Circuit<Box<dyn CircuitComponent>>) -> Vec<(Result<(), String>, String)> {
+		let program = Arc::new(circuit.generate_ir());
+		let tests = circuit.tests().unwrap();
+		let run = |t: &Test<_>| {
+			let mut state = program.clone().new_state();
+			let (mut inputs, mut outputs) = ([ir::Value::Floating], [ir::Value::Floating]);
+			let mut log = String::new();
+			let r = t.run(&mut state, &mut inputs, &mut outputs, &mut log);
+			(r.map_err(|e| e.to_string()), log)
+		};
+		tests.iter().map(run).collect()
+	}
+
+	#[test]
+	fn unknown_function() {
+		let mut circuit = not(r#"(test "typo" (in "a" 1) (rnu))"#);
+		let r = run(&mut circuit);
+		assert_eq!(r[0].0, Err("function 'rnu' not defined".into()));
+	}
+
+	#[test]
+	fn breakpoints() {
+		let mut circuit = not(r#"
+			(test "rises" (in "a" 1) (run) (print (break "y" rises)) (in "a" 0) (print (run)))
+			(test "unwatched" (in "a" 1) (run) (print (break "y" falls)) (in "a" 0) (print (run)))
+		"#);
+		let r = run(&mut circuit);
+		assert_eq!(r[0], (Ok(()), "0\n0\n".into()));
+		assert_eq!(r[1], (Ok(()), "0\nnone\n".into()));
+	}
+
+	#[test]
+	fn dump_vcd() {
+		let dir = std::env::temp_dir();
+		let path = dir.join("logimu-script-dump-vcd.vcd");
+		let mut circuit = not(r#"
+			(test "trace" (dump-vcd "logimu-script-dump-vcd.vcd") (in "a" 1) (run) (in "a" 0) (run))
+		"#);
+		let r = with_directory(&dir, || run(&mut circuit));
+		assert_eq!(r[0], (Ok(()), String::new()));
+		let out = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let expected = "\
+$version logimu $end
+$timescale 1ns $end
+$scope module circuit $end
+$var wire 1 ! a $end
+$var wire 1 \" y $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+z!
+0\"
+$end
+#1
+1!
+#2
+0!
+1\"
+";
+		assert_eq!(out, expected);
+	}
+
+	#[test]
+	fn exhaustive() {
+		let mut circuit = not(r#"
+			(test "pass" (exhaustive (print (in "a")) (assert (= (out "y") (- 1 (in "a"))))))
+			(test "fail" (exhaustive (assert (= (out "y") (in "a")))))
+		"#);
+		let r = run(&mut circuit);
+		assert_eq!(r[0], (Ok(()), "0\n1\n".into()));
+		let e = "assertion failed: `(= (out \"y\") (in \"a\"))`";
+		assert_eq!(r[1], (Err(e.into()), String::new()));
+	}
+}
@@ -0,0 +1,58 @@
+//! # Logimu
+//!
+//! The simulator and circuit model of Logimu, without any GUI.
+//!
+//! - [`simulator`] builds a [`Graph`] of components connected by nexuses, which is compiled to a
+//!   [`Program`]. A [`State`] holds the values of a program and steps it. Graphs can be
+//!   generated from Rust code with a [`Builder`].
+//! - [`circuit`] places components and wires on a grid as a [`Circuit`], which generates a graph.
+//!   Circuits can be embedded in other circuits as an [`Ic`].
+//! - [`script`] parses and runs the LISP dialect of the tests embedded in circuits with a
+//!   [`Runner`].
+//!
+//! [`Graph`]: simulator::Graph
+//! [`Program`]: simulator::Program
+//! [`Builder`]: simulator::Builder
+//! [`State`]: simulator::State
+//! [`Circuit`]: circuit::Circuit
+//! [`Ic`]: circuit::Ic
+//! [`Runner`]: script::Runner
+
+#![feature(binary_heap_into_iter_sorted)]
+#![feature(destructuring_assignment)]
+#![feature(drain_filter)]
+#![feature(iter_advance_by)]
+#![feature(new_uninit)]
+#![feature(map_try_insert)]
+#![cfg_attr(test, feature(test))]
+
+mod arena;
+pub mod circuit;
+mod integer_set;
+pub mod script;
+pub mod simulator;
+
+#[macro_export]
+macro_rules! impl_dyn {
+	{
+		$(#[$outer:meta])*
+		$trait:ident for $ty:ty {
+			$(ref $fn:ident($($args:ident: $aty:ty),*$(,)?) -> $ret:ty;)*
+			$(mut $fn_mut:ident($($args_mut:ident: $aty_mut:ty),*$(,)?) -> $ret_mut:ty;)*
+		}
+	} => {
+		$(#[$outer])*
+		impl $trait for $ty {
+			$(
+				fn $fn(&self $(, $args: $aty)*) -> $ret {
+					(**self).$fn($($args,)*)
+				}
+			)*
+			$(
+				fn $fn_mut(&mut self $(, $args_mut: $aty_mut)*) -> $ret_mut {
+					(**self).$fn_mut($($args_mut,)*)
+				}
+			)*
+		}
+	};
+}
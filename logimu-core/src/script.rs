@@ -634,6 +634,8 @@ pub enum RunError<'a> {
 	UnexpectedArgument,
 	ArithemicError,
 	SymbolNotDefined,
+	FunctionNotDefined(&'a str),
+	FmtError(fmt::Error),
 	AssertionFailed(&'a Arg),
 }
 
@@ -663,6 +665,8 @@ impl fmt::Display for RunError<'_> {
 			UnexpectedArgument => "unexpected argument".fmt(f),
 			ArithemicError => "arithemic error".fmt(f),
 			SymbolNotDefined => "symbol not defined".fmt(f),
+			FunctionNotDefined(n) => write!(f, "function '{}' not defined", n),
+			FmtError(e) => write!(f, "failed to format output: {}", e),
 			AssertionFailed(a) => write!(f, "assertion failed: `{}`", a),
 		}
 	}
@@ -0,0 +1,393 @@
+use super::{
+	bits_property_up_to, default_bits, parse_bits_up_to, parse_bool, signed_property, word_mask,
+	Component, GenerateIr, InputType, IrOp, OutputType, Property, SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// The maximum width of arithmetic components, which ensures that the full result of any
+/// operation fits in a single word.
+pub const MAX_ARITHMETIC_BITS: u16 = 32;
+
+/// Ops loading the value at `a` into the accumulator, extended to a full word.
+fn load(a: usize, bits: u8, signed: bool) -> [IrOp; 2] {
+	[IrOp::Load { value: 0 }, IrOp::Add { a, bits, signed }]
+}
+
+/// Ops saving bit 0 of the result of `ops` to `out`.
+fn flag(ops: impl IntoIterator<Item = IrOp>, out: usize) -> Vec<IrOp> {
+	let mut ir = Vec::from_iter(ops);
+	ir.extend([IrOp::Andi { i: 1 }, IrOp::Save { out }]);
+	ir
+}
+
+/// Ops saving to `out` whether the result of `ops` doesn't fit in `bits` bits.
+fn overflow(ops: &[IrOp], bits: u8, signed: bool, out: usize) -> Vec<IrOp> {
+	// The result fits if all bits from the sign bit up are equal, or all zero if it is unsigned.
+	let i = if signed { bits - 1 } else { bits };
+	let mask = usize::MAX >> i;
+	let mut ir = ops.to_vec();
+	ir.extend([
+		IrOp::Srli { i },
+		IrOp::Eqi { i: 0, mask },
+		IrOp::Xori { i: 1 },
+		IrOp::Andi { i: 1 },
+		IrOp::Save { out },
+	]);
+	if signed {
+		ir.extend_from_slice(ops);
+		ir.extend([IrOp::Srli { i }, IrOp::Eqi { i: mask, mask }]);
+		ir.push(IrOp::SetIf { out, value: 0, mask: 1 });
+	}
+	ir
+}
+
+macro_rules! arithmetic {
+	(
+		$(#[$attr:meta])*
+		$name:ident,
+		$inputs:expr,
+		$outputs:expr,
+		$generate:expr
+	) => {
+		$(#[$attr])*
+		#[derive(Serialize, Deserialize)]
+		pub struct $name {
+			#[serde(default = "default_bits")]
+			pub bits: NonZeroU16,
+			#[serde(default)]
+			pub signed: bool,
+		}
+
+		impl $name {
+			pub fn new(bits: NonZeroU16) -> Self {
+				Self { bits, signed: false }
+			}
+		}
+
+		impl Component for $name {
+			fn inputs(&self) -> Box<[InputType]> {
+				let one = NonZeroU16::new(1).unwrap();
+				let inputs: &[bool] = &$inputs;
+				let bits = |wide| if wide { self.bits } else { one };
+				inputs.iter().map(|&w| InputType { bits: bits(w) }).collect()
+			}
+
+			fn outputs(&self) -> Box<[OutputType]> {
+				let one = NonZeroU16::new(1).unwrap();
+				let outputs: &[bool] = &$outputs;
+				let bits = |wide| if wide { self.bits } else { one };
+				outputs.iter().map(|&w| OutputType { bits: bits(w) }).collect()
+			}
+
+			fn generate_ir(&self, gen: GenerateIr) -> usize {
+				// Don't drive the outputs at all if either operand is not connected.
+				if gen.inputs[0] == usize::MAX || gen.inputs[1] == usize::MAX {
+					return 0;
+				}
+				let generate: fn(u8, bool, &[usize], &[usize]) -> Vec<IrOp> = $generate;
+				let bits = self.bits.get() as u8;
+				let ir = generate(bits, self.signed, gen.inputs, gen.outputs);
+				if !ir.is_empty() {
+					(gen.out)(ir);
+				}
+				0
+			}
+
+			fn properties(&self) -> Box<[Property]> {
+				let bits = bits_property_up_to(self.bits, MAX_ARITHMETIC_BITS);
+				[bits, signed_property(self.signed)].into()
+			}
+
+			fn set_property(
+				&mut self,
+				name: &str,
+				value: SetProperty,
+			) -> Result<(), Box<dyn Error>> {
+				match name {
+					"bits" => self.bits = parse_bits_up_to(value, MAX_ARITHMETIC_BITS)?,
+					"signed" => self.signed = parse_bool(value)?,
+					_ => Err("invalid property")?,
+				}
+				Ok(())
+			}
+		}
+	};
+}
+
+arithmetic!(
+	/// A component adding two numbers and a carry.
+	///
+	/// The inputs are A, B and carry in. The outputs are the sum, carry out and whether the sum
+	/// overflowed. Carry out is always unsigned, while overflow depends on the mode.
+	Adder,
+	[true, true, false],
+	[true, false, false],
+	|bits, signed, i, o| {
+		let sum = |signed| {
+			let mut ir = Vec::from(load(i[0], bits, signed));
+			ir.push(IrOp::Add { a: i[1], bits, signed });
+			if i[2] != usize::MAX {
+				ir.push(IrOp::Add { a: i[2], bits: 1, signed: false });
+			}
+			ir
+		};
+		let mut ir = Vec::new();
+		if o[0] != usize::MAX {
+			ir.extend(sum(signed));
+			ir.extend([IrOp::Andi { i: word_mask(bits.into(), 0) }, IrOp::Save { out: o[0] }]);
+		}
+		if o[1] != usize::MAX {
+			let mut sum = sum(false);
+			sum.push(IrOp::Srli { i: bits });
+			ir.extend(flag(sum, o[1]));
+		}
+		if o[2] != usize::MAX {
+			ir.extend(overflow(&sum(signed), bits, signed, o[2]));
+		}
+		ir
+	}
+);
+
+arithmetic!(
+	/// A component subtracting a number and a borrow from another number.
+	///
+	/// The inputs are A, B and borrow in. The outputs are the difference, borrow out and whether
+	/// the difference overflowed. Borrow out is always unsigned, while overflow depends on the
+	/// mode.
+	Subtractor,
+	[true, true, false],
+	[true, false, false],
+	|bits, signed, i, o| {
+		let difference = |signed| {
+			let mut ir = Vec::from(load(i[0], bits, signed));
+			ir.push(IrOp::Sub { a: i[1], bits, signed });
+			if i[2] != usize::MAX {
+				ir.push(IrOp::Sub { a: i[2], bits: 1, signed: false });
+			}
+			ir
+		};
+		let mut ir = Vec::new();
+		if o[0] != usize::MAX {
+			ir.extend(difference(signed));
+			ir.extend([IrOp::Andi { i: word_mask(bits.into(), 0) }, IrOp::Save { out: o[0] }]);
+		}
+		if o[1] != usize::MAX {
+			// A negative difference has all bits above the operands set.
+			let mut difference = difference(false);
+			difference.push(IrOp::Srli { i: bits });
+			ir.extend(flag(difference, o[1]));
+		}
+		if o[2] != usize::MAX {
+			ir.extend(overflow(&difference(signed), bits, signed, o[2]));
+		}
+		ir
+	}
+);
+
+arithmetic!(
+	/// A component multiplying two numbers and adding a carry.
+	///
+	/// The inputs are A, B and carry in. The outputs are the low and high half of the product and
+	/// whether the product doesn't fit in the low half.
+	Multiplier,
+	[true, true, true],
+	[true, true, false],
+	|bits, signed, i, o| {
+		let mut product = Vec::from(load(i[0], bits, signed));
+		product.push(IrOp::Mul { a: i[1], bits, signed });
+		if i[2] != usize::MAX {
+			product.push(IrOp::Add { a: i[2], bits, signed });
+		}
+		let mask = word_mask(bits.into(), 0);
+		let mut ir = Vec::new();
+		if o[0] != usize::MAX {
+			ir.extend_from_slice(&product);
+			ir.extend([IrOp::Andi { i: mask }, IrOp::Save { out: o[0] }]);
+		}
+		if o[1] != usize::MAX {
+			ir.extend_from_slice(&product);
+			ir.extend([IrOp::Srli { i: bits }, IrOp::Andi { i: mask }]);
+			ir.push(IrOp::Save { out: o[1] });
+		}
+		if o[2] != usize::MAX {
+			ir.extend(overflow(&product, bits, signed, o[2]));
+		}
+		ir
+	}
+);
+
+arithmetic!(
+	/// A component dividing two numbers, rounding towards zero.
+	///
+	/// The inputs are the dividend and the divisor. The outputs are the quotient, the remainder
+	/// and whether the divisor is zero or the quotient overflowed. The remainder has the sign of
+	/// the dividend. Both are shorted if the divisor is zero.
+	Divider,
+	[true, true],
+	[true, true, false],
+	|bits, signed, i, o| {
+		let (a, b) = (i[0], i[1]);
+		let mask = word_mask(bits.into(), 0);
+		let mut quotient = Vec::from(load(a, bits, signed));
+		quotient.push(IrOp::Div { a: b, bits, signed });
+		let mut ir = Vec::new();
+		if o[0] != usize::MAX {
+			ir.extend_from_slice(&quotient);
+			ir.extend([IrOp::Andi { i: mask }, IrOp::Save { out: o[0] }]);
+		}
+		if o[1] != usize::MAX {
+			ir.extend(load(a, bits, signed));
+			ir.extend([IrOp::Rem { a: b, bits, signed }, IrOp::Andi { i: mask }]);
+			ir.push(IrOp::Save { out: o[1] });
+		}
+		if o[2] != usize::MAX {
+			ir.extend(overflow(&quotient, bits, signed, o[2]));
+			ir.extend([IrOp::Copy { a: b }, IrOp::Eqi { i: 0, mask }]);
+			ir.push(IrOp::SetIf { out: o[2], value: 1, mask: 1 });
+		}
+		ir
+	}
+);
+
+arithmetic!(
+	/// A component comparing two numbers.
+	///
+	/// The inputs are A and B. The outputs are whether A is less than, equal to or greater than
+	/// B.
+	Comparator,
+	[true, true],
+	[false, false, false],
+	|bits, signed, i, o| {
+		let (a, b) = (i[0], i[1]);
+		let mut ir = Vec::new();
+		if o[0] != usize::MAX {
+			let lt = IrOp::Lt { a: b, bits, signed };
+			ir.extend(flag(load(a, bits, signed).into_iter().chain([lt]), o[0]));
+		}
+		if o[1] != usize::MAX {
+			let eq = IrOp::Eq { a: b, bits };
+			ir.extend(flag(load(a, bits, false).into_iter().chain([eq]), o[1]));
+		}
+		if o[2] != usize::MAX {
+			let gt = IrOp::Lt { a, bits, signed };
+			ir.extend(flag(load(b, bits, signed).into_iter().chain([gt]), o[2]));
+		}
+		ir
+	}
+);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	fn nz(n: u16) -> NonZeroU16 {
+		NonZeroU16::new(n).unwrap()
+	}
+
+	fn state(component: impl Component + 'static) -> State {
+		let mut b = Builder::new();
+		let inputs = component
+			.inputs()
+			.iter()
+			.enumerate()
+			.map(|(i, t)| b.input(&i.to_string(), t.bits.get()))
+			.collect::<Vec<_>>();
+		let outputs = b.add(component, &inputs);
+		for (i, &o) in outputs.iter().enumerate() {
+			b.output(&i.to_string(), o);
+		}
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	fn run<const N: usize>(state: &mut State, inputs: &[usize]) -> [Value; N] {
+		let inputs = inputs.iter().map(|&v| Value::Set(v)).collect::<Vec<_>>();
+		state.write_inputs(&inputs);
+		assert!(state.run(64).is_settled());
+		let mut out = [Value::Floating; N];
+		state.read_outputs(&mut out);
+		out
+	}
+
+	fn set<const N: usize>(values: [usize; N]) -> [Value; N] {
+		values.map(Value::Set)
+	}
+
+	#[test]
+	fn adder() {
+		let mut s = state(Adder::new(nz(8)));
+		assert_eq!(run(&mut s, &[100, 27, 1]), set([128, 0, 0]));
+		assert_eq!(run(&mut s, &[200, 55, 1]), set([0, 1, 1]));
+		let mut s = state(Adder { bits: nz(8), signed: true });
+		assert_eq!(run(&mut s, &[100, 27, 1]), set([128, 0, 1]));
+		assert_eq!(run(&mut s, &[0xff, 0x80, 0]), set([0x7f, 1, 1]));
+		assert_eq!(run(&mut s, &[0xff, 0x81, 0]), set([0x80, 1, 0]));
+		assert_eq!(run(&mut s, &[0xff, 0x01, 0]), set([0, 1, 0]));
+	}
+
+	#[test]
+	fn adder_32_bits() {
+		let mut s = state(Adder::new(nz(32)));
+		assert_eq!(run(&mut s, &[u32::MAX as usize, 0, 1]), set([0, 1, 1]));
+		assert_eq!(
+			run(&mut s, &[u32::MAX as usize, 0, 0]),
+			set([u32::MAX as usize, 0, 0])
+		);
+	}
+
+	#[test]
+	fn subtractor() {
+		let mut s = state(Subtractor::new(nz(8)));
+		assert_eq!(run(&mut s, &[5, 3, 1]), set([1, 0, 0]));
+		assert_eq!(run(&mut s, &[3, 3, 1]), set([0xff, 1, 1]));
+		let mut s = state(Subtractor { bits: nz(8), signed: true });
+		assert_eq!(run(&mut s, &[3, 3, 1]), set([0xff, 1, 0]));
+		assert_eq!(run(&mut s, &[0x80, 1, 0]), set([0x7f, 0, 1]));
+		assert_eq!(run(&mut s, &[0x7f, 0xff, 0]), set([0x80, 1, 1]));
+	}
+
+	#[test]
+	fn multiplier() {
+		let mut s = state(Multiplier::new(nz(8)));
+		assert_eq!(run(&mut s, &[12, 10, 7]), set([127, 0, 0]));
+		assert_eq!(run(&mut s, &[0xff, 0xff, 0xff]), set([0, 0xff, 1]));
+		let mut s = state(Multiplier { bits: nz(8), signed: true });
+		assert_eq!(run(&mut s, &[0xff, 0xff, 0]), set([1, 0, 0]));
+		assert_eq!(run(&mut s, &[0xfe, 0x40, 0]), set([0x80, 0xff, 0]));
+		assert_eq!(run(&mut s, &[0x80, 0xff, 0]), set([0x80, 0, 1]));
+	}
+
+	#[test]
+	fn divider() {
+		let mut s = state(Divider::new(nz(8)));
+		assert_eq!(run(&mut s, &[200, 7]), set([28, 4, 0]));
+		let [q, r, o] = run(&mut s, &[200, 0]);
+		assert_eq!((q, r, o), (Value::Short, Value::Short, Value::Set(1)));
+		let mut s = state(Divider { bits: nz(8), signed: true });
+		assert_eq!(run(&mut s, &[0xf9, 2]), set([0xfd, 0xff, 0]));
+		assert_eq!(run(&mut s, &[0x80, 0xff]), set([0x80, 0, 1]));
+	}
+
+	#[test]
+	fn comparator() {
+		let mut s = state(Comparator::new(nz(8)));
+		assert_eq!(run(&mut s, &[1, 0xff]), set([1, 0, 0]));
+		assert_eq!(run(&mut s, &[0xff, 0xff]), set([0, 1, 0]));
+		let mut s = state(Comparator { bits: nz(8), signed: true });
+		assert_eq!(run(&mut s, &[1, 0xff]), set([0, 0, 1]));
+		assert_eq!(run(&mut s, &[0xff, 0xff]), set([0, 1, 0]));
+	}
+
+	#[test]
+	fn undefined() {
+		let mut s = state(Adder::new(nz(8)));
+		s.write_inputs(&[Value::Floating, Value::Set(1), Value::Set(0)]);
+		assert!(s.run(64).is_settled());
+		let mut out = [Value::Floating; 3];
+		s.read_outputs(&mut out);
+		assert_eq!(out, [Value::Short; 3]);
+	}
+}
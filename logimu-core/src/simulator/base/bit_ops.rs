@@ -0,0 +1,305 @@
+use super::{
+	bits_property, bits_property_up_to, default_bits, parse_bits, parse_bits_up_to, parse_bool,
+	signed_property, word_mask, words, Component, GenerateIr, InputType, IrOp, OutputType,
+	Property, PropertyValue, SetProperty, MAX_BITS, WORD_BITS,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// The amount of bits needed to hold a count of up to `bits`.
+fn count_bits(bits: NonZeroU16) -> NonZeroU16 {
+	NonZeroU16::new((u16::BITS - bits.get().leading_zeros()) as u16).unwrap()
+}
+
+/// A component reversing the order of the bits of its input.
+#[derive(Serialize, Deserialize)]
+pub struct BitReverse {
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+}
+
+impl BitReverse {
+	pub fn new(bits: NonZeroU16) -> Self {
+		Self { bits }
+	}
+}
+
+impl Component for BitReverse {
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: self.bits }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (input, output) = (gen.inputs[0], gen.outputs[0]);
+		if input == usize::MAX || output == usize::MAX {
+			return 0;
+		}
+		let (n, word) = (usize::from(self.bits.get()), usize::from(WORD_BITS));
+		// Use a separate node for each output word since B can't be cleared.
+		for k in 0..words(self.bits.get()) {
+			let mut ir = Vec::new();
+			for i in k * word..n.min((k + 1) * word) {
+				let s = n - 1 - i;
+				let (from, to) = ((s % word) as u8, (i % word) as u8);
+				ir.push(IrOp::Copy { a: input + s / word });
+				if from > to {
+					ir.push(IrOp::Srli { i: from - to });
+				} else if from < to {
+					ir.push(IrOp::Slli { i: to - from });
+				}
+				ir.extend([IrOp::Andi { i: 1 << to }, IrOp::OrB]);
+			}
+			ir.push(IrOp::SaveB { out: output + k });
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[bits_property(self.bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+macro_rules! count {
+	($(#[$attr:meta])* $name:ident, $op:ident) => {
+		$(#[$attr])*
+		#[derive(Serialize, Deserialize)]
+		pub struct $name {
+			pub bits: NonZeroU16,
+		}
+
+		impl $name {
+			pub fn new(bits: NonZeroU16) -> Self {
+				Self { bits }
+			}
+		}
+
+		impl Component for $name {
+			fn inputs(&self) -> Box<[InputType]> {
+				[InputType { bits: self.bits }].into()
+			}
+
+			fn outputs(&self) -> Box<[OutputType]> {
+				[OutputType { bits: count_bits(self.bits) }].into()
+			}
+
+			fn generate_ir(&self, gen: GenerateIr) -> usize {
+				let (a, out) = (gen.inputs[0], gen.outputs[0]);
+				if a == usize::MAX || out == usize::MAX {
+					return 0;
+				}
+				let i = self.bits.get() as u8;
+				let mask = word_mask(count_bits(self.bits).get(), 0);
+				(gen.out)(vec![
+					IrOp::Copy { a },
+					IrOp::$op { i },
+					IrOp::Andi { i: mask },
+					IrOp::Save { out },
+				]);
+				0
+			}
+
+			fn properties(&self) -> Box<[Property]> {
+				[bits_property_up_to(self.bits, WORD_BITS)].into()
+			}
+
+			fn set_property(
+				&mut self,
+				name: &str,
+				value: SetProperty,
+			) -> Result<(), Box<dyn Error>> {
+				match name {
+					"bits" => self.bits = parse_bits_up_to(value, WORD_BITS)?,
+					_ => Err("invalid property")?,
+				}
+				Ok(())
+			}
+		}
+	};
+}
+
+count!(
+	/// A component counting the ones in its input. The output is shorted if the input is not
+	/// fully defined.
+	PopCount,
+	Popcnt
+);
+
+count!(
+	/// A component counting the zeros above the highest one in its input. The output is shorted
+	/// if the input is not fully defined.
+	LeadingZeros,
+	Clz
+);
+
+/// A component extending or truncating its input to a different width.
+///
+/// The added bits are copies of the highest bit of the input if it is signed, otherwise they
+/// are low.
+#[derive(Serialize, Deserialize)]
+pub struct Extender {
+	pub in_bits: NonZeroU16,
+	pub out_bits: NonZeroU16,
+	#[serde(default)]
+	pub signed: bool,
+}
+
+impl Extender {
+	pub fn new(in_bits: NonZeroU16, out_bits: NonZeroU16) -> Self {
+		Self { in_bits, out_bits, signed: false }
+	}
+}
+
+impl Component for Extender {
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: self.in_bits }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.out_bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (input, output) = (gen.inputs[0], gen.outputs[0]);
+		if input == usize::MAX || output == usize::MAX {
+			return 0;
+		}
+		let (i, o) = (self.in_bits.get(), self.out_bits.get());
+		let sign = usize::from(i - 1);
+		let word = usize::from(WORD_BITS);
+		// Use a separate node for each output word since B can't be cleared.
+		for k in 0..words(o) {
+			let mut ir = Vec::new();
+			let data = word_mask(i.min(o), k);
+			if data != 0 {
+				ir.extend([
+					IrOp::Copy { a: input + k },
+					IrOp::Andi { i: data },
+					IrOp::OrB,
+				]);
+			}
+			let extend = word_mask(o, k) & !word_mask(i, k);
+			if extend != 0 {
+				if self.signed {
+					// Move the sign to the top of the word and copy it to all other bits.
+					let up = (word - 1 - sign % word) as u8;
+					ir.extend([IrOp::Copy { a: input + sign / word }, IrOp::Slli { i: up }]);
+					ir.push(IrOp::Srai { i: WORD_BITS as u8 - 1 });
+				} else {
+					ir.push(IrOp::Load { value: 0 });
+				}
+				ir.extend([IrOp::Andi { i: extend }, IrOp::OrB]);
+			}
+			ir.push(IrOp::SaveB { out: output + k });
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let range = 1..=MAX_BITS.into();
+		let bits = |value: NonZeroU16| PropertyValue::Int {
+			value: value.get().into(),
+			range: range.clone(),
+		};
+		[
+			Property::new("in bits", bits(self.in_bits)),
+			Property::new("out bits", bits(self.out_bits)),
+			signed_property(self.signed),
+		]
+		.into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"in bits" => self.in_bits = parse_bits(value)?,
+			"out bits" => self.out_bits = parse_bits(value)?,
+			"signed" => self.signed = parse_bool(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	fn nz(n: u16) -> NonZeroU16 {
+		NonZeroU16::new(n).unwrap()
+	}
+
+	fn run(component: impl Component + 'static, input: Value) -> Value {
+		let mut b = Builder::new();
+		let a = b.input("A", component.inputs()[0].bits.get());
+		let out = b.add(component, &[a])[0];
+		b.output("Q", out);
+		let mut s = Arc::new(b.generate_ir()).new_state();
+		s.write_inputs(&[input]);
+		assert!(s.run(64).is_settled());
+		let mut out = [Value::Floating];
+		s.read_outputs(&mut out);
+		out[0]
+	}
+
+	#[test]
+	fn bit_reverse() {
+		assert_eq!(
+			run(BitReverse::new(nz(8)), Value::Set(0x96)),
+			Value::Set(0x69)
+		);
+		assert_eq!(
+			run(BitReverse::new(nz(6)), Value::Set(0x03)),
+			Value::Set(0x30)
+		);
+		let wide = Value::from_words([Value::Set(0x3), Value::Set(0x20)]);
+		let expect = Value::from_words([Value::Set(1), Value::Set(0x30)]);
+		assert_eq!(run(BitReverse::new(nz(70)), wide), expect);
+	}
+
+	#[test]
+	fn count() {
+		assert_eq!(run(PopCount::new(nz(8)), Value::Set(0x96)), Value::Set(4));
+		assert_eq!(
+			run(PopCount::new(nz(64)), Value::Set(usize::MAX)),
+			Value::Set(64)
+		);
+		assert_eq!(
+			run(LeadingZeros::new(nz(8)), Value::Set(0x16)),
+			Value::Set(3)
+		);
+		assert_eq!(run(LeadingZeros::new(nz(8)), Value::Set(0)), Value::Set(8));
+		let undefined = Value::Mixed { value: 0x80, floating: 0x01, short: 0 };
+		assert_eq!(run(LeadingZeros::new(nz(8)), undefined), Value::Short);
+	}
+
+	#[test]
+	fn extender() {
+		let mut e = Extender::new(nz(8), nz(70));
+		let expect = Value::from_words([Value::Set(0x80), Value::Set(0)]);
+		assert_eq!(run(e, Value::Set(0x80)), expect);
+		e = Extender { in_bits: nz(8), out_bits: nz(70), signed: true };
+		let expect = Value::from_words([Value::Set(!0x7f), Value::Set(0x3f)]);
+		assert_eq!(run(e, Value::Set(0x80)), expect);
+		e = Extender { in_bits: nz(8), out_bits: nz(12), signed: true };
+		assert_eq!(run(e, Value::Set(0x7f)), Value::Set(0x7f));
+		e = Extender { in_bits: nz(70), out_bits: nz(4), signed: true };
+		let wide = Value::from_words([Value::Set(0x1234), Value::Set(0x20)]);
+		assert_eq!(run(e, wide), Value::Set(0x4));
+	}
+}
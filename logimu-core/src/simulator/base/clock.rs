@@ -0,0 +1,95 @@
+use super::{Component, GenerateIr, InputType, OutputType, Property, PropertyValue, SetProperty};
+use core::num::{NonZeroU16, NonZeroU32};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A component outputting a periodic signal. The output is low for `low` ticks, then high for
+/// `high` ticks.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Clock {
+	pub high: NonZeroU32,
+	pub low: NonZeroU32,
+}
+
+impl Clock {
+	pub fn new(high: NonZeroU32, low: NonZeroU32) -> Self {
+		Self { high, low }
+	}
+}
+
+impl Component for Clock {
+	fn inputs(&self) -> Box<[InputType]> {
+		[].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: NonZeroU16::new(1).unwrap() }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		if gen.outputs[0] != usize::MAX {
+			(gen.clock)(gen.outputs[0], self.low, self.high);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let range = 1..=u32::MAX.into();
+		let high = PropertyValue::Int { value: self.high.get().into(), range: range.clone() };
+		let low = PropertyValue::Int { value: self.low.get().into(), range };
+		[Property::new("high", high), Property::new("low", low)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		let v = value.as_int().ok_or("expected integer")?;
+		let v = v.try_into().ok().and_then(NonZeroU32::new);
+		let v = v.ok_or("integer out of range")?;
+		match name {
+			"high" => self.high = v,
+			"low" => self.low = v,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use std::sync::Arc;
+
+	#[test]
+	fn period() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+		let (two, three) = (NonZeroU32::new(2).unwrap(), NonZeroU32::new(3).unwrap());
+		let clock = graph.add(Box::new(Clock::new(two, three)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let out = graph.add(Box::new(Out::new("", NonZeroU16::new(1).unwrap(), 0)), ());
+		let [a, b] = [(); 2].map(|_| graph.new_nexus(()));
+		graph
+			.connect(Port::Output { node: clock, port: 0 }, Some(a))
+			.unwrap();
+		graph
+			.connect(Port::Input { node: not, port: 0 }, Some(a))
+			.unwrap();
+		graph
+			.connect(Port::Output { node: not, port: 0 }, Some(b))
+			.unwrap();
+		graph
+			.connect(Port::Input { node: out, port: 0 }, Some(b))
+			.unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let mut trace = Vec::new();
+		for _ in 0..12 {
+			state.step();
+			let mut o = [ir::Value::Floating];
+			state.read_outputs(&mut o);
+			trace.push(o[0]);
+		}
+		assert_eq!(state.ticks(), 12);
+		let expected = [1, 1, 1, 0, 0, 1, 1, 1, 0, 0, 1, 1].map(ir::Value::Set);
+		assert_eq!(trace, expected);
+	}
+}
@@ -0,0 +1,64 @@
+use super::{
+	bits_property, mask_word, parse_bits, words, Component, GenerateIr, InputType, IrOp,
+	OutputType, Property, PropertyValue, SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A component outputting a fixed value. Values wider than a word are sign-extended.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Constant {
+	pub value: usize,
+	pub bits: NonZeroU16,
+}
+
+impl Constant {
+	pub fn new(bits: NonZeroU16, value: usize) -> Self {
+		Self { bits, value }
+	}
+}
+
+impl Component for Constant {
+	fn inputs(&self) -> Box<[InputType]> {
+		[].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		if gen.outputs[0] != usize::MAX {
+			let sign = ((self.value as isize) >> (usize::BITS - 1)) as usize;
+			let ir = (0..words(self.bits.get()))
+				.flat_map(|k| {
+					let value = if k == 0 { self.value } else { sign };
+					[IrOp::Load { value }]
+						.into_iter()
+						.chain(mask_word(self.bits, k))
+						.chain(Some(IrOp::Save { out: gen.outputs[0] + k }))
+				})
+				.collect();
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let bits = self.bits.get().min(64);
+		let clamp = |v: i128| v.clamp(i64::MIN.into(), i64::MAX.into()) as i64;
+		let range = clamp(-(1 << bits) >> 1)..=clamp((1 << bits) - 1);
+		let value = PropertyValue::Int { value: self.value as i64, range };
+		[bits_property(self.bits), Property::new("value", value)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			"value" => self.value = value.as_int().ok_or("expected integer")? as usize,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,268 @@
+use super::{
+	edge_property, parse_bool, Component, GenerateIr, InputType, IrOp, OutputType, Property,
+	SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Ops computing a condition in bit 0 of the accumulator and the op updating the state if the
+/// condition holds on an edge of the clock.
+type Update = (Vec<IrOp>, IrOp);
+
+/// Generate IR for a flip-flop with `data` inputs followed by the clock, set and reset inputs.
+/// The outputs are the state and its inverse.
+///
+/// `updates` gets the data inputs and the state. Later updates override earlier ones. The state
+/// only changes on the clock if all inputs are connected. Set and reset are asynchronous, with
+/// reset taking precedence.
+fn generate(
+	gen: GenerateIr,
+	falling: bool,
+	data: usize,
+	updates: fn(&[usize], usize) -> Vec<Update>,
+) -> usize {
+	let (clock, set, reset) = (gen.inputs[data], gen.inputs[data + 1], gen.inputs[data + 2]);
+	let (q, nq) = (gen.outputs[0], gen.outputs[1]);
+	// The state is followed by the previous value of the clock.
+	let (state, prev) = (gen.memory_size, gen.memory_size + 1);
+
+	let mut ir = Vec::new();
+	let inputs = &gen.inputs[..data];
+	if clock != usize::MAX && inputs.iter().all(|&a| a != usize::MAX) {
+		for (cond, update) in updates(inputs, state) {
+			ir.extend(cond);
+			ir.extend([IrOp::Edge { a: clock, prev, falling }, update]);
+		}
+	}
+	for (a, value) in [(set, 1), (reset, 0)] {
+		if a != usize::MAX {
+			ir.extend([IrOp::Copy { a }, IrOp::SetIf { out: state, value, mask: 1 }]);
+		}
+	}
+	if !ir.is_empty() {
+		// Run again after the state changed to output it.
+		ir.push(IrOp::CheckDirty { a: state, node: gen.nodes, mask: 1 });
+	}
+	if q != usize::MAX || nq != usize::MAX {
+		ir.extend([IrOp::Copy { a: state }, IrOp::Andi { i: 1 }]);
+		if q != usize::MAX {
+			ir.push(IrOp::Save { out: q });
+		}
+		if nq != usize::MAX {
+			ir.extend([IrOp::Xori { i: 1 }, IrOp::Save { out: nq }]);
+		}
+	}
+	if !ir.is_empty() {
+		(gen.out)(ir);
+	}
+	2
+}
+
+macro_rules! flip_flop {
+	($(#[$attr:meta])* $name:ident, $data:literal, $updates:expr) => {
+		$(#[$attr])*
+		#[derive(Default, Serialize, Deserialize)]
+		pub struct $name {
+			/// Whether the state changes on the falling instead of the rising edge of the clock.
+			#[serde(default)]
+			pub falling: bool,
+		}
+
+		impl $name {
+			pub fn new() -> Self {
+				Self::default()
+			}
+		}
+
+		impl Component for $name {
+			fn inputs(&self) -> Box<[InputType]> {
+				let bits = NonZeroU16::new(1).unwrap();
+				(0..$data + 3).map(|_| InputType { bits }).collect()
+			}
+
+			fn outputs(&self) -> Box<[OutputType]> {
+				let bits = NonZeroU16::new(1).unwrap();
+				[OutputType { bits }, OutputType { bits }].into()
+			}
+
+			fn generate_ir(&self, gen: GenerateIr) -> usize {
+				generate(gen, self.falling, $data, $updates)
+			}
+
+			fn properties(&self) -> Box<[Property]> {
+				[edge_property(self.falling)].into()
+			}
+
+			fn set_property(
+				&mut self,
+				name: &str,
+				value: SetProperty,
+			) -> Result<(), Box<dyn Error>> {
+				match name {
+					"falling edge" => self.falling = parse_bool(value)?,
+					_ => Err("invalid property")?,
+				}
+				Ok(())
+			}
+		}
+	};
+}
+
+flip_flop!(
+	/// A flip-flop storing its input on an edge of the clock.
+	///
+	/// The inputs are D, the clock, set and reset.
+	DFlipFlop,
+	1,
+	|i, state| vec![(vec![IrOp::Load { value: 1 }], IrOp::SaveIf { a: i[0], out: state })]
+);
+
+flip_flop!(
+	/// A flip-flop inverting its state on an edge of the clock if T is high.
+	///
+	/// The inputs are T, the clock, set and reset.
+	TFlipFlop,
+	1,
+	|i, state| {
+		vec![
+			(vec![IrOp::Copy { a: i[0] }], IrOp::SetIf { out: state, value: 1, mask: 1 }),
+			(
+				vec![IrOp::Copy { a: i[0] }, IrOp::And { a: state }],
+				IrOp::SetIf { out: state, value: 0, mask: 1 },
+			),
+		]
+	}
+);
+
+flip_flop!(
+	/// A flip-flop that is set on an edge of the clock if J is high, cleared if K is high and
+	/// inverted if both are high.
+	///
+	/// The inputs are J, K, the clock, set and reset.
+	JkFlipFlop,
+	2,
+	|i, state| {
+		let (j, k) = (i[0], i[1]);
+		vec![
+			(vec![IrOp::Copy { a: j }], IrOp::SetIf { out: state, value: 1, mask: 1 }),
+			(
+				vec![IrOp::Copy { a: k }, IrOp::And { a: state }],
+				IrOp::SetIf { out: state, value: 0, mask: 1 },
+			),
+			(
+				vec![IrOp::Copy { a: j }, IrOp::Xori { i: 1 }, IrOp::And { a: k }],
+				IrOp::SetIf { out: state, value: 0, mask: 1 },
+			),
+		]
+	}
+);
+
+flip_flop!(
+	/// A flip-flop that is set on an edge of the clock if S is high and cleared if R is high.
+	/// If both are high it is cleared.
+	///
+	/// The inputs are S, R, the clock, set and reset.
+	SrFlipFlop,
+	2,
+	|i, state| {
+		vec![
+			(vec![IrOp::Copy { a: i[0] }], IrOp::SetIf { out: state, value: 1, mask: 1 }),
+			(vec![IrOp::Copy { a: i[1] }], IrOp::SetIf { out: state, value: 0, mask: 1 }),
+		]
+	}
+);
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	/// A flip-flop with all inputs connected to external inputs, in order. Outputs Q and Q̄.
+	fn flip_flop(component: impl Component + 'static, data: usize) -> State {
+		let mut b = Builder::new();
+		let inputs = (0..data + 3)
+			.map(|i| b.input(&i.to_string(), 1))
+			.collect::<Vec<_>>();
+		let out = b.add(component, &inputs);
+		b.output("Q", out[0]);
+		b.output("NQ", out[1]);
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	/// Write the data inputs, pulse the clock and return Q and Q̄.
+	fn pulse(state: &mut State, data: &[usize]) -> [Value; 2] {
+		let mut inputs = data.iter().map(|&v| Value::Set(v)).collect::<Vec<_>>();
+		inputs.extend([Value::Set(0); 3]);
+		let clock = data.len();
+		for c in [0, 1, 0] {
+			inputs[clock] = Value::Set(c);
+			state.write_inputs(&inputs);
+			assert!(state.run(64).is_settled());
+		}
+		let mut out = [Value::Floating; 2];
+		state.read_outputs(&mut out);
+		out
+	}
+
+	const LOW: [Value; 2] = [Value::Set(0), Value::Set(1)];
+	const HIGH: [Value; 2] = [Value::Set(1), Value::Set(0)];
+
+	#[test]
+	fn d() {
+		let mut s = flip_flop(DFlipFlop::new(), 1);
+		assert_eq!(pulse(&mut s, &[1]), HIGH);
+		assert_eq!(pulse(&mut s, &[0]), LOW);
+		assert_eq!(pulse(&mut s, &[1]), HIGH);
+	}
+
+	#[test]
+	fn t() {
+		let mut s = flip_flop(TFlipFlop::new(), 1);
+		assert_eq!(pulse(&mut s, &[1]), HIGH);
+		assert_eq!(pulse(&mut s, &[0]), HIGH);
+		assert_eq!(pulse(&mut s, &[1]), LOW);
+		assert_eq!(pulse(&mut s, &[1]), HIGH);
+	}
+
+	#[test]
+	fn jk() {
+		let mut s = flip_flop(JkFlipFlop::new(), 2);
+		assert_eq!(pulse(&mut s, &[0, 1]), LOW);
+		assert_eq!(pulse(&mut s, &[0, 0]), LOW);
+		assert_eq!(pulse(&mut s, &[1, 0]), HIGH);
+		assert_eq!(pulse(&mut s, &[1, 1]), LOW);
+		assert_eq!(pulse(&mut s, &[1, 1]), HIGH);
+		assert_eq!(pulse(&mut s, &[0, 1]), LOW);
+	}
+
+	#[test]
+	fn sr() {
+		let mut s = flip_flop(SrFlipFlop::new(), 2);
+		assert_eq!(pulse(&mut s, &[1, 0]), HIGH);
+		assert_eq!(pulse(&mut s, &[0, 0]), HIGH);
+		assert_eq!(pulse(&mut s, &[0, 1]), LOW);
+		assert_eq!(pulse(&mut s, &[1, 1]), LOW);
+	}
+
+	/// The state only changes on the selected edge, while set and reset apply immediately.
+	#[test]
+	fn falling_edge_and_async() {
+		let mut s = flip_flop(DFlipFlop { falling: true }, 1);
+		let mut out = [Value::Floating; 2];
+		let mut write = |s: &mut State, inputs: [usize; 4]| {
+			s.write_inputs(&inputs.map(Value::Set));
+			assert!(s.run(64).is_settled());
+			s.read_outputs(&mut out);
+			out
+		};
+		assert_eq!(write(&mut s, [1, 0, 0, 1]), LOW);
+		assert_eq!(write(&mut s, [1, 1, 0, 0]), LOW);
+		assert_eq!(write(&mut s, [1, 0, 0, 0]), HIGH);
+		assert_eq!(write(&mut s, [1, 0, 0, 1]), LOW);
+		assert_eq!(write(&mut s, [0, 0, 1, 0]), HIGH);
+		// Reset takes precedence over set.
+		assert_eq!(write(&mut s, [0, 0, 1, 1]), LOW);
+	}
+}
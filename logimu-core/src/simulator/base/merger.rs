@@ -0,0 +1,119 @@
+use super::{
+	bits_property, parse_bits, word_mask, words, BitRange, Component, GenerateIr, InputType, IrOp,
+	OutputType, Property, PropertyValue, SetProperty, MAX_BITS, WORD_BITS,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Serialize, Deserialize)]
+pub struct Merger {
+	pub bits: NonZeroU16,
+	/// Range of output bits of each input.
+	pub inputs: Vec<BitRange>,
+}
+
+impl Merger {
+	pub fn new(bits: NonZeroU16) -> Self {
+		let one = NonZeroU16::new(1).unwrap();
+		Self {
+			bits,
+			inputs: (0..bits.get()).map(|i| BitRange::new(i, one)).collect(),
+		}
+	}
+}
+
+impl Component for Merger {
+	fn inputs(&self) -> Box<[InputType]> {
+		self.inputs
+			.iter()
+			.map(|e| InputType { bits: e.bits })
+			.collect()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		assert_eq!(gen.outputs.len(), 1, "expected only one output");
+		let output = gen.outputs[0];
+		if output == usize::MAX {
+			return 0;
+		}
+		// B can't be cleared, so each output word is done by a separate node.
+		for k in 0..words(self.bits.get()) {
+			let mut ir = Vec::new();
+			for (&a, r) in gen.inputs.iter().zip(self.inputs.iter()) {
+				if a == usize::MAX {
+					continue;
+				}
+				// Each input word ends up in at most two output words.
+				let (d, s) = (
+					usize::from(r.start / WORD_BITS),
+					(r.start % WORD_BITS) as u8,
+				);
+				let mut push = |q: usize, shift: Option<IrOp>| {
+					if q < words(r.bits.get()) {
+						ir.push(IrOp::Copy { a: a + q });
+						ir.push(IrOp::Andi { i: word_mask(r.bits.get(), q) });
+						ir.extend(shift);
+						ir.push(IrOp::OrB);
+					}
+				};
+				if let Some(q) = k.checked_sub(d) {
+					push(q, (s > 0).then(|| IrOp::Slli { i: s }));
+				}
+				if let Some(q) = k.checked_sub(d + 1).filter(|_| s > 0) {
+					push(q, Some(IrOp::Srli { i: WORD_BITS as u8 - s }));
+				}
+			}
+			ir.push(IrOp::SaveB { out: output + k });
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let inputs = PropertyValue::Int {
+			value: self.inputs.len().try_into().unwrap(),
+			range: 1..=MAX_BITS.into(),
+		};
+		let mut v = Vec::from([bits_property(self.bits), Property::new("inputs", inputs)]);
+		for (i, o) in self.inputs.iter().enumerate() {
+			let range = PropertyValue::Range { value: *o };
+			v.push(Property::new(format!("input {}", i), range));
+		}
+		v.into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			"inputs" => {
+				let v = value.as_int().ok_or("expected integer")?;
+				let v: u16 = v.try_into().map_err(|_| "expected positive integer")?;
+				if v > MAX_BITS {
+					Err("integer out of range")?;
+				}
+				let mut i = self.inputs.len();
+				self.inputs.resize_with(v.into(), || {
+					let n = BitRange::new(i as u16, NonZeroU16::new(1).unwrap());
+					i += 1;
+					n
+				});
+			}
+			name if name.starts_with("input ") => {
+				let (_, n) = name.split_once(' ').ok_or("invalid property")?;
+				let n = n.parse::<usize>().map_err(|_| "invalid property")?;
+				let r = self.inputs.get_mut(n).ok_or("invalid property")?;
+				let v = value.as_range().ok_or("expected range")?;
+				(v.end() <= MAX_BITS)
+					.then(|| *r = v)
+					.ok_or("range out of bounds")?;
+			}
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
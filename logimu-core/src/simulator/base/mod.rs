@@ -0,0 +1,423 @@
+mod arithmetic;
+mod bit_ops;
+mod clock;
+mod constant;
+mod flip_flop;
+mod merger;
+mod plexers;
+mod ram;
+mod register;
+mod rom;
+mod shifter;
+mod splitter;
+mod tri_state;
+
+pub use arithmetic::*;
+pub use bit_ops::*;
+pub use clock::*;
+pub use constant::*;
+pub use flip_flop::*;
+pub use merger::*;
+pub use plexers::*;
+pub use ram::*;
+pub use register::*;
+pub use rom::*;
+pub use shifter::*;
+pub use splitter::*;
+pub use tri_state::*;
+
+use super::ir::{word_mask, words, MAX_BITS, MAX_WORDS, WORD_BITS};
+use super::{
+	BitRange, Component, GenerateIr, InputType, IrOp, OutputType, Property, PropertyValue,
+	SetProperty,
+};
+use core::fmt;
+use core::num::{NonZeroU16, NonZeroU8};
+use serde::de;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::error::Error;
+
+/// A u8 that is larger than 2.
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct NonZeroOneU8(NonZeroU8);
+
+impl NonZeroOneU8 {
+	pub fn new(n: u8) -> Option<Self> {
+		(n >= 2).then(|| Self(NonZeroU8::new(n).unwrap()))
+	}
+
+	pub fn get(&self) -> u8 {
+		self.0.get()
+	}
+}
+
+impl Serialize for NonZeroOneU8 {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_u8(self.get())
+	}
+}
+
+impl<'a> Deserialize<'a> for NonZeroOneU8 {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'a>,
+	{
+		struct V;
+
+		impl<'b> de::Visitor<'b> for V {
+			type Value = NonZeroOneU8;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				formatter.write_str("out of range")
+			}
+
+			fn visit_u8<E>(self, value: u8) -> Result<Self::Value, E>
+			where
+				E: de::Error,
+			{
+				NonZeroOneU8::new(value).ok_or(E::invalid_value(
+					de::Unexpected::Unsigned(value.into()),
+					&self,
+				))
+			}
+		}
+
+		deserializer.deserialize_u8(V)
+	}
+}
+
+/// The delay of components for which none is set.
+fn default_delay() -> NonZeroU8 {
+	NonZeroU8::new(1).unwrap()
+}
+
+fn delay_property(delay: NonZeroU8) -> Property {
+	Property::new(
+		"delay",
+		PropertyValue::Int { value: delay.get().into(), range: 1..=255 },
+	)
+}
+
+fn parse_delay(value: SetProperty) -> Result<NonZeroU8, Box<dyn Error>> {
+	let v = value.as_int().ok_or("expected integer")?;
+	let v = v.try_into().map_err(|_| "integer out of range")?;
+	Ok(NonZeroU8::new(v).ok_or("integer out of range")?)
+}
+
+/// The width of gates for which none is set.
+fn default_bits() -> NonZeroU16 {
+	NonZeroU16::new(32).unwrap()
+}
+
+fn bits_property(bits: NonZeroU16) -> Property {
+	bits_property_up_to(bits, MAX_BITS)
+}
+
+fn parse_bits(value: SetProperty) -> Result<NonZeroU16, Box<dyn Error>> {
+	parse_bits_up_to(value, MAX_BITS)
+}
+
+/// [`bits_property`] for components that support at most `max` bits.
+fn bits_property_up_to(bits: NonZeroU16, max: u16) -> Property {
+	named_bits_property_up_to("bits", bits, max)
+}
+
+/// [`bits_property_up_to`] for widths other than that of the data, such as the select bits of a
+/// multiplexer.
+fn named_bits_property_up_to(name: &str, bits: NonZeroU16, max: u16) -> Property {
+	Property::new(
+		name,
+		PropertyValue::Int { value: bits.get().into(), range: 1..=max.into() },
+	)
+}
+
+fn parse_bits_up_to(value: SetProperty, max: u16) -> Result<NonZeroU16, Box<dyn Error>> {
+	let v = value.as_int().ok_or("expected integer")?;
+	(1..=max.into())
+		.contains(&v)
+		.then(|| NonZeroU16::new(v.try_into().unwrap()).unwrap())
+		.ok_or_else(|| "integer out of range".into())
+}
+
+/// Whether the inputs of a component are interpreted as two's complement numbers.
+fn signed_property(signed: bool) -> Property {
+	Property::new(
+		"signed",
+		PropertyValue::Int { value: signed.into(), range: 0..=1 },
+	)
+}
+
+/// Whether a component is triggered by the falling instead of the rising edge of its clock.
+fn edge_property(falling: bool) -> Property {
+	Property::new(
+		"falling edge",
+		PropertyValue::Int { value: falling.into(), range: 0..=1 },
+	)
+}
+
+/// Parses a property that is either 0 or 1, such as [`signed_property`] or [`edge_property`].
+fn parse_bool(value: SetProperty) -> Result<bool, Box<dyn Error>> {
+	match value.as_int() {
+		Some(v @ 0..=1) => Ok(v == 1),
+		Some(_) => Err("integer out of range".into()),
+		None => Err("expected integer".into()),
+	}
+}
+
+/// An op leaving the bits past the end of a value floating, if word `k` is not fully used.
+fn mask_word(bits: NonZeroU16, k: usize) -> Option<IrOp> {
+	let i = word_mask(bits.get(), k);
+	(i != usize::MAX).then(|| IrOp::Andi { i })
+}
+
+macro_rules! gate {
+	($name:ident, $op:ident) => {
+		#[derive(Serialize, Deserialize)]
+		pub struct $name {
+			/// The amount of inputs this gate has. Must be at least 2.
+			pub inputs: NonZeroOneU8,
+			#[serde(default = "default_bits")]
+			pub bits: NonZeroU16,
+			#[serde(default = "default_delay")]
+			pub delay: NonZeroU8,
+		}
+
+		impl $name {
+			pub fn new(inputs: NonZeroOneU8) -> Self {
+				Self { inputs, bits: default_bits(), delay: default_delay() }
+			}
+		}
+
+		impl Component for $name {
+			fn inputs(&self) -> Box<[InputType]> {
+				let bits = self.bits;
+				(0..self.inputs.get()).map(|_| InputType { bits }).collect()
+			}
+
+			fn outputs(&self) -> Box<[OutputType]> {
+				[OutputType { bits: self.bits }].into()
+			}
+
+			fn generate_ir(&self, gen: GenerateIr) -> usize {
+				assert_eq!(gen.outputs.len(), 1);
+				if gen.outputs[0] == usize::MAX || gen.inputs.iter().all(|&i| i == usize::MAX) {
+					return 0;
+				}
+				let mut ir = Vec::new();
+				for k in 0..words(self.bits.get()) {
+					let mut it = gen.inputs[..].iter().filter(|&&a| a != usize::MAX);
+					let it = it
+						.next()
+						.map(|&a| IrOp::Copy { a: a + k })
+						.into_iter()
+						.chain(it.map(|&a| IrOp::$op { a: a + k }))
+						.chain(mask_word(self.bits, k))
+						.chain(Some(IrOp::Save { out: gen.outputs[0] + k }));
+					ir.extend(it);
+				}
+				(gen.out)(ir);
+				0
+			}
+
+			fn properties(&self) -> Box<[Property]> {
+				let inputs = PropertyValue::Int { value: self.inputs.get().into(), range: 2..=31 };
+				[
+					Property { name: "inputs".into(), read_only: false, value: inputs },
+					bits_property(self.bits),
+					delay_property(self.delay),
+				]
+				.into()
+			}
+
+			fn set_property(
+				&mut self,
+				name: &str,
+				value: SetProperty,
+			) -> Result<(), Box<dyn Error>> {
+				match name {
+					"inputs" => {
+						let v = value.as_int().ok_or("expected integer")?;
+						let v = v.try_into().map_err(|_| "integer out of range")?;
+						self.inputs = NonZeroOneU8::new(v).ok_or("integer out of range")?;
+					}
+					"bits" => self.bits = parse_bits(value)?,
+					"delay" => self.delay = parse_delay(value)?,
+					_ => Err("invalid property")?,
+				}
+				Ok(())
+			}
+
+			fn delay(&self) -> NonZeroU8 {
+				self.delay
+			}
+		}
+	};
+}
+
+gate!(AndGate, And);
+gate!(OrGate, Or);
+gate!(XorGate, Xor);
+
+#[derive(Serialize, Deserialize)]
+pub struct NotGate {
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+	#[serde(default = "default_delay")]
+	pub delay: NonZeroU8,
+}
+
+impl NotGate {
+	pub fn new() -> Self {
+		Self { bits: default_bits(), delay: default_delay() }
+	}
+}
+
+impl Component for NotGate {
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: self.bits }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		if gen.inputs[0] != usize::MAX && gen.outputs[0] != usize::MAX {
+			let ir = (0..words(self.bits.get()))
+				.flat_map(|k| {
+					[
+						IrOp::Copy { a: gen.inputs[0] + k },
+						IrOp::Xori { i: usize::MAX },
+					]
+					.into_iter()
+					.chain(mask_word(self.bits, k))
+					.chain(Some(IrOp::Save { out: gen.outputs[0] + k }))
+				})
+				.collect();
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[bits_property(self.bits), delay_property(self.delay)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			"delay" => self.delay = parse_delay(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+
+	fn delay(&self) -> NonZeroU8 {
+		self.delay
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct In {
+	#[serde(default)]
+	pub name: Box<str>,
+	pub bits: NonZeroU16,
+	pub index: usize,
+}
+
+impl In {
+	pub fn new(name: impl Into<Box<str>>, bits: NonZeroU16, index: usize) -> Self {
+		Self { name: name.into(), bits, index }
+	}
+}
+
+impl Component for In {
+	fn label(&self) -> Option<&str> {
+		(!self.name.is_empty()).then(|| &*self.name)
+	}
+
+	fn inputs(&self) -> Box<[InputType]> {
+		[].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, _: GenerateIr) -> usize {
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let name = PropertyValue::Str { value: self.name.clone() };
+		[Property::new("name", name), bits_property(self.bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"name" => self.name = value.into_str().ok_or("expected string")?,
+			"bits" => self.bits = parse_bits(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+
+	fn external_type(&self) -> Option<super::ExternalType> {
+		Some(super::ExternalType::In(self.index, self.bits))
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Out {
+	#[serde(default)]
+	pub name: Box<str>,
+	pub bits: NonZeroU16,
+	pub index: usize,
+}
+
+impl Out {
+	pub fn new(name: impl Into<Box<str>>, bits: NonZeroU16, index: usize) -> Self {
+		Self { name: name.into(), bits, index }
+	}
+}
+
+impl Component for Out {
+	fn label(&self) -> Option<&str> {
+		(!self.name.is_empty()).then(|| &*self.name)
+	}
+
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: self.bits }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[].into()
+	}
+
+	fn generate_ir(&self, _: GenerateIr) -> usize {
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let name = PropertyValue::Str { value: self.name.clone() };
+		[Property::new("name", name), bits_property(self.bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"name" => self.name = value.into_str().ok_or("expected string")?,
+			"bits" => self.bits = parse_bits(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+
+	fn external_type(&self) -> Option<super::ExternalType> {
+		Some(super::ExternalType::Out(self.index, self.bits))
+	}
+}
@@ -0,0 +1,389 @@
+use super::{
+	bits_property, default_bits, named_bits_property_up_to, parse_bits, parse_bits_up_to,
+	word_mask, words, Component, GenerateIr, InputType, IrOp, OutputType, Property, SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// The maximum amount of select bits, which limits plexers to 32 inputs or outputs.
+pub const MAX_SELECT_BITS: u16 = 5;
+
+fn select_property(select_bits: NonZeroU16) -> Property {
+	named_bits_property_up_to("select bits", select_bits, MAX_SELECT_BITS)
+}
+
+/// Ops loading a floating word.
+const FLOATING: [IrOp; 2] = [IrOp::Load { value: 0 }, IrOp::Andi { i: 0 }];
+
+/// Ops setting bit 0 of the accumulator if the value at `select` equals `i`.
+fn select(select: usize, select_bits: NonZeroU16, i: usize) -> [IrOp; 2] {
+	let mask = word_mask(select_bits.get(), 0);
+	[IrOp::Copy { a: select }, IrOp::Eqi { i, mask }]
+}
+
+/// A component passing one of its inputs to its output.
+///
+/// The inputs are the data inputs followed by the select input. The output is floating if the
+/// select input is not fully defined.
+#[derive(Serialize, Deserialize)]
+pub struct Multiplexer {
+	pub select_bits: NonZeroU16,
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+}
+
+impl Multiplexer {
+	pub fn new(select_bits: NonZeroU16, bits: NonZeroU16) -> Self {
+		Self { select_bits, bits }
+	}
+
+	/// The amount of data inputs.
+	pub fn data_inputs(&self) -> usize {
+		1 << self.select_bits.get()
+	}
+}
+
+impl Component for Multiplexer {
+	fn inputs(&self) -> Box<[InputType]> {
+		let data = (0..self.data_inputs()).map(|_| InputType { bits: self.bits });
+		data.chain([InputType { bits: self.select_bits }]).collect()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (n, out) = (self.data_inputs(), gen.outputs[0]);
+		let sel = gen.inputs[n];
+		if sel == usize::MAX || out == usize::MAX {
+			return 0;
+		}
+		let w = words(self.bits.get());
+		let mut ir = Vec::new();
+		for k in 0..w {
+			ir.extend(FLOATING);
+			ir.push(IrOp::Save { out: out + k });
+		}
+		for (i, &a) in gen.inputs[..n].iter().enumerate() {
+			if a != usize::MAX {
+				ir.extend(select(sel, self.select_bits, i));
+				ir.extend((0..w).map(|k| IrOp::SaveIf { a: a + k, out: out + k }));
+			}
+		}
+		(gen.out)(ir);
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[select_property(self.select_bits), bits_property(self.bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"select bits" => self.select_bits = parse_bits_up_to(value, MAX_SELECT_BITS)?,
+			"bits" => self.bits = parse_bits(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+/// A component passing its input to one of its outputs. All other outputs are low.
+///
+/// The inputs are the data input and the select input.
+#[derive(Serialize, Deserialize)]
+pub struct Demultiplexer {
+	pub select_bits: NonZeroU16,
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+}
+
+impl Demultiplexer {
+	pub fn new(select_bits: NonZeroU16, bits: NonZeroU16) -> Self {
+		Self { select_bits, bits }
+	}
+
+	/// The amount of data outputs.
+	pub fn data_outputs(&self) -> usize {
+		1 << self.select_bits.get()
+	}
+}
+
+impl Component for Demultiplexer {
+	fn inputs(&self) -> Box<[InputType]> {
+		[
+			InputType { bits: self.bits },
+			InputType { bits: self.select_bits },
+		]
+		.into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		(0..self.data_outputs())
+			.map(|_| OutputType { bits: self.bits })
+			.collect()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (data, sel) = (gen.inputs[0], gen.inputs[1]);
+		// Don't drive the outputs at all if any input is not connected.
+		if data == usize::MAX || sel == usize::MAX {
+			return 0;
+		}
+		let mut ir = Vec::new();
+		for (i, &out) in gen.outputs.iter().enumerate() {
+			if out == usize::MAX {
+				continue;
+			}
+			let w = words(self.bits.get());
+			for k in 0..w {
+				let mask = word_mask(self.bits.get(), k);
+				ir.extend([IrOp::Load { value: 0 }, IrOp::Andi { i: mask }]);
+				ir.push(IrOp::Save { out: out + k });
+			}
+			ir.extend(select(sel, self.select_bits, i));
+			ir.extend((0..w).map(|k| IrOp::SaveIf { a: data + k, out: out + k }));
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[select_property(self.select_bits), bits_property(self.bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"select bits" => self.select_bits = parse_bits_up_to(value, MAX_SELECT_BITS)?,
+			"bits" => self.bits = parse_bits(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+/// A component setting the output selected by its input and clearing all others.
+#[derive(Serialize, Deserialize)]
+pub struct Decoder {
+	pub select_bits: NonZeroU16,
+}
+
+impl Decoder {
+	pub fn new(select_bits: NonZeroU16) -> Self {
+		Self { select_bits }
+	}
+}
+
+impl Component for Decoder {
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: self.select_bits }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		let bits = NonZeroU16::new(1).unwrap();
+		(0..1 << self.select_bits.get())
+			.map(|_| OutputType { bits })
+			.collect()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let sel = gen.inputs[0];
+		if sel == usize::MAX {
+			return 0;
+		}
+		let mut ir = Vec::new();
+		for (i, &out) in gen.outputs.iter().enumerate() {
+			if out != usize::MAX {
+				ir.extend(select(sel, self.select_bits, i));
+				ir.extend([IrOp::Andi { i: 1 }, IrOp::Save { out }]);
+			}
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[select_property(self.select_bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"select bits" => self.select_bits = parse_bits_up_to(value, MAX_SELECT_BITS)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+/// A component outputting the index of its highest input that is set.
+///
+/// The outputs are the index and whether any input is set. The index is floating if no input is
+/// set. Inputs that are floating or shorted are considered cleared.
+#[derive(Serialize, Deserialize)]
+pub struct PriorityEncoder {
+	pub select_bits: NonZeroU16,
+}
+
+impl PriorityEncoder {
+	pub fn new(select_bits: NonZeroU16) -> Self {
+		Self { select_bits }
+	}
+}
+
+impl Component for PriorityEncoder {
+	fn inputs(&self) -> Box<[InputType]> {
+		let bits = NonZeroU16::new(1).unwrap();
+		(0..1 << self.select_bits.get())
+			.map(|_| InputType { bits })
+			.collect()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		let one = NonZeroU16::new(1).unwrap();
+		[
+			OutputType { bits: self.select_bits },
+			OutputType { bits: one },
+		]
+		.into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (index, valid) = (gen.outputs[0], gen.outputs[1]);
+		let mask = word_mask(self.select_bits.get(), 0);
+		let mut ir = Vec::new();
+		if index != usize::MAX {
+			ir.extend(FLOATING);
+			ir.push(IrOp::Save { out: index });
+		}
+		if valid != usize::MAX {
+			ir.extend([IrOp::Load { value: 0 }, IrOp::Andi { i: 1 }]);
+			ir.push(IrOp::Save { out: valid });
+		}
+		// Higher inputs override lower ones.
+		for (i, &a) in gen.inputs.iter().enumerate() {
+			if a == usize::MAX {
+				continue;
+			}
+			ir.push(IrOp::Copy { a });
+			if index != usize::MAX {
+				ir.push(IrOp::SetIf { out: index, value: i, mask });
+			}
+			if valid != usize::MAX {
+				ir.push(IrOp::SetIf { out: valid, value: 1, mask: 1 });
+			}
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[select_property(self.select_bits)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"select bits" => self.select_bits = parse_bits_up_to(value, MAX_SELECT_BITS)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	fn nz(n: u16) -> NonZeroU16 {
+		NonZeroU16::new(n).unwrap()
+	}
+
+	/// Connect all inputs of a component to external inputs and all outputs to external outputs.
+	fn state(component: impl Component + 'static) -> State {
+		let mut b = Builder::new();
+		let inputs = component
+			.inputs()
+			.iter()
+			.enumerate()
+			.map(|(i, t)| b.input(&i.to_string(), t.bits.get()))
+			.collect::<Vec<_>>();
+		let outputs = b.add(component, &inputs);
+		for (i, &o) in outputs.iter().enumerate() {
+			b.output(&i.to_string(), o);
+		}
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	fn run<const N: usize>(state: &mut State, inputs: &[Value]) -> [Value; N] {
+		state.write_inputs(inputs);
+		assert!(state.run(64).is_settled());
+		let mut out = [Value::Floating; N];
+		state.read_outputs(&mut out);
+		out
+	}
+
+	#[test]
+	fn multiplexer() {
+		let mut s = state(Multiplexer::new(nz(2), nz(70)));
+		let wide = Value::from_words([Value::Set(5), Value::Set(0x3f)]);
+		let mut inputs = [
+			Value::Set(10),
+			Value::Set(11),
+			wide,
+			Value::Set(13),
+			Value::Set(0),
+		];
+		for (i, v) in [10, 11, 12, 13].into_iter().enumerate() {
+			inputs[4] = Value::Set(i);
+			let expect = if i == 2 { wide } else { Value::Set(v) };
+			assert_eq!(run(&mut s, &inputs), [expect]);
+		}
+		inputs[4] = Value::Mixed { value: 0, floating: 1, short: 0 };
+		assert_eq!(run(&mut s, &inputs), [Value::Floating]);
+	}
+
+	#[test]
+	fn demultiplexer() {
+		let mut s = state(Demultiplexer::new(nz(1), nz(8)));
+		let out = run(&mut s, &[Value::Set(0x12), Value::Set(1)]);
+		assert_eq!(out, [Value::Set(0), Value::Set(0x12)]);
+		let out = run(&mut s, &[Value::Set(0x34), Value::Set(0)]);
+		assert_eq!(out, [Value::Set(0x34), Value::Set(0)]);
+	}
+
+	#[test]
+	fn decoder() {
+		let mut s = state(Decoder::new(nz(2)));
+		for i in 0..4 {
+			let mut expect = [Value::Set(0); 4];
+			expect[i] = Value::Set(1);
+			assert_eq!(run(&mut s, &[Value::Set(i)]), expect);
+		}
+		let out = run::<4>(&mut s, &[Value::Mixed { value: 2, floating: 1, short: 0 }]);
+		let (zero, short) = (Value::Set(0), Value::Short);
+		assert_eq!(out, [zero, zero, short, short]);
+	}
+
+	#[test]
+	fn priority_encoder() {
+		let mut s = state(PriorityEncoder::new(nz(2)));
+		let set = |bits: usize| {
+			(0..4)
+				.map(|i| Value::Set(bits >> i & 1))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(run(&mut s, &set(0)), [Value::Floating, Value::Set(0)]);
+		assert_eq!(run(&mut s, &set(0b0001)), [Value::Set(0), Value::Set(1)]);
+		assert_eq!(run(&mut s, &set(0b0110)), [Value::Set(2), Value::Set(1)]);
+		assert_eq!(run(&mut s, &set(0b1011)), [Value::Set(3), Value::Set(1)]);
+	}
+}
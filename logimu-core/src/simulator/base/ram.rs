@@ -0,0 +1,199 @@
+use super::{
+	bits_property, default_bits, mask_word, named_bits_property_up_to, parse_bits,
+	parse_bits_up_to, parse_bool, words, Component, GenerateIr, InputType, IrOp, OutputType,
+	Property, PropertyValue, SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// The maximum amount of address bits, which limits RAM to 64K entries.
+pub const MAX_ADDRESS_BITS: u16 = 16;
+
+/// A component representing read/write memory.
+///
+/// The inputs are the address, the data to write, write enable and the clock. The data is
+/// written on the rising edge of the clock if write enable is high. With asynchronous reads the
+/// output follows the address, otherwise the address is latched on the rising edge of the clock.
+///
+/// The contents are not part of the component but of the state of the simulation, so they are
+/// lost when the circuit is closed.
+#[derive(Serialize, Deserialize)]
+pub struct RandomAccessMemory {
+	pub address_bits: NonZeroU16,
+	/// The amount of bits of each entry.
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+	pub async_read: bool,
+}
+
+impl RandomAccessMemory {
+	pub fn new(address_bits: NonZeroU16, bits: NonZeroU16) -> Self {
+		Self { address_bits, bits, async_read: true }
+	}
+
+	/// The amount of entries.
+	pub fn entries(&self) -> usize {
+		1 << self.address_bits.get()
+	}
+}
+
+impl Default for RandomAccessMemory {
+	fn default() -> Self {
+		Self::new(NonZeroU16::new(8).unwrap(), NonZeroU16::new(8).unwrap())
+	}
+}
+
+impl Component for RandomAccessMemory {
+	fn inputs(&self) -> Box<[InputType]> {
+		let one = NonZeroU16::new(1).unwrap();
+		[
+			InputType { bits: self.address_bits },
+			InputType { bits: self.bits },
+			InputType { bits: one },
+			InputType { bits: one },
+		]
+		.into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let [address, data, write, clock] = [0, 1, 2, 3].map(|i| gen.inputs[i]);
+		let out = gen.outputs[0];
+		let (len, w) = (self.entries(), words(self.bits.get()));
+		// The contents are followed by the previous value of the clock and the latched address.
+		let base = gen.memory_size;
+		let (prev, latch) = (base + w * len, base + w * len + 1);
+		(gen.memory)(base, len, self.bits);
+
+		let mut ir = Vec::new();
+		if clock != usize::MAX {
+			// Detect the rising edge of the clock.
+			ir.extend([
+				IrOp::Load { value: 1 },
+				IrOp::Edge { a: clock, prev, falling: false },
+			]);
+			if !self.async_read && address != usize::MAX {
+				ir.push(IrOp::SaveIf { a: address, out: latch });
+			}
+			if address != usize::MAX && data != usize::MAX && write != usize::MAX {
+				ir.push(IrOp::And { a: write });
+				ir.extend((0..w).map(|k| {
+					let base = base + k * len;
+					IrOp::Store { a: data + k, index: address, base, len }
+				}));
+			}
+			// Run again after the clock changed to output what was written or latched.
+			ir.push(IrOp::CheckDirty { a: prev, node: gen.nodes, mask: 1 });
+		}
+		let index = if self.async_read { address } else { latch };
+		if out != usize::MAX && index != usize::MAX {
+			for k in 0..w {
+				ir.extend([
+					IrOp::Copy { a: index },
+					IrOp::Fetch { base: base + k * len, len },
+				]);
+				ir.extend(mask_word(self.bits, k));
+				ir.push(IrOp::Save { out: out + k });
+			}
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		w * len + 2
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let async_read = PropertyValue::Int { value: self.async_read.into(), range: 0..=1 };
+		[
+			named_bits_property_up_to("address bits", self.address_bits, MAX_ADDRESS_BITS),
+			bits_property(self.bits),
+			Property::new("async read", async_read),
+		]
+		.into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"address bits" => self.address_bits = parse_bits_up_to(value, MAX_ADDRESS_BITS)?,
+			"bits" => self.bits = parse_bits(value)?,
+			"async read" => self.async_read = parse_bool(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	/// A RAM with 4 entries of 8 bits with all ports connected to inputs.
+	fn ram(async_read: bool) -> State {
+		let mut b = Builder::new();
+		let (address, data) = (b.input("A", 2), b.input("D", 8));
+		let (write, clock) = (b.input("W", 1), b.input("C", 1));
+		let bits = [2, 8].map(|b| NonZeroU16::new(b).unwrap());
+		let mut ram = RandomAccessMemory::new(bits[0], bits[1]);
+		ram.async_read = async_read;
+		let out = b.add(ram, &[address, data, write, clock])[0];
+		b.output("Q", out);
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	/// Write the inputs, then pulse the clock if `clock` is set. Returns the output.
+	fn cycle(state: &mut State, address: usize, data: usize, write: bool, clock: bool) -> Value {
+		let set = Value::Set;
+		let mut inputs = [set(address), set(data), set(write.into()), set(0)];
+		state.write_inputs(&inputs);
+		assert!(state.run(64).is_settled());
+		if clock {
+			inputs[3] = set(1);
+			state.write_inputs(&inputs);
+			assert!(state.run(64).is_settled());
+		}
+		let mut out = [Value::Floating];
+		state.read_outputs(&mut out);
+		out[0]
+	}
+
+	#[test]
+	fn async_read() {
+		let mut s = ram(true);
+		assert_eq!(cycle(&mut s, 1, 0, false, false), Value::Floating);
+		assert_eq!(cycle(&mut s, 1, 0x12, true, true), Value::Set(0x12));
+		assert_eq!(cycle(&mut s, 2, 0x34, true, true), Value::Set(0x34));
+		// Nothing is written without a rising edge or with write enable low.
+		assert_eq!(cycle(&mut s, 2, 0x56, true, false), Value::Set(0x34));
+		assert_eq!(cycle(&mut s, 2, 0x56, false, true), Value::Set(0x34));
+		assert_eq!(cycle(&mut s, 1, 0x56, false, false), Value::Set(0x12));
+	}
+
+	#[test]
+	fn sync_read() {
+		let mut s = ram(false);
+		assert_eq!(cycle(&mut s, 1, 0x12, true, true), Value::Set(0x12));
+		// The output only follows the address on the rising edge of the clock.
+		assert_eq!(cycle(&mut s, 0, 0, false, false), Value::Set(0x12));
+		assert_eq!(cycle(&mut s, 0, 0, false, true), Value::Floating);
+	}
+
+	#[test]
+	fn edit_contents() {
+		let mut s = ram(true);
+		let ram = s.program().memories[0].0;
+		assert_eq!(s.memory_len(ram), Some(4));
+		cycle(&mut s, 3, 0x12, true, true);
+		assert_eq!(s.read_memory(ram, 3), Value::Set(0x12));
+		s.write_memory(ram, 3, Value::Set(0x34));
+		assert!(s.run(64).is_settled());
+		let mut out = [Value::Floating];
+		s.read_outputs(&mut out);
+		assert_eq!(out, [Value::Set(0x34)]);
+	}
+}
@@ -0,0 +1,167 @@
+use super::{
+	bits_property, default_bits, edge_property, mask_word, parse_bits, parse_bool, word_mask,
+	words, Component, GenerateIr, InputType, IrOp, OutputType, Property, SetProperty,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A component storing a value on an edge of the clock if it is enabled.
+///
+/// The inputs are the data, the clock, enable, set and reset. It is always enabled if enable
+/// isn't connected. Set and reset are asynchronous and set or clear all bits, with reset taking
+/// precedence.
+#[derive(Serialize, Deserialize)]
+pub struct Register {
+	#[serde(default = "default_bits")]
+	pub bits: NonZeroU16,
+	/// Whether the value is stored on the falling instead of the rising edge of the clock.
+	#[serde(default)]
+	pub falling: bool,
+}
+
+impl Register {
+	pub fn new(bits: NonZeroU16) -> Self {
+		Self { bits, falling: false }
+	}
+}
+
+impl Component for Register {
+	fn inputs(&self) -> Box<[InputType]> {
+		let one = NonZeroU16::new(1).unwrap();
+		let bits = [self.bits, one, one, one, one];
+		bits.map(|bits| InputType { bits }).into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let [data, clock, enable, set, reset] = [0, 1, 2, 3, 4].map(|i| gen.inputs[i]);
+		let out = gen.outputs[0];
+		let w = words(self.bits.get());
+		// The value is followed by the previous value of the clock.
+		let (value, prev) = (gen.memory_size, gen.memory_size + w);
+
+		let mut ir = Vec::new();
+		if data != usize::MAX && clock != usize::MAX {
+			ir.push(match enable {
+				usize::MAX => IrOp::Load { value: 1 },
+				a => IrOp::Copy { a },
+			});
+			ir.push(IrOp::Edge { a: clock, prev, falling: self.falling });
+			ir.extend((0..w).map(|k| IrOp::SaveIf { a: data + k, out: value + k }));
+		}
+		for (a, ones) in [(set, true), (reset, false)] {
+			if a != usize::MAX {
+				ir.push(IrOp::Copy { a });
+				ir.extend((0..w).map(|k| {
+					let mask = word_mask(self.bits.get(), k);
+					let v = if ones { mask } else { 0 };
+					IrOp::SetIf { out: value + k, value: v, mask }
+				}));
+			}
+		}
+		if !ir.is_empty() {
+			// Run again after the value changed to output it.
+			let checks = (0..w).map(|k| IrOp::CheckDirty {
+				a: value + k,
+				node: gen.nodes,
+				mask: usize::MAX,
+			});
+			ir.extend(checks);
+		}
+		if out != usize::MAX {
+			for k in 0..w {
+				ir.push(IrOp::Copy { a: value + k });
+				ir.extend(mask_word(self.bits, k));
+				ir.push(IrOp::Save { out: out + k });
+			}
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		w + 1
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[bits_property(self.bits), edge_property(self.falling)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			"falling edge" => self.falling = parse_bool(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	/// A register of 12 bits with all inputs connected to external inputs, in order.
+	fn register(falling: bool) -> State {
+		let mut b = Builder::new();
+		let data = b.input("D", 12);
+		let mut inputs = vec![data];
+		inputs.extend(["C", "E", "S", "R"].map(|n| b.input(n, 1)));
+		let mut register = Register::new(NonZeroU16::new(12).unwrap());
+		register.falling = falling;
+		let out = b.add(register, &inputs)[0];
+		b.output("Q", out);
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	fn write(state: &mut State, inputs: [usize; 5]) -> Value {
+		state.write_inputs(&inputs.map(Value::Set));
+		assert!(state.run(64).is_settled());
+		let mut out = [Value::Floating];
+		state.read_outputs(&mut out);
+		out[0]
+	}
+
+	#[test]
+	fn enable() {
+		let mut s = register(false);
+		assert_eq!(write(&mut s, [0x123, 0, 1, 0, 0]), Value::Floating);
+		assert_eq!(write(&mut s, [0x123, 1, 1, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 0, 0, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 1, 0, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 0, 1, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 1, 1, 0, 0]), Value::Set(0x456));
+	}
+
+	#[test]
+	fn falling_edge_and_async() {
+		let mut s = register(true);
+		assert_eq!(write(&mut s, [0x123, 1, 1, 0, 0]), Value::Floating);
+		assert_eq!(write(&mut s, [0x123, 0, 1, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x123, 0, 1, 1, 0]), Value::Set(0xfff));
+		assert_eq!(write(&mut s, [0x123, 0, 1, 1, 1]), Value::Set(0));
+	}
+
+	#[test]
+	fn adapt() {
+		let mut b = Builder::new();
+		let mut inputs = vec![b.input("D", 12)];
+		inputs.extend(["C", "E", "S", "R"].map(|n| b.input(n, 1)));
+		let out = b.add(Register::new(NonZeroU16::new(12).unwrap()), &inputs)[0];
+		b.output("Q", out);
+		let mut s = Arc::new(b.generate_ir()).new_state();
+		write(&mut s, [0x123, 0, 1, 0, 0]);
+		assert_eq!(write(&mut s, [0x123, 1, 1, 0, 0]), Value::Set(0x123));
+
+		// A wide nexus moves the memory of the register, which must keep its value.
+		b.constant(100, 0);
+		let mut s = s.adapt(Arc::new(b.generate_ir()));
+		assert_eq!(write(&mut s, [0x456, 1, 1, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 0, 1, 0, 0]), Value::Set(0x123));
+		assert_eq!(write(&mut s, [0x456, 1, 1, 0, 0]), Value::Set(0x456));
+	}
+}
@@ -0,0 +1,145 @@
+use super::*;
+use core::cell::Cell;
+use core::iter;
+use thin_dst::ThinArc;
+
+type Memory = ThinArc<(), usize>;
+
+/// A component representing read-only memory.
+#[derive(Serialize, Deserialize)]
+pub struct ReadOnlyMemory {
+	/// The amount of bits of each entry.
+	#[serde(default = "default_bits")]
+	bits: NonZeroU16,
+	/// The words of each entry, least significant word first.
+	contents: Vec<usize>,
+	/// The contents split by word of each entry.
+	#[serde(skip)]
+	cached: Cell<Option<Box<[Memory]>>>,
+}
+
+impl Default for ReadOnlyMemory {
+	fn default() -> Self {
+		Self {
+			bits: default_bits(),
+			contents: Vec::new(),
+			cached: Cell::default(),
+		}
+	}
+}
+
+impl ReadOnlyMemory {
+	/// Get the least significant word of an entry.
+	pub fn get(&self, index: usize) -> Option<usize> {
+		self.contents.get(index * self.words()).copied()
+	}
+
+	/// The amount of words of each entry.
+	fn words(&self) -> usize {
+		words(self.bits.get())
+	}
+}
+
+impl Component for ReadOnlyMemory {
+	fn inputs(&self) -> Box<[InputType]> {
+		[InputType { bits: NonZeroU16::new(32).unwrap() }].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (address, out) = (gen.inputs[0], gen.outputs[0]);
+		if address != usize::MAX && out != usize::MAX {
+			let mut cached = self.cached.take();
+			let w = self.words();
+			let memory = cached.get_or_insert_with(|| {
+				(0..w)
+					.map(|k| {
+						let words = self.contents.iter().skip(k).step_by(w).copied();
+						ThinArc::new((), words.collect::<Vec<_>>())
+					})
+					.collect()
+			});
+			let ir = memory
+				.iter()
+				.enumerate()
+				.flat_map(|(k, memory)| {
+					[
+						IrOp::Copy { a: address },
+						IrOp::Read { memory: memory.clone() },
+						IrOp::Save { out: out + k },
+					]
+				})
+				.collect();
+			(gen.out)(ir);
+			self.cached.set(cached);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		// TODO some 'memory' / 'dialog' property should be used for editing a large amount of
+		// data
+		let range = i32::MIN.into()..=u32::MAX.into();
+		let w = self.words();
+		// Entries wider than a word have a property for each word.
+		let name = |i: usize| match w {
+			1 => format!("0x{:03x}", i),
+			_ => format!("0x{:03x}.{}", i / w, i % w),
+		};
+		let entries = self
+			.contents
+			.iter()
+			.chain(&[0; MAX_WORDS][..w])
+			.enumerate()
+			.map(|(i, e)| {
+				Property::new(
+					name(i),
+					PropertyValue::Int { value: *e as i64, range: range.clone() },
+				)
+			});
+		iter::once(bits_property(self.bits))
+			.chain(entries)
+			.collect()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		if name == "bits" {
+			let (old, bits) = (self.words(), parse_bits(value)?);
+			let new = words(bits.get());
+			self.contents = self
+				.contents
+				.chunks(old)
+				.flat_map(|e| (0..new).map(|k| e.get(k).copied().unwrap_or(0)))
+				.collect();
+			self.bits = bits;
+			self.cached.set(None);
+			return Ok(());
+		}
+		if !name.starts_with("0x") {
+			Err("invalid property")?;
+		}
+		let (index, k) = name
+			.split_at(2)
+			.1
+			.split_once('.')
+			.unwrap_or((name.split_at(2).1, "0"));
+		let i = match (usize::from_str_radix(index, 16), k.parse::<usize>()) {
+			(Ok(i), Ok(k)) if k < self.words() => i * self.words() + k,
+			_ => Err("invalid property")?,
+		};
+		match value.as_int() {
+			Some(v) if i < self.contents.len() => self.contents[i] = v as usize,
+			Some(v) if i / self.words() == self.contents.len() / self.words() => {
+				self.contents.resize(self.contents.len() + self.words(), 0);
+				self.contents[i] = v as usize;
+			}
+			Some(_) => Err("address out of range")?,
+			None => Err("expected integer")?,
+		}
+		self.cached.set(None);
+		Ok(())
+	}
+}
@@ -0,0 +1,210 @@
+use super::{
+	bits_property_up_to, parse_bits_up_to, word_mask, Component, GenerateIr, InputType, IrOp,
+	OutputType, Property, PropertyValue, SetProperty, WORD_BITS,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// The operation performed by a [`Shifter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShiftKind {
+	/// Shift left, shifting in zeros.
+	LogicalLeft,
+	/// Shift right, shifting in zeros.
+	LogicalRight,
+	/// Shift right, shifting in copies of the highest bit.
+	ArithmeticRight,
+	RotateLeft,
+	RotateRight,
+}
+
+impl Default for ShiftKind {
+	fn default() -> Self {
+		Self::LogicalLeft
+	}
+}
+
+impl ShiftKind {
+	const ALL: [Self; 5] = [
+		Self::LogicalLeft,
+		Self::LogicalRight,
+		Self::ArithmeticRight,
+		Self::RotateLeft,
+		Self::RotateRight,
+	];
+
+	/// The name used for the "shift" property.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::LogicalLeft => "logical left",
+			Self::LogicalRight => "logical right",
+			Self::ArithmeticRight => "arithmetic right",
+			Self::RotateLeft => "rotate left",
+			Self::RotateRight => "rotate right",
+		}
+	}
+}
+
+/// A component shifting or rotating its input by a variable amount.
+///
+/// The inputs are the data and the amount. The amount has just enough bits to shift out all but
+/// one bit. The output is shorted if the amount is not fully defined. Undefined bits of the data
+/// are shifted like defined ones, so an arithmetic shift copies an undefined highest bit.
+#[derive(Serialize, Deserialize)]
+pub struct Shifter {
+	pub bits: NonZeroU16,
+	#[serde(default)]
+	pub kind: ShiftKind,
+}
+
+impl Shifter {
+	pub fn new(bits: NonZeroU16) -> Self {
+		Self { bits, kind: ShiftKind::default() }
+	}
+
+	/// The width of the amount input.
+	pub fn amount_bits(&self) -> NonZeroU16 {
+		let max = self.bits.get() - 1;
+		NonZeroU16::new((u16::BITS - max.leading_zeros()).max(1) as u16).unwrap()
+	}
+}
+
+impl Component for Shifter {
+	fn inputs(&self) -> Box<[InputType]> {
+		[
+			InputType { bits: self.bits },
+			InputType { bits: self.amount_bits() },
+		]
+		.into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (data, a, out) = (gen.inputs[0], gen.inputs[1], gen.outputs[0]);
+		if data == usize::MAX || a == usize::MAX || out == usize::MAX {
+			return 0;
+		}
+		let (width, bits) = (self.bits.get() as u8, self.amount_bits().get() as u8);
+		let mask = word_mask(self.bits.get(), 0);
+		let mut ir = Vec::new();
+		match self.kind {
+			ShiftKind::LogicalLeft => ir.extend([IrOp::Copy { a: data }, IrOp::Sll { a, bits }]),
+			ShiftKind::LogicalRight => {
+				// Clear the bits above the data so zeros are shifted in.
+				ir.extend([IrOp::Load { value: mask }, IrOp::And { a: data }]);
+				ir.push(IrOp::Srl { a, bits });
+			}
+			ShiftKind::ArithmeticRight => {
+				// Move the highest bit of the data to the top of the word.
+				let i = WORD_BITS as u8 - width;
+				ir.extend([IrOp::Copy { a: data }, IrOp::Slli { i }]);
+				ir.extend([IrOp::Sra { a, bits }, IrOp::Srli { i }]);
+			}
+			ShiftKind::RotateLeft => {
+				ir.extend([IrOp::Copy { a: data }, IrOp::Rotl { a, bits, width }])
+			}
+			ShiftKind::RotateRight => {
+				ir.extend([IrOp::Copy { a: data }, IrOp::Rotr { a, bits, width }])
+			}
+		}
+		ir.extend([IrOp::Andi { i: mask }, IrOp::Save { out }]);
+		(gen.out)(ir);
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let kind = PropertyValue::Str { value: self.kind.name().into() };
+		[
+			bits_property_up_to(self.bits, WORD_BITS),
+			Property::new("shift", kind),
+		]
+		.into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits_up_to(value, WORD_BITS)?,
+			"shift" => {
+				let value = value.into_str().ok_or("expected string")?;
+				self.kind = *ShiftKind::ALL
+					.iter()
+					.find(|k| k.name() == &*value)
+					.ok_or("invalid shift")?;
+			}
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::{ir::Value, *};
+	use std::sync::Arc;
+
+	fn shifter(bits: u16, kind: ShiftKind) -> State {
+		let mut b = Builder::new();
+		let mut shifter = Shifter::new(NonZeroU16::new(bits).unwrap());
+		shifter.kind = kind;
+		let data = b.input("D", bits);
+		let amount = b.input("A", shifter.amount_bits().get());
+		let out = b.add(shifter, &[data, amount])[0];
+		b.output("Q", out);
+		Arc::new(b.generate_ir()).new_state()
+	}
+
+	fn shift(state: &mut State, data: Value, amount: Value) -> Value {
+		state.write_inputs(&[data, amount]);
+		assert!(state.run(64).is_settled());
+		let mut out = [Value::Floating];
+		state.read_outputs(&mut out);
+		out[0]
+	}
+
+	#[test]
+	fn kinds() {
+		let expect = [
+			(ShiftKind::LogicalLeft, [0x96, 0x2c, 0x60, 0x00]),
+			(ShiftKind::LogicalRight, [0x96, 0x4b, 0x09, 0x01]),
+			(ShiftKind::ArithmeticRight, [0x96, 0xcb, 0xf9, 0xff]),
+			(ShiftKind::RotateLeft, [0x96, 0x2d, 0x69, 0x4b]),
+			(ShiftKind::RotateRight, [0x96, 0x4b, 0x69, 0x2d]),
+		];
+		for (kind, expect) in expect {
+			let mut s = shifter(8, kind);
+			for (amount, v) in [0, 1, 4, 7].into_iter().zip(expect) {
+				let out = shift(&mut s, Value::Set(0x96), Value::Set(amount));
+				assert_eq!(out, Value::Set(v), "{:?} {}", kind, amount);
+			}
+		}
+	}
+
+	#[test]
+	fn word() {
+		let mut s = shifter(64, ShiftKind::ArithmeticRight);
+		let v = shift(&mut s, Value::Set(1 << 63), Value::Set(63));
+		assert_eq!(v, Value::Set(usize::MAX));
+		let mut s = shifter(64, ShiftKind::RotateRight);
+		let v = shift(&mut s, Value::Set(1), Value::Set(1));
+		assert_eq!(v, Value::Set(1 << 63));
+	}
+
+	#[test]
+	fn undefined() {
+		let mut s = shifter(8, ShiftKind::LogicalLeft);
+		let data = Value::Mixed { value: 0x01, floating: 0x10, short: 0 };
+		let expect = Value::Mixed { value: 0x04, floating: 0x40, short: 0 };
+		assert_eq!(shift(&mut s, data, Value::Set(2)), expect);
+		assert_eq!(shift(&mut s, Value::Set(1), Value::Floating), Value::Short);
+
+		let mut s = shifter(8, ShiftKind::ArithmeticRight);
+		let data = Value::Mixed { value: 0x04, floating: 0x80, short: 0 };
+		let expect = Value::Mixed { value: 0x01, floating: 0xe0, short: 0 };
+		assert_eq!(shift(&mut s, data, Value::Set(2)), expect);
+	}
+}
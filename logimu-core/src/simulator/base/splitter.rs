@@ -0,0 +1,129 @@
+use super::{
+	words, BitRange, Component, GenerateIr, InputType, IrOp, OutputType, Property, PropertyValue,
+	SetProperty, MAX_BITS, MAX_WORDS, WORD_BITS,
+};
+use core::num::NonZeroU16;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Serialize, Deserialize)]
+pub struct Splitter {
+	/// Range of bits to output from input.
+	pub outputs: Vec<BitRange>,
+}
+
+impl Splitter {
+	pub fn new() -> Self {
+		Self {
+			outputs: [BitRange::new(0, NonZeroU16::new(1).unwrap())].into(),
+		}
+	}
+}
+
+impl Component for Splitter {
+	fn inputs(&self) -> Box<[InputType]> {
+		let bits = self.outputs.iter().map(BitRange::end).max().unwrap_or(1);
+		[InputType { bits: NonZeroU16::new(bits).unwrap() }].into()
+	}
+
+	fn input_masks(&self) -> Box<[[usize; MAX_WORDS]]> {
+		let mask = |k| self.outputs.iter().fold(0, |m, o| m | o.mask(k));
+		[core::array::from_fn(mask)].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		self.outputs
+			.iter()
+			.map(|e| OutputType { bits: e.bits })
+			.collect()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		assert_eq!(gen.inputs.len(), 1, "expected only one input");
+		//assert_eq!(outputs.len(), self.outputs.len(), "outputs do not match");
+		let input = gen.inputs[0];
+		if input == usize::MAX {
+			return 0;
+		}
+		let mut ir = Vec::new();
+		for (&w, r) in gen
+			.outputs
+			.iter()
+			.zip(self.outputs.iter())
+			.filter(|(&w, _)| w != usize::MAX)
+		{
+			let in_words = words(r.end());
+			for k in 0..words(r.bits.get()) {
+				let bit = r.start + k as u16 * WORD_BITS;
+				let (a, shift) = (
+					input + usize::from(bit / WORD_BITS),
+					(bit % WORD_BITS) as u8,
+				);
+				ir.push(IrOp::Copy { a });
+				if shift > 0 {
+					ir.push(IrOp::Srli { i: shift });
+				}
+				let next = usize::from(bit / WORD_BITS) + 1;
+				if shift > 0 && next < in_words {
+					// The output word straddles two input words.
+					ir.push(IrOp::OrB);
+					ir.push(IrOp::Copy { a: a + 1 });
+					ir.push(IrOp::Slli { i: WORD_BITS as u8 - shift });
+					ir.push(IrOp::OrB);
+					ir.push(IrOp::SaveB { out: w + k });
+					// B can't be cleared, so each straddling word needs its own node.
+					(gen.out)(ir);
+					ir = Vec::new();
+				} else {
+					ir.push(IrOp::Save { out: w + k });
+				}
+			}
+		}
+		if !ir.is_empty() {
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		let outputs = PropertyValue::Int {
+			value: self.outputs.len().try_into().unwrap(),
+			range: 1..=MAX_BITS.into(),
+		};
+		let mut v = Vec::from([Property::new("outputs", outputs)]);
+		for (i, o) in self.outputs.iter().enumerate() {
+			let range = PropertyValue::Range { value: *o };
+			v.push(Property::new(format!("output {}", i), range));
+		}
+		v.into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"outputs" => {
+				let v = value.as_int().ok_or("expected integer")?;
+				let v: u16 = v.try_into().map_err(|_| "expected positive integer")?;
+				if v > MAX_BITS {
+					Err("integer out of range")?;
+				}
+				let mut i = self.outputs.len();
+				self.outputs.resize_with(v.into(), || {
+					let n = BitRange::new(i as u16, NonZeroU16::new(1).unwrap());
+					i += 1;
+					n
+				});
+			}
+			name if name.starts_with("output ") => {
+				let (_, n) = name.split_once(' ').ok_or("invalid property")?;
+				let n = n.parse::<usize>().map_err(|_| "invalid property")?;
+				let r = self.outputs.get_mut(n).ok_or("invalid property")?;
+				let v = value.as_range().ok_or("expected range")?;
+				(v.end() <= MAX_BITS)
+					.then(|| *r = v)
+					.ok_or("range out of bounds")?;
+			}
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+}
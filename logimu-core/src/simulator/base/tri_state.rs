@@ -0,0 +1,67 @@
+use super::{
+	bits_property, default_delay, delay_property, mask_word, parse_bits, parse_delay, words,
+	Component, GenerateIr, InputType, IrOp, OutputType, Property, SetProperty,
+};
+use core::num::{NonZeroU16, NonZeroU8};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A buffer that only drives its output if it is enabled. If it is disabled the output is
+/// floating.
+#[derive(Serialize, Deserialize)]
+pub struct TriStateBuffer {
+	pub bits: NonZeroU16,
+	#[serde(default = "default_delay")]
+	pub delay: NonZeroU8,
+}
+
+impl TriStateBuffer {
+	pub fn new(bits: NonZeroU16) -> Self {
+		Self { bits, delay: default_delay() }
+	}
+}
+
+impl Component for TriStateBuffer {
+	fn inputs(&self) -> Box<[InputType]> {
+		let enable = InputType { bits: NonZeroU16::new(1).unwrap() };
+		[InputType { bits: self.bits }, enable].into()
+	}
+
+	fn outputs(&self) -> Box<[OutputType]> {
+		[OutputType { bits: self.bits }].into()
+	}
+
+	fn generate_ir(&self, gen: GenerateIr) -> usize {
+		let (input, enable, out) = (gen.inputs[0], gen.inputs[1], gen.outputs[0]);
+		// Don't drive the output at all if any port is not connected.
+		if input != usize::MAX && enable != usize::MAX && out != usize::MAX {
+			let ir = (0..words(self.bits.get()))
+				.flat_map(|k| {
+					[IrOp::Copy { a: input + k }]
+						.into_iter()
+						.chain(mask_word(self.bits, k))
+						.chain([IrOp::Enable { a: enable }, IrOp::Save { out: out + k }])
+				})
+				.collect();
+			(gen.out)(ir);
+		}
+		0
+	}
+
+	fn properties(&self) -> Box<[Property]> {
+		[bits_property(self.bits), delay_property(self.delay)].into()
+	}
+
+	fn set_property(&mut self, name: &str, value: SetProperty) -> Result<(), Box<dyn Error>> {
+		match name {
+			"bits" => self.bits = parse_bits(value)?,
+			"delay" => self.delay = parse_delay(value)?,
+			_ => Err("invalid property")?,
+		}
+		Ok(())
+	}
+
+	fn delay(&self) -> NonZeroU8 {
+		self.delay
+	}
+}
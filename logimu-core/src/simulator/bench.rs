@@ -0,0 +1,189 @@
+//! Tests and benchmarks on large generated circuits.
+//!
+//! Run the benchmarks with `cargo bench`.
+
+extern crate test;
+
+use super::*;
+use core::num::{NonZeroU16, NonZeroUsize};
+use std::sync::Arc;
+use test::Bencher;
+
+type G = Graph<Box<dyn Component>, (), ()>;
+
+/// Generate a circuit of random gates. The inputs of each gate are taken from the last `window`
+/// nexuses, so a larger window gives a wider and shallower circuit.
+fn random_circuit(inputs: usize, gates: usize, window: usize) -> G {
+	let mut rng = 0x9e37_79b9_7f4a_7c15_u64;
+	let mut next = |n: usize| {
+		rng ^= rng << 13;
+		rng ^= rng >> 7;
+		rng ^= rng << 17;
+		rng as usize % n
+	};
+
+	let mut graph = G::new();
+	let bits = NonZeroU16::new(16).unwrap();
+	let two = NonZeroOneU8::new(2).unwrap();
+	let mut nexuses = Vec::new();
+	for i in 0..inputs {
+		let node = graph.add(Box::new(In::new("", bits, i)), ());
+		let n = graph.new_nexus(());
+		graph
+			.connect(Port::Output { node, port: 0 }, Some(n))
+			.unwrap();
+		nexuses.push(n);
+	}
+	for _ in 0..gates {
+		let component: Box<dyn Component> = match next(4) {
+			0 => Box::new(AndGate::new(two)),
+			1 => Box::new(OrGate::new(two)),
+			2 => Box::new(XorGate::new(two)),
+			_ => Box::new(NotGate::new()),
+		};
+		let ports = component.inputs().len();
+		let node = graph.add(component, ());
+		for port in 0..ports {
+			let n = nexuses[nexuses.len() - 1 - next(nexuses.len().min(window))];
+			graph.connect(Port::Input { node, port }, Some(n)).unwrap();
+		}
+		let n = graph.new_nexus(());
+		graph
+			.connect(Port::Output { node, port: 0 }, Some(n))
+			.unwrap();
+		nexuses.push(n);
+	}
+	for (i, &n) in nexuses.iter().rev().take(32).enumerate() {
+		let node = graph.add(Box::new(Out::new("", bits, i)), ());
+		graph
+			.connect(Port::Input { node, port: 0 }, Some(n))
+			.unwrap();
+	}
+	graph
+}
+
+/// Connect an output port to input ports with a new nexus.
+fn wire(graph: &mut G, from: (GraphNodeHandle, usize), to: &[(GraphNodeHandle, usize)]) {
+	let n = Some(graph.new_nexus(()));
+	let (node, port) = from;
+	graph.connect(Port::Output { node, port }, n).unwrap();
+	for &(node, port) in to {
+		graph.connect(Port::Input { node, port }, n).unwrap();
+	}
+}
+
+/// Generate a circuit that inverts a bus, splits it into single bits, sends each bit through a
+/// chain of `depth` inverters and merges the bits again.
+fn bus_circuit(bits: u16, depth: usize) -> G {
+	let mut graph = G::new();
+	let width = NonZeroU16::new(bits).unwrap();
+	let input = graph.add(Box::new(In::new("", width, 0)), ());
+	let not = graph.add(Box::new(NotGate::new()), ());
+	let merger = graph.add(Box::new(Merger::new(width)), ());
+	let output = graph.add(Box::new(Out::new("", width, 0)), ());
+	wire(&mut graph, (input, 0), &[(not, 0)]);
+	let mut splitters = Vec::new();
+	for k in 0..bits {
+		let mut splitter = Splitter::new();
+		splitter.outputs = [BitRange::new(k, NonZeroU16::new(1).unwrap())].into();
+		let mut last = (graph.add(Box::new(splitter), ()), 0);
+		splitters.push((last.0, 0));
+		for _ in 0..depth {
+			let not = graph.add(Box::new(NotGate::new()), ());
+			wire(&mut graph, last, &[(not, 0)]);
+			last = (not, 0);
+		}
+		wire(&mut graph, last, &[(merger, k.into())]);
+	}
+	wire(&mut graph, (not, 0), &splitters);
+	wire(&mut graph, (merger, 0), &[(output, 0)]);
+	graph
+}
+
+/// Inputs for the given iteration of a test or benchmark.
+fn inputs(i: usize) -> Vec<ir::Value> {
+	(0..16)
+		.map(|k| ir::Value::Set((i.wrapping_mul(0x9e37) ^ (k * 0x3b9d)) & 0xffff))
+		.collect()
+}
+
+#[test]
+fn parallel_deterministic() {
+	let program = Arc::new(random_circuit(16, 8000, 2048).generate_ir());
+	let mut states = [1, 2, 3, 8].map(|t| {
+		let mut s = program.clone().new_state();
+		s.set_threads(NonZeroUsize::new(t).unwrap());
+		s
+	});
+	let mut outputs = [(); 4].map(|_| [ir::Value::Floating; 32]);
+	for i in 0..8 {
+		states.iter_mut().for_each(|s| s.write_inputs(&inputs(i)));
+		for _ in 0..1024 {
+			let dirty = states.iter_mut().map(|s| s.step()).collect::<Vec<_>>();
+			states
+				.iter()
+				.zip(outputs.iter_mut())
+				.for_each(|(s, o)| s.read_outputs(o));
+			assert!(dirty.iter().all(|&d| d == dirty[0]), "{:?}", dirty);
+			assert!(outputs.iter().all(|o| o == &outputs[0]));
+			if dirty[0] == 0 {
+				break;
+			}
+		}
+	}
+}
+
+#[test]
+fn bus_evaluations() {
+	let program = Arc::new(bus_circuit(32, 4).generate_ir());
+	let mut state = program.new_state();
+	state.write_inputs(&[ir::Value::Set(0)]);
+	assert!(state.run(1024).is_settled());
+	// Only the splitter reading the toggled bit, its inverters and the merger are updated.
+	state.write_inputs(&[ir::Value::Set(1 << 7)]);
+	let mut evaluations = 0;
+	loop {
+		match state.step() {
+			0 => break,
+			n => evaluations += n,
+		}
+	}
+	assert_eq!(evaluations, 1 + 4 + 1);
+	let mut out = [ir::Value::Floating];
+	state.read_outputs(&mut out);
+	assert_eq!(out, [ir::Value::Set(!(1 << 7) & 0xffff_ffff)]);
+}
+
+fn run_threads(b: &mut Bencher, threads: usize) {
+	let program = Arc::new(random_circuit(16, 50_000, 8192).generate_ir());
+	let mut state = program.new_state();
+	state.set_threads(NonZeroUsize::new(threads).unwrap());
+	let mut i = 0;
+	b.iter(|| {
+		i += 1;
+		state.write_inputs(&inputs(i));
+		state.run(1024)
+	});
+}
+
+#[bench]
+fn run_1_thread(b: &mut Bencher) {
+	run_threads(b, 1);
+}
+
+#[bench]
+fn run_4_threads(b: &mut Bencher) {
+	run_threads(b, 4);
+}
+
+#[bench]
+fn run_bus(b: &mut Bencher) {
+	let program = Arc::new(bus_circuit(32, 64).generate_ir());
+	let mut state = program.new_state();
+	let mut i = 0;
+	b.iter(|| {
+		i += 1;
+		state.write_inputs(&[ir::Value::Set(1 << (i % 32))]);
+		state.run(1024)
+	});
+}
@@ -0,0 +1,373 @@
+//! # Builder
+//!
+//! Build graphs from Rust code by declaring components and passing the nexuses they read from,
+//! instead of connecting each port by hand.
+//!
+//! ```ignore
+//! let mut b = Builder::new();
+//! let (x, y) = (b.input("X", 4), b.input("Y", 4));
+//! let z = b.xor([x, y]);
+//! b.output("Z", z);
+//! let program = b.generate_ir();
+//! ```
+
+use super::*;
+use crate::circuit::CircuitComponent;
+use core::num::NonZeroU16;
+use std::collections::BTreeMap;
+
+/// A graph of which each nexus is driven by at most one component, unless joined explicitly.
+pub struct Builder {
+	graph: Graph<Box<dyn Component>, (), ()>,
+	/// The width of each nexus.
+	bits: BTreeMap<NexusHandle, NonZeroU16>,
+	inputs: usize,
+	outputs: usize,
+}
+
+impl Builder {
+	pub fn new() -> Self {
+		Self {
+			graph: Graph::new(),
+			bits: Default::default(),
+			inputs: 0,
+			outputs: 0,
+		}
+	}
+
+	/// The amount of bits of a nexus.
+	///
+	/// # Panics
+	///
+	/// If the nexus wasn't created by this builder.
+	pub fn bits(&self, nexus: NexusHandle) -> NonZeroU16 {
+		self.bits[&nexus]
+	}
+
+	/// Create a nexus that isn't driven by anything yet. It can be driven later with
+	/// [`Self::join`], which makes it possible to build feedback loops.
+	pub fn wire(&mut self, bits: u16) -> NexusHandle {
+		let n = self.graph.new_nexus(());
+		self.bits
+			.insert(n, NonZeroU16::new(bits).expect("zero bits"));
+		n
+	}
+
+	/// Add a component, connect each input to a nexus in order and create a nexus for each
+	/// output.
+	///
+	/// # Panics
+	///
+	/// If the amount of nexuses doesn't match the amount of inputs of the component.
+	pub fn add<C>(&mut self, component: C, inputs: &[NexusHandle]) -> Box<[NexusHandle]>
+	where
+		C: Component + 'static,
+	{
+		let ports = component.inputs().len();
+		assert_eq!(ports, inputs.len(), "amount of inputs doesn't match");
+		self.add_ports(component, inputs.iter().copied().enumerate())
+	}
+
+	/// Add a component like [`Self::add`], but connect inputs by their
+	/// [name](CircuitComponent::input_name). Inputs that aren't named are left unconnected.
+	///
+	/// ```ignore
+	/// let q = b.add_named(register, &[("Data", d), ("Clock", clock)])[0];
+	/// ```
+	///
+	/// # Panics
+	///
+	/// If the component has no input with one of the names.
+	pub fn add_named<C>(
+		&mut self,
+		component: C,
+		inputs: &[(&str, NexusHandle)],
+	) -> Box<[NexusHandle]>
+	where
+		C: CircuitComponent + 'static,
+	{
+		let names = (0..component.inputs().len())
+			.map(|i| component.input_name(i))
+			.collect::<Vec<_>>();
+		let inputs = inputs
+			.iter()
+			.map(|&(name, n)| match names.iter().position(|p| &**p == name) {
+				Some(port) => (port, n),
+				None => panic!("no input named {:?}", name),
+			})
+			.collect::<Vec<_>>();
+		self.add_ports(component, inputs)
+	}
+
+	/// Add a component, connect the given inputs and create a nexus for each output.
+	fn add_ports<C>(
+		&mut self,
+		component: C,
+		inputs: impl IntoIterator<Item = (usize, NexusHandle)>,
+	) -> Box<[NexusHandle]>
+	where
+		C: Component + 'static,
+	{
+		let outputs = component.outputs();
+		let node = self.graph.add(Box::new(component), ());
+		for (port, n) in inputs {
+			self.graph
+				.connect(Port::Input { node, port }, Some(n))
+				.unwrap();
+		}
+		outputs
+			.iter()
+			.enumerate()
+			.map(|(port, t)| {
+				let n = self.wire(t.bits.get());
+				self.graph
+					.connect(Port::Output { node, port }, Some(n))
+					.unwrap();
+				n
+			})
+			.collect()
+	}
+
+	/// Add a component with a single output.
+	fn add_one<C>(&mut self, component: C, inputs: &[NexusHandle]) -> NexusHandle
+	where
+		C: Component + 'static,
+	{
+		self.add(component, inputs)[0]
+	}
+
+	/// Drive `wire` with the value of `value`. `value` is no longer valid afterwards.
+	///
+	/// # Panics
+	///
+	/// If both are the same nexus.
+	pub fn join(&mut self, wire: NexusHandle, value: NexusHandle) {
+		self.graph.merge_nexuses(wire, value, |_, _| ()).unwrap();
+		let bits = self.bits.remove(&value).unwrap();
+		let w = self.bits.get_mut(&wire).unwrap();
+		*w = (*w).max(bits);
+	}
+
+	/// Add an external input with the next free index.
+	pub fn input(&mut self, name: &str, bits: u16) -> NexusHandle {
+		let bits = NonZeroU16::new(bits).expect("zero bits");
+		self.inputs += 1;
+		self.add_one(In::new(name, bits, self.inputs - 1), &[])
+	}
+
+	/// Add an external output with the next free index and the width of the nexus.
+	pub fn output(&mut self, name: &str, nexus: NexusHandle) {
+		self.outputs += 1;
+		let out = Out::new(name, self.bits(nexus), self.outputs - 1);
+		self.add(out, &[nexus]);
+	}
+
+	pub fn constant(&mut self, bits: u16, value: usize) -> NexusHandle {
+		let bits = NonZeroU16::new(bits).expect("zero bits");
+		self.add_one(Constant::new(bits, value), &[])
+	}
+
+	pub fn and(&mut self, inputs: impl IntoIterator<Item = NexusHandle>) -> NexusHandle {
+		let inputs = inputs.into_iter().collect::<Vec<_>>();
+		let mut gate = AndGate::new(self.gate_inputs(&inputs));
+		gate.bits = self.widest(&inputs);
+		self.add_one(gate, &inputs)
+	}
+
+	pub fn or(&mut self, inputs: impl IntoIterator<Item = NexusHandle>) -> NexusHandle {
+		let inputs = inputs.into_iter().collect::<Vec<_>>();
+		let mut gate = OrGate::new(self.gate_inputs(&inputs));
+		gate.bits = self.widest(&inputs);
+		self.add_one(gate, &inputs)
+	}
+
+	pub fn xor(&mut self, inputs: impl IntoIterator<Item = NexusHandle>) -> NexusHandle {
+		let inputs = inputs.into_iter().collect::<Vec<_>>();
+		let mut gate = XorGate::new(self.gate_inputs(&inputs));
+		gate.bits = self.widest(&inputs);
+		self.add_one(gate, &inputs)
+	}
+
+	pub fn not(&mut self, input: NexusHandle) -> NexusHandle {
+		let mut gate = NotGate::new();
+		gate.bits = self.bits(input);
+		self.add_one(gate, &[input])
+	}
+
+	/// Drive the output with `input` if `enable` is high, otherwise leave it floating.
+	pub fn tri_state(&mut self, input: NexusHandle, enable: NexusHandle) -> NexusHandle {
+		self.add_one(TriStateBuffer::new(self.bits(input)), &[input, enable])
+	}
+
+	/// Take a range of bits of a nexus.
+	pub fn slice(&mut self, input: NexusHandle, start: u16, bits: u16) -> NexusHandle {
+		self.split(input, &[(start, bits)])[0]
+	}
+
+	/// Take a single bit of a nexus.
+	pub fn bit(&mut self, input: NexusHandle, bit: u16) -> NexusHandle {
+		self.slice(input, bit, 1)
+	}
+
+	/// Split a nexus into ranges of bits given as the first bit and the amount of bits.
+	pub fn split(&mut self, input: NexusHandle, ranges: &[(u16, u16)]) -> Box<[NexusHandle]> {
+		let mut splitter = Splitter::new();
+		splitter.outputs = ranges
+			.iter()
+			.map(|&(s, b)| BitRange::new(s, NonZeroU16::new(b).expect("zero bits")))
+			.collect();
+		self.add(splitter, &[input])
+	}
+
+	/// Concatenate nexuses. The first nexus ends up in the lowest bits.
+	pub fn merge(&mut self, inputs: impl IntoIterator<Item = NexusHandle>) -> NexusHandle {
+		let inputs = inputs.into_iter().collect::<Vec<_>>();
+		let mut start = 0;
+		let ranges = inputs
+			.iter()
+			.map(|&n| {
+				start += self.bits(n).get();
+				BitRange::new(start - self.bits(n).get(), self.bits(n))
+			})
+			.collect();
+		let mut merger = Merger::new(NonZeroU16::new(start).expect("no inputs"));
+		merger.inputs = ranges;
+		self.add_one(merger, &inputs)
+	}
+
+	/// The graph built so far.
+	pub fn graph(&self) -> &Graph<Box<dyn Component>, (), ()> {
+		&self.graph
+	}
+
+	pub fn into_graph(self) -> Graph<Box<dyn Component>, (), ()> {
+		self.graph
+	}
+
+	/// Generate IR to simulate the graph built so far.
+	pub fn generate_ir(&self) -> Program {
+		self.graph.generate_ir()
+	}
+
+	fn gate_inputs(&self, inputs: &[NexusHandle]) -> NonZeroOneU8 {
+		let n = inputs.len().try_into().ok().and_then(NonZeroOneU8::new);
+		n.expect("gates need at least 2 inputs")
+	}
+
+	fn widest(&self, inputs: &[NexusHandle]) -> NonZeroU16 {
+		inputs.iter().map(|&n| self.bits(n)).max().unwrap()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::sync::Arc;
+
+	/// The same circuit as `graph::test::manual_xor`.
+	#[test]
+	fn xor() {
+		let mut b = Builder::new();
+		let (i0, i1) = (b.input("I0", 4), b.input("I1", 4));
+		let and = b.and([i0, i1]);
+		let nand = b.not(and);
+		let or = b.or([i0, i1]);
+		let o0 = b.and([nand, or]);
+		let o1 = b.xor([i0, i1]);
+		b.output("O0", o0);
+		b.output("O1", o1);
+
+		let mut state = Arc::new(b.generate_ir()).new_state();
+		let (a, c) = (0b1100, 0b0110);
+		state.write_inputs(&[ir::Value::Set(a), ir::Value::Set(c)]);
+		assert!(state.run(1024).is_settled());
+		let mut out = [ir::Value::Floating; 2];
+		state.read_outputs(&mut out);
+		assert_eq!(out, [ir::Value::Set(a ^ c); 2]);
+	}
+
+	/// Generate an N-bit ripple-carry adder out of full adders.
+	#[test]
+	fn ripple_carry_adder() {
+		const N: u16 = 12;
+		let mut b = Builder::new();
+		let (x, y) = (b.input("X", N), b.input("Y", N));
+		let mut carry = b.constant(1, 0);
+		let mut sum = Vec::new();
+		for i in 0..N {
+			let (xi, yi) = (b.bit(x, i), b.bit(y, i));
+			let half = b.xor([xi, yi]);
+			sum.push(b.xor([half, carry]));
+			let (g, p) = (b.and([xi, yi]), b.and([half, carry]));
+			carry = b.or([g, p]);
+		}
+		let sum = b.merge(sum);
+		b.output("S", sum);
+		b.output("C", carry);
+
+		let mut state = Arc::new(b.generate_ir()).new_state();
+		let mut out = [ir::Value::Floating; 2];
+		for (x, y) in [(0, 0), (1, 1), (1234, 2345), (4095, 1), (4095, 4095)] {
+			state.write_inputs(&[ir::Value::Set(x), ir::Value::Set(y)]);
+			assert!(state.run(1024).is_settled());
+			state.read_outputs(&mut out);
+			let (s, c) = ((x + y) & 0xfff, (x + y) >> N);
+			assert_eq!(out, [ir::Value::Set(s), ir::Value::Set(c)]);
+		}
+	}
+
+	/// An SR latch made of two cross-coupled NOR gates.
+	#[test]
+	fn sr_latch() {
+		let mut b = Builder::new();
+		let (s, r) = (b.input("S", 1), b.input("R", 1));
+		let q = b.wire(1);
+		let or = b.or([s, q]);
+		let nq = b.not(or);
+		let or = b.or([r, nq]);
+		let value = b.not(or);
+		b.join(q, value);
+		b.output("Q", q);
+
+		let mut state = Arc::new(b.generate_ir()).new_state();
+		let mut out = [ir::Value::Floating];
+		let mut test = |s, r, expect| {
+			state.write_inputs(&[ir::Value::Set(s), ir::Value::Set(r)]);
+			assert!(state.run(1024).is_settled());
+			state.read_outputs(&mut out);
+			assert_eq!(out, [ir::Value::Set(expect)]);
+		};
+		test(1, 0, 1);
+		test(0, 0, 1);
+		test(0, 1, 0);
+		test(0, 0, 0);
+	}
+
+	/// A register with only its data and clock connected, by name.
+	#[test]
+	fn named_ports() {
+		let mut b = Builder::new();
+		let (d, clock) = (b.input("D", 8), b.input("C", 1));
+		let register = Register::new(NonZeroU16::new(8).unwrap());
+		let q = b.add_named(register, &[("Clock", clock), ("Data", d)])[0];
+		b.output("Q", q);
+
+		let mut state = Arc::new(b.generate_ir()).new_state();
+		let mut out = [ir::Value::Floating];
+		let (f, set) = (ir::Value::Floating, ir::Value::Set);
+		for (d, clock, expect) in [(0x12, 0, f), (0x12, 1, set(0x12)), (0x34, 0, set(0x12))] {
+			state.write_inputs(&[ir::Value::Set(d), ir::Value::Set(clock)]);
+			assert!(state.run(1024).is_settled());
+			state.read_outputs(&mut out);
+			assert_eq!(out, [expect]);
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "no input named")]
+	fn unknown_port() {
+		let mut b = Builder::new();
+		let d = b.input("D", 1);
+		b.add_named(DFlipFlop::default(), &[("E", d)]);
+	}
+}
@@ -0,0 +1,252 @@
+use super::ir::{self, IrOp, MAX_WORDS};
+use crate::impl_dyn;
+use core::fmt;
+use core::num::{NonZeroU16, NonZeroU32, NonZeroU8};
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A single component with one or more inputs and outputs.
+pub trait Component {
+	/// The label attached to this component.
+	fn label(&self) -> Option<&str> {
+		None
+	}
+
+	/// The types of inputs.
+	fn inputs(&self) -> Box<[InputType]>;
+
+	/// The types of outputs.
+	fn outputs(&self) -> Box<[OutputType]>;
+
+	/// Generate IR for this component.
+	fn generate_ir(&self, generate: GenerateIr) -> usize;
+
+	/// Return all properties of this component.
+	fn properties(&self) -> Box<[Property]>;
+
+	/// Set a property of this component.
+	fn set_property(&mut self, name: &str, property: SetProperty) -> Result<(), Box<dyn Error>>;
+
+	/// Whether this component is an In, Out or regular component.
+	fn external_type(&self) -> Option<ExternalType> {
+		None
+	}
+
+	/// The amount of steps it takes for a change of the inputs to affect the outputs.
+	fn delay(&self) -> NonZeroU8 {
+		NonZeroU8::new(1).unwrap()
+	}
+
+	/// The bits of each word of each input that can affect the outputs. Changes of other bits
+	/// don't cause the component to be updated.
+	fn input_masks(&self) -> Box<[[usize; MAX_WORDS]]> {
+		self.inputs().iter().map(InputType::masks).collect()
+	}
+}
+
+/// An external input or output with its index and amount of bits.
+pub enum ExternalType {
+	In(usize, NonZeroU16),
+	Out(usize, NonZeroU16),
+}
+
+#[derive(Clone, Debug)]
+pub struct Property {
+	pub name: Box<str>,
+	pub read_only: bool,
+	pub value: PropertyValue,
+}
+
+impl Property {
+	pub fn new(name: impl Into<Box<str>>, value: impl Into<PropertyValue>) -> Self {
+		Self { name: name.into(), value: value.into(), read_only: false }
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum PropertyValue {
+	Int { value: i64, range: RangeInclusive<i64> },
+	Str { value: Box<str> },
+	Range { value: BitRange },
+}
+
+#[derive(Clone, Debug)]
+pub enum SetProperty {
+	Int(i64),
+	Str(Box<str>),
+	Range(BitRange),
+}
+
+impl SetProperty {
+	pub fn as_int(&self) -> Option<i64> {
+		match self {
+			Self::Int(i) => Some(*i),
+			_ => None,
+		}
+	}
+
+	pub fn into_str(self) -> Option<Box<str>> {
+		match self {
+			Self::Str(s) => Some(s),
+			_ => None,
+		}
+	}
+
+	pub fn as_range(&self) -> Option<BitRange> {
+		match self {
+			Self::Range(r) => Some(*r),
+			_ => None,
+		}
+	}
+}
+
+impl_dyn! {
+	Component for Box<dyn Component> {
+		ref inputs() -> Box<[InputType]>;
+		ref outputs() -> Box<[OutputType]>;
+		ref generate_ir(gen: GenerateIr) -> usize;
+		ref properties() -> Box<[Property]>;
+		ref external_type() -> Option<ExternalType>;
+		ref delay() -> NonZeroU8;
+		ref input_masks() -> Box<[[usize; MAX_WORDS]]>;
+		mut set_property(name: &str, value: SetProperty) -> Result<(), Box<dyn Error>>;
+	}
+}
+
+/// The type of an input.
+pub struct InputType {
+	/// How many bits this input has.
+	pub bits: NonZeroU16,
+}
+
+impl InputType {
+	/// A mask of all bits of this input in each word.
+	pub fn masks(&self) -> [usize; MAX_WORDS] {
+		core::array::from_fn(|k| ir::word_mask(self.bits.get(), k))
+	}
+}
+
+/// The type of an output.
+pub struct OutputType {
+	/// How many bits this output has.
+	pub bits: NonZeroU16,
+}
+
+pub struct GenerateIr<'a> {
+	pub inputs: &'a [usize],
+	pub outputs: &'a [usize],
+	pub out: &'a mut dyn FnMut(Vec<IrOp>),
+	/// Override the delay of the n-th node output by this component. Nodes have the delay of the
+	/// component by default.
+	pub set_delay: &'a mut dyn FnMut(usize, NonZeroU8),
+	/// Drive the memory at the given address with a clock that is low and high for the given
+	/// amount of ticks.
+	pub clock: &'a mut dyn FnMut(usize, NonZeroU32, NonZeroU32),
+	/// Register memory that can be inspected and edited while simulating, given as the first word,
+	/// the amount of entries and the amount of bits of each entry. Word `k` of entry `i` must be
+	/// at `base + k * len + i`.
+	pub memory: &'a mut dyn FnMut(usize, usize, NonZeroU16),
+	pub memory_size: usize,
+	pub nodes: usize,
+}
+
+/// A contiguous range of bits of a bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BitRangeRepr")]
+pub struct BitRange {
+	/// The first bit.
+	pub start: u16,
+	/// The amount of bits.
+	pub bits: NonZeroU16,
+}
+
+/// Older circuits store ranges as masks.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BitRangeRepr {
+	Mask(usize),
+	Range { start: u16, bits: NonZeroU16 },
+}
+
+impl TryFrom<BitRangeRepr> for BitRange {
+	type Error = &'static str;
+
+	fn try_from(r: BitRangeRepr) -> Result<Self, Self::Error> {
+		match r {
+			BitRangeRepr::Mask(m) => {
+				let (start, bits) = (
+					m.trailing_zeros(),
+					(m >> m.trailing_zeros()).trailing_ones(),
+				);
+				(m != 0 && bits == m.count_ones())
+					.then(|| Self::new(start as u16, NonZeroU16::new(bits as u16).unwrap()))
+					.ok_or("mask is not contiguous")
+			}
+			BitRangeRepr::Range { start, bits } => Ok(Self::new(start, bits)),
+		}
+	}
+}
+
+impl BitRange {
+	pub fn new(start: u16, bits: NonZeroU16) -> Self {
+		Self { start, bits }
+	}
+
+	/// The bit after the last bit.
+	pub fn end(&self) -> u16 {
+		self.start + self.bits.get()
+	}
+
+	/// A mask of the bits in the range in word `k`.
+	pub fn mask(&self, k: usize) -> usize {
+		ir::word_mask(self.end(), k) & !ir::word_mask(self.start, k)
+	}
+}
+
+impl fmt::Display for BitRange {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.bits.get() {
+			1 => write!(f, "{}", self.start),
+			n => write!(f, "{}-{}", self.start, self.start + n - 1),
+		}
+	}
+}
+
+impl FromStr for BitRange {
+	type Err = Box<dyn Error>;
+
+	/// Parse a range as `first-last` or a single bit.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (min, max) = s.split_once('-').unwrap_or((s, s));
+		let (min, max) = (min.trim().parse::<u16>()?, max.trim().parse::<u16>()?);
+		let bits = max.checked_sub(min).and_then(|n| n.checked_add(1));
+		let bits = bits
+			.and_then(NonZeroU16::new)
+			.ok_or("last bit is before first bit")?;
+		Ok(Self::new(min, bits))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn bit_range() {
+		let r = BitRange::new(60, NonZeroU16::new(8).unwrap());
+		assert_eq!((r.mask(0), r.mask(1)), (0xf << 60, 0xf));
+		assert_eq!(r.to_string(), "60-67");
+		assert_eq!("60-67".parse::<BitRange>().unwrap(), r);
+		assert!("3-2".parse::<BitRange>().is_err());
+		// Older circuits store contiguous masks.
+		assert_eq!(
+			ron::from_str::<BitRange>("48").unwrap(),
+			"4-5".parse().unwrap()
+		);
+		assert!(ron::from_str::<BitRange>("5").is_err());
+		let s = ron::to_string(&r).unwrap();
+		assert_eq!(ron::from_str::<BitRange>(&s).unwrap(), r);
+	}
+}
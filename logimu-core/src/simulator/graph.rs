@@ -0,0 +1,855 @@
+use super::*;
+use crate::arena::{Arena, Handle};
+use core::iter;
+use core::mem;
+use core::num::NonZeroU8;
+use ir::{words, MAX_WORDS};
+use std::collections::BTreeMap;
+
+/// A graph of connected components
+pub struct Graph<C, Uc, Un>
+where
+	C: Component,
+{
+	/// All components in this graph.
+	nodes: Arena<Node<C, Uc>>,
+	/// All nexuses in this graph with a list of connected nodes.
+	nexuses: Arena<Nexus<Un>>,
+	outputs: Vec<Handle>,
+	inputs: Vec<Handle>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct GraphNodeHandle(Handle);
+
+/// A nexus is a single point connecting multiple ports. It can have only one value at any time.
+///
+/// It is equivalent to a collection of interconnected wires in a circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct NexusHandle(Handle);
+
+impl NexusHandle {
+	pub fn index(self) -> usize {
+		self.0.index()
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Port {
+	Input { node: GraphNodeHandle, port: usize },
+	Output { node: GraphNodeHandle, port: usize },
+}
+
+impl Port {
+	pub fn node(self) -> GraphNodeHandle {
+		match self {
+			Self::Input { node, .. } | Self::Output { node, .. } => node,
+		}
+	}
+}
+
+pub struct Node<C, Uc>
+where
+	C: Component,
+{
+	pub component: C,
+	inputs: Box<[Option<NexusHandle>]>,
+	outputs: Box<[Option<NexusHandle>]>,
+	pub userdata: Uc,
+}
+
+pub struct Nexus<Un> {
+	/// A list of components feeding into this nexus.
+	inputs: Vec<GraphNodeHandle>,
+	/// A list of components reading from this nexus.
+	outputs: Vec<GraphNodeHandle>,
+	pub userdata: Un,
+}
+
+impl<C, Uc, Un> Graph<C, Uc, Un>
+where
+	C: Component,
+{
+	pub fn new() -> Self {
+		Self {
+			nodes: Default::default(),
+			nexuses: Default::default(),
+			inputs: Default::default(),
+			outputs: Default::default(),
+		}
+	}
+
+	pub fn add(&mut self, component: C, userdata: Uc) -> GraphNodeHandle {
+		let ic = component.inputs().len();
+		let oc = component.outputs().len();
+		let node = Node {
+			component,
+			inputs: iter::repeat(None).take(ic).collect(),
+			outputs: iter::repeat(None).take(oc).collect(),
+			userdata,
+		};
+
+		let i = self.nodes.insert(node);
+		// FIXME unreliable
+		assert!(ic != 0 || oc != 0, "impossible");
+		if ic == 0 {
+			self.inputs.push(i);
+		}
+		if oc == 0 {
+			self.outputs.push(i);
+		}
+		GraphNodeHandle(i)
+	}
+
+	pub fn new_nexus(&mut self, userdata: Un) -> NexusHandle {
+		let nexus = Nexus { inputs: Vec::new(), outputs: Vec::new(), userdata };
+		NexusHandle(self.nexuses.insert(nexus))
+	}
+
+	pub fn merge_nexuses<F>(
+		&mut self,
+		keep: NexusHandle,
+		merge: NexusHandle,
+		merge_userdata: F,
+	) -> Result<(), MergeNexusError>
+	where
+		F: FnOnce(&mut Un, Un),
+	{
+		if merge == keep {
+			Err(MergeNexusError::SameNexus)?;
+		}
+		let mg = self
+			.nexuses
+			.remove(merge.0)
+			.ok_or(MergeNexusError::InvalidNexus)?;
+		let kp = self
+			.nexuses
+			.get_mut(keep.0)
+			.ok_or(MergeNexusError::InvalidNexus)?;
+		for i in mg.inputs.iter() {
+			let node = self.nodes.get_mut(i.0).unwrap();
+			*node
+				.outputs
+				.iter_mut()
+				.find(|h| **h == Some(merge))
+				.unwrap() = Some(keep);
+		}
+		for o in mg.outputs.iter() {
+			let node = self.nodes.get_mut(o.0).unwrap();
+			*node.inputs.iter_mut().find(|h| **h == Some(merge)).unwrap() = Some(keep);
+		}
+		// The ports of the merged nexus are now connected to the kept nexus.
+		kp.inputs.extend(mg.inputs);
+		kp.outputs.extend(mg.outputs);
+		merge_userdata(&mut kp.userdata, mg.userdata);
+		Ok(())
+	}
+
+	pub fn get(&self, handle: GraphNodeHandle) -> Option<(&C, &Uc)> {
+		self.nodes
+			.get(handle.0)
+			.map(|n| (&n.component, &n.userdata))
+	}
+
+	pub fn get_mut(&mut self, handle: GraphNodeHandle) -> Option<(&mut C, &mut Uc)> {
+		self.nodes
+			.get_mut(handle.0)
+			.map(|n| (&mut n.component, &mut n.userdata))
+	}
+
+	pub fn remove(&mut self, component: GraphNodeHandle) -> Result<(), RemoveError> {
+		// Remove the component itself
+		let node = self
+			.nodes
+			.remove(component.0)
+			.ok_or(RemoveError::InvalidNode)?;
+
+		// Disconnect from any nexuses
+		for i in node.inputs.into_iter().filter_map(|i| *i) {
+			let outp = &mut self.nexuses[i.0].outputs;
+			outp.remove(outp.iter().position(|e| *e == component).unwrap());
+		}
+		for o in node.outputs.into_iter().filter_map(|i| *i) {
+			let inp = &mut self.nexuses[o.0].inputs;
+			inp.remove(inp.iter().position(|e| *e == component).unwrap());
+		}
+
+		// Remove from inputs and/or outputs list if it is one.
+		self.inputs
+			.iter()
+			.position(|e| *e == component.0)
+			.map(|i| self.inputs.remove(i));
+		self.outputs
+			.iter()
+			.position(|e| *e == component.0)
+			.map(|i| self.outputs.remove(i));
+
+		Ok(())
+	}
+
+	pub fn remove_nexus(&mut self, handle: NexusHandle) -> Result<(), RemoveNexusError> {
+		// Remove the nexus itself
+		let nexus = self
+			.nexuses
+			.remove(handle.0)
+			.ok_or(RemoveNexusError::InvalidNexus)?;
+
+		// Disconnect from any components
+		for i in nexus.inputs {
+			let outp = &mut self.nodes[i.0].outputs;
+			outp[outp.iter().position(|e| *e == Some(handle)).unwrap()] = None;
+		}
+		for o in nexus.outputs {
+			let inp = &mut self.nodes[o.0].inputs;
+			inp[inp.iter().position(|e| *e == Some(handle)).unwrap()] = None;
+		}
+
+		Ok(())
+	}
+
+	/// Connect a node's port to a nexus, or disconnect by specifying None.
+	pub fn connect(
+		&mut self,
+		port: Port,
+		nexus: Option<NexusHandle>,
+	) -> Result<Option<NexusHandle>, ConnectError> {
+		let nod = self
+			.nodes
+			.get_mut(port.node().0)
+			.ok_or(ConnectError::InvalidNode)?;
+		let nex = nexus
+			.map(|n| self.nexuses.get_mut(n.0).ok_or(ConnectError::InvalidNexus))
+			.transpose()?;
+		match port {
+			Port::Input { port, node } => {
+				nex.map(|n| n.outputs.push(node));
+				let e = nod.inputs.get_mut(port).ok_or(ConnectError::InvalidPort)?;
+				if let Some(e) = e {
+					let n = &mut self.nexuses[e.0];
+					n.outputs
+						.remove(n.outputs.iter().position(|e| *e == node).unwrap());
+				}
+				Ok(mem::replace(e, nexus))
+			}
+			Port::Output { port, node } => {
+				nex.map(|n| n.inputs.push(node));
+				let e = nod.outputs.get_mut(port).ok_or(ConnectError::InvalidPort)?;
+				if let Some(e) = e {
+					let n = &mut self.nexuses[e.0];
+					n.inputs
+						.remove(n.inputs.iter().position(|e| *e == node).unwrap());
+				}
+				Ok(mem::replace(e, nexus))
+			}
+		}
+	}
+
+	/// Generate IR to simulate this graph.
+	///
+	/// # Process
+	///
+	/// - IR is generated for all components.
+	/// - Nexuses with multiple drivers get a node resolving the value of each driver.
+	/// - Nexuses are iterated and IR is amended with checks.
+	/// - IR are put into nodes.
+	/// - Program is generated.
+	///
+	/// # Example: D flip-flop
+	///
+	/// ```text
+	///                       ________   ___b___ _______
+	///     D ---+-----------o        \ /       o       \
+	///          |           | AND (1) o        | OR (1) o--d--o NOT (1) o--- Q
+	/// Clock ---------+-----o________/   nQ -> o_______/
+	///          |     |      ________           _______
+	///          |     +-----o        \    Q -> o       \
+	///          |           | AND (2) o        | OR (2) o--e--o NOT (2) o--- nQ
+	///          o NOT o--a--o________/ \___c___o_______/
+	/// ```
+	pub fn generate_ir(&self) -> Program {
+		// Map nexuses to memory. Each nexus gets enough words for the widest port connected to it.
+		let len = self.nexuses.iter().map(|(h, _)| h.index() + 1).max();
+		let mut widths = vec![1; len.unwrap_or(0)];
+		for (_, node) in self.nodes.iter() {
+			let (inputs, outputs) = (node.component.inputs(), node.component.outputs());
+			let inputs = node.inputs.iter().zip(inputs.iter().map(|t| t.bits));
+			let outputs = node.outputs.iter().zip(outputs.iter().map(|t| t.bits));
+			for (n, bits) in inputs.chain(outputs) {
+				if let Some(n) = n {
+					widths[n.index()] = widths[n.index()].max(bits.get());
+				}
+			}
+		}
+		let mut memory_size = 0;
+		let nexus_map = widths
+			.into_iter()
+			.map(|bits| {
+				memory_size += words(bits);
+				(memory_size - words(bits), bits)
+			})
+			.collect::<Box<[_]>>();
+		let nexus_to_mem = |h: NexusHandle| nexus_map[h.index()].0;
+		let nexus_words = |h: NexusHandle| words(nexus_map[h.index()].1);
+
+		// Generate IR
+		let mut input_map = Vec::new();
+		let mut output_map = Vec::new();
+		let mut ir = Vec::new();
+		// Delays of nodes that differ from the delay of their component.
+		let mut delays = Vec::new();
+		let mut clocks = Vec::new();
+		let mut memories = Vec::new();
+		let mut component_map = Vec::new();
+		// Nexuses with multiple drivers and the memory each driver writes to.
+		let mut shared = BTreeMap::<NexusHandle, Vec<(GraphNodeHandle, usize)>>::new();
+		for (h, Node { inputs, outputs, component, .. }) in self.nodes.iter() {
+			let inp = inputs
+				.iter()
+				.map(|n| n.map(nexus_to_mem).unwrap_or(usize::MAX))
+				.collect::<Box<_>>();
+			let mut outp = Vec::with_capacity(outputs.len());
+			for n in outputs.iter() {
+				outp.push(match *n {
+					Some(n) if self.nexuses[n.0].inputs.len() > 1 => {
+						// Give each driver its own memory so the values can be resolved later.
+						memory_size += nexus_words(n);
+						let d = shared.entry(n).or_default();
+						d.push((GraphNodeHandle(h), memory_size - nexus_words(n)));
+						memory_size - nexus_words(n)
+					}
+					Some(n) => nexus_to_mem(n),
+					None => usize::MAX,
+				});
+			}
+
+			// FIXME this is a quick hack to get things working. It is a very ugly
+			// solution.
+			if let Some(io) = component.external_type() {
+				let (m, i, a, bits) = match io {
+					super::ExternalType::In(i, b) => (&mut input_map, i, outp[0], b),
+					super::ExternalType::Out(i, b) => (&mut output_map, i, inp[0], b),
+				};
+				m.resize(m.len().max(i + 1), (usize::MAX, 0));
+				if a != usize::MAX {
+					m[i] = (a, bits.get());
+				}
+			}
+			let nodes = ir.len();
+			let delay = component.delay();
+			let gen = GenerateIr {
+				inputs: &inp,
+				outputs: &outp,
+				out: &mut |ops| ir.push((h, ops, delay)),
+				set_delay: &mut |n, d| delays.push((nodes + n, d)),
+				clock: &mut |out, low, high| clocks.push((out, low, high)),
+				memory: &mut |base, len, bits| {
+					let bits = bits.get();
+					memories.push((GraphNodeHandle(h), ir::Region { base, len, bits }))
+				},
+				memory_size,
+				nodes,
+			};
+			let base = memory_size;
+			memory_size += component.generate_ir(gen);
+			if memory_size > base {
+				component_map.resize(component_map.len().max(h.index() + 1), (0, 0));
+				component_map[h.index()] = (base, memory_size - base);
+			}
+		}
+
+		delays.into_iter().for_each(|(n, d)| ir[n].2 = d);
+
+		let ir_search = |ir: &Vec<_>, h: &GraphNodeHandle| {
+			if let Ok(i) = ir.binary_search_by_key(&h.0, |&(h, _, _)| h) {
+				let mut l @ mut r = i;
+				while ir.get(l.wrapping_sub(1)).map_or(false, |e| e.0 == h.0) {
+					l -= 1;
+				}
+				while ir.get(r).map_or(false, |e| e.0 == h.0) {
+					r += 1;
+				}
+				l..r
+			} else {
+				0..0
+			}
+		};
+
+		// Resolve the values of nexuses with multiple drivers.
+		let mut resolve = Vec::new();
+		for (&n, drivers) in shared.iter() {
+			let node = ir.len() + resolve.len();
+			let mut ops = Vec::new();
+			for k in 0..nexus_words(n) {
+				let mut it = drivers.iter().map(|&(_, a)| a + k);
+				let it = it
+					.next()
+					.map(|a| IrOp::Copy { a })
+					.into_iter()
+					.chain(it.map(|a| IrOp::Resolve { a }))
+					.chain(Some(IrOp::Save { out: nexus_to_mem(n) + k }));
+				ops.extend(it);
+			}
+			resolve.push((n, node, ops));
+			for &(dh, a) in drivers.iter() {
+				for i in ir_search(&ir, &dh) {
+					for k in 0..nexus_words(n) {
+						ir[i]
+							.1
+							.push(IrOp::CheckDirty { a: a + k, node, mask: usize::MAX });
+					}
+				}
+			}
+		}
+
+		// Amend IR
+		let mut input_nodes_map = Vec::new();
+		for (h, Nexus { inputs, outputs, .. }) in self.nexuses.iter() {
+			let (a, bits) = nexus_map[h.index()];
+			let words = words(bits);
+			// Readers only need an update if any of the bits they use changes.
+			let mask = |r: &GraphNodeHandle| {
+				let node = &self.nodes[r.0];
+				let masks = node.component.input_masks();
+				let ports = node.inputs.iter().zip(masks.iter());
+				ports
+					.filter(|(n, _)| **n == Some(NexusHandle(h)))
+					.fold([0; MAX_WORDS], |m, (_, k)| {
+						core::array::from_fn(|i| m[i] | k[i])
+					})
+			};
+			let readers = outputs
+				.iter()
+				.flat_map(|r| ir_search(&ir, r).map(move |o| (o, mask(r))))
+				.collect::<Box<[_]>>();
+			let checks = (0..words).flat_map(|k| {
+				let readers = readers.iter().filter(move |(_, m)| m[k] != 0);
+				readers.map(move |&(o, m)| IrOp::CheckDirty { a: a + k, node: o, mask: m[k] })
+			});
+			let r = resolve.iter().position(|(n, ..)| n.0 == h);
+			if let Some(r) = r {
+				resolve[r].2.extend(checks);
+			} else {
+				for ih in inputs.iter() {
+					// Can be none for noop components such as In or Out
+					for i in ir_search(&ir, ih) {
+						ir[i].1.extend(checks.clone());
+					}
+				}
+			}
+			// Inputs mark readers dirty if any of the bits they use changes in any word.
+			let readers = readers
+				.iter()
+				.map(|&(o, m)| (o, m.iter().fold(0, |a, b| a | b)))
+				.collect::<Box<[_]>>();
+			for ih in inputs.iter() {
+				if let Some(et) = self.nodes[ih.0].component.external_type() {
+					match et {
+						super::ExternalType::In(i, _) => {
+							input_nodes_map
+								.resize_with(input_nodes_map.len().max(i + 1), Box::default);
+							// Inputs on a nexus with multiple drivers only affect the resolver.
+							input_nodes_map[i] = r.map_or_else(
+								|| readers.clone(),
+								|r| [(resolve[r].1, usize::MAX)].into(),
+							);
+						}
+						super::ExternalType::Out(..) => (),
+					}
+				}
+			}
+		}
+
+		// Map nodes and memory back to the graph to be able to report oscillations.
+		let components = ir
+			.iter()
+			.map(|&(h, _, _)| Some(GraphNodeHandle(h)))
+			.chain(resolve.iter().map(|_| None))
+			.collect();
+		let mut nexuses = vec![None; memory_size];
+		for (h, _) in self.nexuses.iter() {
+			let (a, bits) = nexus_map[h.index()];
+			let words = words(bits);
+			nexuses[a..a + words].fill(Some(NexusHandle(h)));
+		}
+		for (&n, d) in shared.iter() {
+			d.iter()
+				.for_each(|&(_, a)| nexuses[a..a + nexus_words(n)].fill(Some(n)));
+		}
+
+		// Create program
+		let drivers = shared
+			.into_iter()
+			.map(|(n, d)| (nexus_to_mem(n), d.into_iter().map(|(_, a)| a).collect()))
+			.collect();
+		// Resolvers aren't components, so they don't have a delay of their own.
+		let unit = NonZeroU8::new(1).unwrap();
+		let nodes = ir
+			.into_iter()
+			.map(|(_, ir, delay)| (ir, delay))
+			.chain(resolve.into_iter().map(|(.., ir)| (ir, unit)))
+			.map(|(ir, delay)| super::ir::Node { ir: ir.into(), delay })
+			.collect::<Box<[_]>>();
+		// Clocks can also be used inside ICs, so find the readers from the generated IR.
+		let clocks = clocks
+			.into_iter()
+			.map(|(out, low, high)| {
+				let reads =
+					|n: &super::ir::Node| n.ir.iter().flat_map(IrOp::reads).any(|a| a == out);
+				let nodes = (0..nodes.len()).filter(|&n| reads(&nodes[n])).collect();
+				super::ir::program::ClockSource { out, high, low, nodes }
+			})
+			.collect();
+		Program {
+			clocks,
+			components,
+			nexuses: nexuses.into(),
+			memory_size,
+			input_map: input_map.into(),
+			output_map: output_map.into(),
+			input_nodes_map: input_nodes_map.into(),
+			nexus_map,
+			component_map: component_map.into(),
+			drivers,
+			nodes,
+			memories: memories.into(),
+		}
+	}
+
+	pub fn nodes(&self) -> GraphIter<C, Uc> {
+		GraphIter { iter: self.nodes.iter() }
+	}
+
+	pub fn nexus_mut(&mut self, nexus: NexusHandle) -> Option<&mut Nexus<Un>> {
+		self.nexuses.get_mut(nexus.0)
+	}
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum RemoveError {
+	InvalidNode,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum RemoveNexusError {
+	InvalidNexus,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum MergeNexusError {
+	InvalidNexus,
+	SameNexus,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ConnectError {
+	InvalidPort,
+	InvalidNode,
+	InvalidNexus,
+}
+
+pub struct GraphIter<'a, C, Uc>
+where
+	C: Component,
+{
+	iter: crate::arena::Iter<'a, Node<C, Uc>>,
+}
+
+impl<'a, C, Uc> Iterator for GraphIter<'a, C, Uc>
+where
+	C: Component,
+{
+	type Item = (&'a C, GraphNodeHandle, &'a Uc);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		self.iter
+			.next()
+			.map(|(h, n)| (&n.component, GraphNodeHandle(h), &n.userdata))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use core::num::NonZeroU16;
+	use std::sync::Arc;
+
+	/// ```
+	/// i0 --+-------v
+	///      |      AND --> NOT
+	/// i1 --|--+----^       |
+	///      |  |            v
+	///      +--|----v      AND --> o0
+	///      |  |    OR -----^
+	///      |  +----^
+	///      |  |
+	///      +--|----v
+	///         |   XOR ----------> o1
+	///         +----^
+	/// ```
+	#[test]
+	fn manual_xor() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		let bits = NonZeroU16::new(4).unwrap();
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", bits, 1)), ());
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let l0 = graph.add(Box::new(AndGate::new(inputs)), ());
+		let l1 = graph.add(Box::new(NotGate::new()), ());
+		let r0 = graph.add(Box::new(OrGate::new(inputs)), ());
+		let lr = graph.add(Box::new(AndGate::new(inputs)), ());
+		let cp = graph.add(Box::new(XorGate::new(inputs)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		let o1 = graph.add(Box::new(Out::new("O1", bits, 1)), ());
+
+		let i0n = Some(graph.new_nexus(()));
+		let i1n = Some(graph.new_nexus(()));
+		let l0n = Some(graph.new_nexus(()));
+		let l1n = Some(graph.new_nexus(()));
+		let r0n = Some(graph.new_nexus(()));
+		let lrn = Some(graph.new_nexus(()));
+		let cpn = Some(graph.new_nexus(()));
+
+		graph
+			.connect(Port::Output { node: i0, port: 0 }, i0n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: i1, port: 0 }, i1n)
+			.unwrap();
+
+		graph
+			.connect(Port::Input { node: l0, port: 0 }, i0n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: l0, port: 1 }, i1n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: l0, port: 0 }, l0n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: r0, port: 0 }, i0n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: r0, port: 1 }, i1n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: r0, port: 0 }, r0n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: l1, port: 0 }, l0n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: l1, port: 0 }, l1n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: lr, port: 0 }, l1n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: lr, port: 1 }, r0n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: lr, port: 0 }, lrn)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: o0, port: 0 }, lrn)
+			.unwrap();
+
+		graph
+			.connect(Port::Input { node: cp, port: 0 }, i0n)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: cp, port: 1 }, i1n)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: cp, port: 0 }, cpn)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: o1, port: 0 }, cpn)
+			.unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let (a, b) = (0b1100, 0b0110);
+		state.write_inputs(&[ir::Value::Set(a), ir::Value::Set(b)]);
+		state.run(1024);
+		let mut out = [ir::Value::Floating; 2];
+		state.read_outputs(&mut out);
+		assert_eq!(out, [ir::Value::Set(a ^ b); 2]);
+	}
+
+	#[test]
+	fn floating_bits() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		let i0 = graph.add(Box::new(In::new("I0", NonZeroU16::new(4).unwrap(), 0)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", NonZeroU16::new(8).unwrap(), 0)), ());
+		let o1 = graph.add(Box::new(Out::new("O1", NonZeroU16::new(8).unwrap(), 1)), ());
+		let n = Some(graph.new_nexus(()));
+		graph
+			.connect(Port::Output { node: i0, port: 0 }, n)
+			.unwrap();
+		graph.connect(Port::Input { node: o0, port: 0 }, n).unwrap();
+		let m = Some(graph.new_nexus(()));
+		graph.connect(Port::Input { node: o1, port: 0 }, m).unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.write_inputs(&[ir::Value::Set(0b0101)]);
+		state.run(1024);
+		let mut out = [ir::Value::Short; 2];
+		state.read_outputs(&mut out);
+		let value = ir::Value::Mixed { value: 0b0101, floating: 0xf0, short: 0 };
+		assert_eq!(out, [value, ir::Value::Floating]);
+	}
+
+	#[test]
+	fn multiple_drivers() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		let bits = NonZeroU16::new(2).unwrap();
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", bits, 1)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		let n0 = Some(graph.new_nexus(()));
+		let n1 = Some(graph.new_nexus(()));
+		graph
+			.connect(Port::Output { node: i0, port: 0 }, n0)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: i1, port: 0 }, n0)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: not, port: 0 }, n0)
+			.unwrap();
+		graph
+			.connect(Port::Output { node: not, port: 0 }, n1)
+			.unwrap();
+		graph
+			.connect(Port::Input { node: o0, port: 0 }, n1)
+			.unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let mut out = [ir::Value::Floating];
+		let mut test = |a, b, expect| {
+			state.write_inputs(&[a, b]);
+			state.run(1024);
+			state.read_outputs(&mut out);
+			assert_eq!(out, [expect]);
+		};
+		use ir::Value::*;
+		test(Set(0b01), Floating, Set(0b10));
+		test(Floating, Set(0b10), Set(0b01));
+		test(Floating, Floating, Short);
+		test(Set(0b01), Set(0b01), Short);
+		let value = Mixed { value: 0b10, floating: 0, short: 0b01 };
+		let mixed = Mixed { value: 0b01, floating: 0b10, short: 0 };
+		test(mixed, Set(0b01), value);
+	}
+
+	#[test]
+	fn tri_state_bus() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		let (one, bits) = (NonZeroU16::new(1).unwrap(), NonZeroU16::new(4).unwrap());
+		let d0 = graph.add(Box::new(In::new("D0", bits, 0)), ());
+		let e0 = graph.add(Box::new(In::new("E0", one, 1)), ());
+		let d1 = graph.add(Box::new(In::new("D1", bits, 2)), ());
+		let e1 = graph.add(Box::new(In::new("E1", one, 3)), ());
+		let b0 = graph.add(Box::new(TriStateBuffer::new(bits)), ());
+		let b1 = graph.add(Box::new(TriStateBuffer::new(bits)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+
+		let bus = graph.new_nexus(());
+		for (node, d, e) in [(b0, d0, e0), (b1, d1, e1)] {
+			let (dn, en) = (Some(graph.new_nexus(())), Some(graph.new_nexus(())));
+			graph
+				.connect(Port::Output { node: d, port: 0 }, dn)
+				.unwrap();
+			graph
+				.connect(Port::Output { node: e, port: 0 }, en)
+				.unwrap();
+			graph.connect(Port::Input { node, port: 0 }, dn).unwrap();
+			graph.connect(Port::Input { node, port: 1 }, en).unwrap();
+			graph
+				.connect(Port::Output { node, port: 0 }, Some(bus))
+				.unwrap();
+		}
+		graph
+			.connect(Port::Input { node: o0, port: 0 }, Some(bus))
+			.unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let mut out = [ir::Value::Floating];
+		let mut test = |e0, e1, expect, contended| {
+			use ir::Value::Set;
+			state.write_inputs(&[Set(0b0101), e0, Set(0b0011), e1]);
+			state.run(1024);
+			state.read_outputs(&mut out);
+			assert_eq!(out, [expect]);
+			assert_eq!(state.is_contended(bus), contended);
+		};
+		use ir::Value::*;
+		test(Set(1), Set(0), Set(0b0101), false);
+		test(Set(0), Set(1), Set(0b0011), false);
+		test(Set(0), Set(0), Floating, false);
+		test(Set(1), Set(1), Short, true);
+		test(Floating, Set(0), Short, false);
+	}
+
+	#[test]
+	fn wide_bus() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		// Swap the halves of a bus spanning several words and invert it.
+		let (bits, half) = (NonZeroU16::new(200).unwrap(), NonZeroU16::new(100).unwrap());
+		let mut splitter = Splitter::new();
+		splitter.outputs = [BitRange::new(0, half), BitRange::new(100, half)].into();
+		let mut merger = Merger::new(bits);
+		merger.inputs = [BitRange::new(100, half), BitRange::new(0, half)].into();
+		let mut not = NotGate::new();
+		not.bits = bits;
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let splitter = graph.add(Box::new(splitter), ());
+		let merger = graph.add(Box::new(merger), ());
+		let not = graph.add(Box::new(not), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		let [n0, n1, n2, n3, n4] = [(); 5].map(|_| Some(graph.new_nexus(())));
+		let connect = |graph: &mut Graph<_, _, _>, port, nexus| graph.connect(port, nexus).unwrap();
+		connect(&mut graph, Port::Output { node: i0, port: 0 }, n0);
+		connect(&mut graph, Port::Input { node: splitter, port: 0 }, n0);
+		connect(&mut graph, Port::Output { node: splitter, port: 0 }, n1);
+		connect(&mut graph, Port::Output { node: splitter, port: 1 }, n2);
+		connect(&mut graph, Port::Input { node: merger, port: 0 }, n1);
+		connect(&mut graph, Port::Input { node: merger, port: 1 }, n2);
+		connect(&mut graph, Port::Output { node: merger, port: 0 }, n3);
+		connect(&mut graph, Port::Input { node: not, port: 0 }, n3);
+		connect(&mut graph, Port::Output { node: not, port: 0 }, n4);
+		connect(&mut graph, Port::Input { node: o0, port: 0 }, n4);
+
+		let words = ir::words(bits.get());
+		let value: [usize; ir::MAX_WORDS] = core::array::from_fn(|k| {
+			(k + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15) & ir::word_mask(bits.get(), k)
+		});
+		let bit =
+			|v: &[usize], i: usize| v[i / usize::BITS as usize] >> (i % usize::BITS as usize) & 1;
+		let mut expect = [0; ir::MAX_WORDS];
+		for i in 0..200 {
+			let b = bit(&value, (i + 100) % 200) ^ 1;
+			expect[i / usize::BITS as usize] |= b << (i % usize::BITS as usize);
+		}
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let (floating, short) = ([0; ir::MAX_WORDS], [0; ir::MAX_WORDS]);
+		state.write_inputs(&[ir::Value::Wide { value, floating, short }]);
+		assert!(state.run(1024).is_settled());
+		let mut out = [ir::Value::Floating];
+		state.read_outputs(&mut out);
+		assert_eq!(
+			out,
+			[ir::Value::from_words(
+				expect[..words].iter().map(|&w| ir::Value::Set(w))
+			)]
+		);
+	}
+}
@@ -0,0 +1,214 @@
+//! # Batch mode
+//!
+//! Most circuits only use 1-bit wide nexuses, which leaves all but one bit of each word unused.
+//! In batch mode each bit of a word is a separate lane that is simulated as if it were bit 0, so
+//! [`LANES`] independent sets of inputs can be simulated at once. Bit `k` of each input and
+//! output value belongs to lane `k`.
+//!
+//! Only programs in which no op moves bits to another position can be run in batch mode.
+
+use super::program::{IrOp, Program, State, Value};
+use super::RunResult;
+use std::sync::Arc;
+
+/// The amount of lanes simulated by a state in batch mode.
+pub const LANES: usize = usize::BITS as usize;
+
+impl Program {
+	/// Whether this program can be simulated in batch mode, i.e. all inputs and outputs are 1 bit
+	/// wide and no bits are shifted or used as an address.
+	pub fn supports_batch(&self) -> bool {
+		let mut io = self.input_map.iter().chain(self.output_map.iter());
+		io.all(|&(a, bits)| a == usize::MAX || bits == 1)
+			&& self
+				.nodes
+				.iter()
+				.flat_map(|n| n.ir.iter())
+				.all(|op| match op {
+					IrOp::Slli { i } | IrOp::Srli { i } | IrOp::Srai { i } => *i == 0,
+					IrOp::Read { .. }
+					| IrOp::Fetch { .. }
+					| IrOp::Store { .. }
+					| IrOp::SaveIf { .. }
+					| IrOp::SetIf { .. }
+					| IrOp::Eqi { .. }
+					| IrOp::Add { .. }
+					| IrOp::Sub { .. }
+					| IrOp::Mul { .. }
+					| IrOp::Div { .. }
+					| IrOp::Rem { .. }
+					| IrOp::Lt { .. }
+					| IrOp::Eq { .. }
+					| IrOp::Sll { .. }
+					| IrOp::Srl { .. }
+					| IrOp::Sra { .. }
+					| IrOp::Rotl { .. }
+					| IrOp::Rotr { .. }
+					| IrOp::Popcnt { .. }
+					| IrOp::Clz { .. }
+					| IrOp::Edge { .. } => false,
+					_ => true,
+				})
+	}
+
+	/// Create a state that simulates [`LANES`] independent lanes at once. Returns `None` if the
+	/// program [doesn't support it](Self::supports_batch).
+	pub fn new_batch_state(self: Arc<Self>) -> Option<State> {
+		self.supports_batch().then(|| {
+			let mut s = self.new_state();
+			s.batch = true;
+			s
+		})
+	}
+
+	/// Get the outputs for all combinations of inputs. Bit `i` of the index of each row is the
+	/// value of input `i`. Each combination starts from a fresh state and is run for up to
+	/// `max_iterations` steps.
+	///
+	/// Returns `None` if the program doesn't support batch mode. If any combination doesn't
+	/// settle, the result of the first batch of combinations that didn't is returned as error.
+	pub fn truth_table(
+		self: Arc<Self>,
+		max_iterations: usize,
+	) -> Option<Result<Vec<Box<[Value]>>, RunResult>> {
+		if !self.supports_batch() {
+			return None;
+		}
+		let (inputs, outputs) = (self.input_map.len(), self.output_map.len());
+		let rows = 1usize.checked_shl(inputs.try_into().ok()?)?;
+		let mut table = Vec::with_capacity(rows);
+		let mut values = vec![Value::Floating; inputs.max(outputs)];
+		for base in (0..rows).step_by(LANES) {
+			let mut state = self.clone().new_batch_state()?;
+			for (i, v) in values[..inputs].iter_mut().enumerate() {
+				let lanes = (0..LANES).filter(|k| (base + k) >> i & 1 != 0);
+				*v = Value::Set(lanes.fold(0, |v, k| v | 1 << k));
+			}
+			state.write_inputs(&values[..inputs]);
+			let r = state.run(max_iterations);
+			if !r.is_settled() {
+				return Some(Err(r));
+			}
+			state.read_outputs(&mut values[..outputs]);
+			for k in 0..LANES.min(rows - base) {
+				table.push(values[..outputs].iter().map(|v| v.lane(k)).collect());
+			}
+		}
+		Some(Ok(table))
+	}
+}
+
+impl Value {
+	/// Get the value of a single lane of a value read in batch mode.
+	pub fn lane(self, lane: usize) -> Self {
+		let (value, floating, short) = self.to_masks(1 << lane);
+		Self::from_masks(value >> lane, floating >> lane, short >> lane, 1)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::NonZeroU16;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// A full adder with a tri-state buffer on the sum, enabled by the last input.
+	fn adder() -> G {
+		let mut graph = G::new();
+		let one = NonZeroU16::new(1).unwrap();
+		let two = NonZeroOneU8::new(2).unwrap();
+		let [a, b, c, en, x, s, g0, g1, co, sum] = [(); 10].map(|_| graph.new_nexus(()));
+		let mut connect = |component: Box<dyn Component>, inputs: &[_], outputs: &[_]| {
+			let node = graph.add(component, ());
+			for (port, &n) in inputs.iter().enumerate() {
+				graph.connect(Port::Input { node, port }, Some(n)).unwrap();
+			}
+			for (port, &n) in outputs.iter().enumerate() {
+				graph.connect(Port::Output { node, port }, Some(n)).unwrap();
+			}
+		};
+		for (i, n) in [a, b, c, en].into_iter().enumerate() {
+			connect(Box::new(In::new("", one, i)), &[], &[n]);
+		}
+		connect(Box::new(XorGate::new(two)), &[a, b], &[x]);
+		connect(Box::new(XorGate::new(two)), &[x, c], &[s]);
+		connect(Box::new(AndGate::new(two)), &[a, b], &[g0]);
+		connect(Box::new(AndGate::new(two)), &[x, c], &[g1]);
+		connect(Box::new(OrGate::new(two)), &[g0, g1], &[co]);
+		connect(Box::new(TriStateBuffer::new(one)), &[s, en], &[sum]);
+		connect(Box::new(Out::new("", one, 0)), &[sum], &[]);
+		connect(Box::new(Out::new("", one, 1)), &[co], &[]);
+		graph
+	}
+
+	#[test]
+	fn truth_table() {
+		let program = Arc::new(adder().generate_ir());
+		let table = program.clone().truth_table(1024).unwrap().unwrap();
+		assert_eq!(table.len(), 16);
+		for (i, row) in table.iter().enumerate() {
+			let mut state = program.clone().new_state();
+			let inputs = (0..4).map(|k| Value::Set(i >> k & 1)).collect::<Vec<_>>();
+			state.write_inputs(&inputs);
+			state.run(1024);
+			let mut outputs = [Value::Floating; 2];
+			state.read_outputs(&mut outputs);
+			assert_eq!(&outputs[..], &row[..], "{}", i);
+		}
+		assert_eq!(&table[0b0011][..], &[Value::Floating, Value::Set(1)]);
+		assert_eq!(&table[0b1011][..], &[Value::Set(0), Value::Set(1)]);
+	}
+
+	#[test]
+	fn undefined_lanes() {
+		let program = Arc::new(adder().generate_ir());
+		let mut batch = program.clone().new_batch_state().unwrap();
+		let values = [Value::Set(0), Value::Set(1), Value::Floating, Value::Short];
+		// Each lane uses a different combination of values for all inputs except the carry.
+		let shift = |i: usize| [0, 2, 6, 4][i];
+		let inputs = (0..4)
+			.map(|i| {
+				let (mut v, mut f, mut s) = (0, 0, 0);
+				for k in 0..LANES {
+					match k >> shift(i) & 3 {
+						0 => (),
+						1 => v |= 1 << k,
+						2 => f |= 1 << k,
+						_ => s |= 1 << k,
+					}
+				}
+				Value::from_masks(v, f, s, usize::MAX)
+			})
+			.collect::<Vec<_>>();
+		batch.write_inputs(&inputs);
+		batch.run(1024);
+		let mut lanes = [Value::Floating; 2];
+		batch.read_outputs(&mut lanes);
+		for k in 0..LANES {
+			let mut state = program.clone().new_state();
+			let inputs = (0..4)
+				.map(|i| values[k >> shift(i) & 3])
+				.collect::<Vec<_>>();
+			state.write_inputs(&inputs);
+			state.run(1024);
+			let mut outputs = [Value::Floating; 2];
+			state.read_outputs(&mut outputs);
+			assert_eq!(outputs, lanes.map(|v| v.lane(k)), "{}", k);
+		}
+	}
+
+	#[test]
+	fn unsupported() {
+		let mut graph = G::new();
+		let node = graph.add(Box::new(In::new("", NonZeroU16::new(2).unwrap(), 0)), ());
+		let n = graph.new_nexus(());
+		graph
+			.connect(Port::Output { node, port: 0 }, Some(n))
+			.unwrap();
+		let program = Arc::new(graph.generate_ir());
+		assert!(!program.supports_batch());
+		assert!(program.truth_table(1024).is_none());
+	}
+}
@@ -0,0 +1,193 @@
+//! # Breakpoints
+//!
+//! Breakpoints watch the value of a nexus, input or output and fire when their condition becomes true.
+//! They are checked after each step, which makes [`State::run`] and [`State::advance`] stop at the
+//! exact step that triggered them.
+//!
+//! Each breakpoint compares against the value it saw after the previous step, so changes made in
+//! between steps, e.g. by writing inputs, are noticed in the next step.
+
+use super::super::NexusHandle;
+use super::program::{State, Value};
+
+/// The value a breakpoint watches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watch {
+	Nexus(NexusHandle),
+	Input(usize),
+	Output(usize),
+}
+
+/// When a breakpoint fires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+	/// The value becomes equal to the given value.
+	Equals(Value),
+	/// The value changes.
+	Changes,
+	/// The least significant bit goes from low to high.
+	Rises,
+	/// The least significant bit goes from high to low.
+	Falls,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Breakpoint {
+	pub watch: Watch,
+	pub condition: Condition,
+}
+
+impl Breakpoint {
+	pub fn new(watch: Watch, condition: Condition) -> Self {
+		Self { watch, condition }
+	}
+
+	/// Whether the breakpoint fires if the watched value goes from `prev` to `value`.
+	fn fires(&self, prev: Value, value: Value) -> bool {
+		match self.condition {
+			Condition::Equals(v) => prev != v && value == v,
+			Condition::Changes => prev != value,
+			Condition::Rises => lsb(prev) == Some(false) && lsb(value) == Some(true),
+			Condition::Falls => lsb(prev) == Some(true) && lsb(value) == Some(false),
+		}
+	}
+}
+
+/// The least significant bit of a value, if it is defined.
+fn lsb(value: Value) -> Option<bool> {
+	match value.to_masks(1) {
+		(v, 0, 0) => Some(v != 0),
+		_ => None,
+	}
+}
+
+impl State {
+	/// Replace all breakpoints.
+	pub fn set_breakpoints(&mut self, breakpoints: impl IntoIterator<Item = Breakpoint>) {
+		self.breakpoints = breakpoints
+			.into_iter()
+			.map(|b| (b, Value::Floating))
+			.collect();
+		self.sync_breakpoints();
+	}
+
+	/// All breakpoints, in the order they were set.
+	pub fn breakpoints(&self) -> impl Iterator<Item = &Breakpoint> {
+		self.breakpoints.iter().map(|(b, _)| b)
+	}
+
+	/// The index of the breakpoint that fired in the last step, if any. If multiple breakpoints
+	/// fired the first one is returned.
+	pub fn breakpoint_hit(&self) -> Option<usize> {
+		self.breakpoint_hit
+	}
+
+	/// Get the value watched by a breakpoint. Nexuses, inputs and outputs that don't exist are
+	/// floating.
+	pub fn read_watch(&self, watch: Watch) -> Value {
+		match self.locate_watch(watch) {
+			Some((k, bits)) if k != usize::MAX => self.read_value(k, bits),
+			_ => Value::Floating,
+		}
+	}
+
+	/// The width of the watched value, or 1 if it doesn't exist.
+	pub fn watch_bits(&self, watch: Watch) -> u16 {
+		self.locate_watch(watch).map_or(1, |(_, bits)| bits)
+	}
+
+	/// The address & width of the watched value.
+	fn locate_watch(&self, watch: Watch) -> Option<(usize, u16)> {
+		let p = &self.program;
+		match watch {
+			Watch::Nexus(h) => p.nexus_map.get(h.index()).copied(),
+			Watch::Input(i) => p.input_map.get(i).copied(),
+			Watch::Output(i) => p.output_map.get(i).copied(),
+		}
+	}
+
+	/// Check the breakpoints after a step and remember the watched values.
+	pub(super) fn check_breakpoints(&mut self) {
+		self.breakpoint_hit = None;
+		for i in 0..self.breakpoints.len() {
+			let (b, prev) = self.breakpoints[i];
+			let value = self.read_watch(b.watch);
+			if b.fires(prev, value) && self.breakpoint_hit.is_none() {
+				self.breakpoint_hit = Some(i);
+			}
+			self.breakpoints[i].1 = value;
+		}
+	}
+
+	/// Remember the currently watched values without firing any breakpoints, e.g. after the state
+	/// was moved back in time.
+	pub(super) fn sync_breakpoints(&mut self) {
+		for i in 0..self.breakpoints.len() {
+			self.breakpoints[i].1 = self.read_watch(self.breakpoints[i].0.watch);
+		}
+		self.breakpoint_hit = None;
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::{NonZeroU16, NonZeroU32};
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// An inverter driven by the given component. Returns the graph and the nexus of the input.
+	fn inverter(source: Box<dyn Component>) -> (G, NexusHandle) {
+		let mut graph = G::new();
+		let i = graph.add(source, ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let o = graph.add(Box::new(Out::new("", NonZeroU16::new(1).unwrap(), 0)), ());
+		let [a, b] = [(); 2].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: i, port: 0 }, a);
+		connect(&mut graph, Port::Input { node: not, port: 0 }, a);
+		connect(&mut graph, Port::Output { node: not, port: 0 }, b);
+		connect(&mut graph, Port::Input { node: o, port: 0 }, b);
+		(graph, a)
+	}
+
+	#[test]
+	fn run() {
+		let (graph, _) = inverter(Box::new(In::new("", NonZeroU16::new(1).unwrap(), 0)));
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.set_breakpoints([Breakpoint::new(Watch::Output(0), Condition::Changes)]);
+		state.write_inputs(&[Value::Set(0)]);
+		assert_eq!(
+			state.run(1024),
+			ir::RunResult::Break { steps: 1, breakpoint: 0 }
+		);
+		assert_eq!(state.run(1024), ir::RunResult::Settled { steps: 0 });
+		state.write_inputs(&[Value::Set(1)]);
+		assert_eq!(
+			state.run(1024),
+			ir::RunResult::Break { steps: 1, breakpoint: 0 }
+		);
+		assert_eq!(state.breakpoint_hit(), Some(0));
+		assert_eq!(state.read_watch(Watch::Output(0)), Value::Set(0));
+	}
+
+	#[test]
+	fn clock_edges() {
+		// The clock is low at tick 0 and 3 and high otherwise.
+		let (one, two) = (NonZeroU32::new(1).unwrap(), NonZeroU32::new(2).unwrap());
+		let (graph, clock) = inverter(Box::new(Clock::new(two, one)));
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.set_breakpoints([
+			Breakpoint::new(Watch::Nexus(clock), Condition::Rises),
+			Breakpoint::new(Watch::Output(0), Condition::Equals(Value::Set(1))),
+		]);
+		let mut hits = Vec::new();
+		for _ in 0..4 {
+			let hit = state.advance(100);
+			hits.push((state.ticks(), hit.unwrap()));
+		}
+		assert_eq!(hits, [(1, 1), (2, 0), (4, 1), (5, 0)]);
+	}
+}
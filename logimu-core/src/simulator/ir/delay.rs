@@ -0,0 +1,228 @@
+//! # Propagation delays
+//!
+//! Nodes with a delay of `d` steps run as soon as they are dirty, but their writes only take
+//! effect `d - 1` steps later, as if a node with unit delay ran at that step. Changes shorter than
+//! the delay are thus not filtered out, which allows simulating glitches.
+//!
+//! Pending writes are kept in a timing wheel with a slot for each step up to the maximum delay of
+//! the program.
+
+use super::program::{Output, Word};
+use crate::integer_set::IntegerSet;
+use core::hash::{Hash, Hasher};
+use core::num::NonZeroU8;
+use serde::{Deserialize, Serialize};
+
+/// The writes of all nodes that take effect in the same step.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Slot {
+	/// All writes, in order.
+	writes: Vec<(usize, Word)>,
+	/// Nodes to mark dirty if the masked memory at the given address changed once all writes are
+	/// done.
+	checks: Vec<(usize, usize, usize)>,
+	/// The amount of node runs the writes come from.
+	nodes: usize,
+}
+
+impl Output for Slot {
+	fn save(&mut self, a: usize, w: Word) {
+		self.writes.push((a, w));
+	}
+
+	fn check_dirty(&mut self, _: &[Word], a: usize, node: usize, mask: usize) {
+		self.checks.push((a, node, mask));
+	}
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub(super) struct Wheel {
+	slots: Box<[Slot]>,
+	/// The slot of the current step.
+	now: usize,
+	/// The amount of node runs with writes that haven't taken effect yet.
+	pending: usize,
+}
+
+impl Wheel {
+	/// Create a wheel for nodes with delays up to `max_delay`. The wheel is empty if the maximum
+	/// delay is 1.
+	pub(super) fn new(max_delay: NonZeroU8) -> Self {
+		let slots = if max_delay.get() > 1 {
+			(0..max_delay.get()).map(|_| Slot::default()).collect()
+		} else {
+			Box::default()
+		};
+		Self { slots, now: 0, pending: 0 }
+	}
+
+	/// Get an output that takes effect `delay - 1` steps after the current one.
+	///
+	/// # Panics
+	///
+	/// The delay is larger than the maximum delay of the wheel.
+	pub(super) fn schedule(&mut self, delay: NonZeroU8) -> &mut impl Output {
+		let len = self.slots.len();
+		assert!(usize::from(delay.get()) <= len, "delay out of range");
+		let slot = &mut self.slots[(self.now + usize::from(delay.get()) - 1) % len];
+		slot.nodes += 1;
+		self.pending += 1;
+		slot
+	}
+
+	/// Apply the writes of the current step and advance to the next step.
+	pub(super) fn advance(&mut self, rd: &[Word], wr: &mut [Word], dirty: &mut IntegerSet) {
+		if self.slots.is_empty() {
+			return;
+		}
+		let slot = &mut self.slots[self.now];
+		slot.writes.drain(..).for_each(|(a, w)| wr[a] = w);
+		for (a, node, mask) in slot.checks.drain(..) {
+			if wr[a].changed(rd[a]) & mask != 0 {
+				dirty.insert(node);
+			}
+		}
+		self.pending -= slot.nodes;
+		slot.nodes = 0;
+		self.now = (self.now + 1) % self.slots.len();
+	}
+
+	/// Whether the wheel has no slots because no node has a delay of more than one step.
+	pub(super) fn is_empty(&self) -> bool {
+		self.slots.is_empty()
+	}
+
+	/// The amount of node runs with writes that haven't taken effect yet.
+	pub(super) fn pending(&self) -> usize {
+		self.pending
+	}
+
+	/// Drop all writes that haven't taken effect yet.
+	pub(super) fn clear(&mut self) {
+		self.slots.iter_mut().for_each(|s| *s = Slot::default());
+		self.pending = 0;
+	}
+
+	/// Whether this wheel can replace `other`, i.e. it has the same amount of slots and its
+	/// bookkeeping is consistent.
+	pub(super) fn fits(&self, other: &Self) -> bool {
+		let nodes = self.slots.iter().map(|s| s.nodes).sum::<usize>();
+		self.slots.len() == other.slots.len()
+			&& (self.now < self.slots.len() || (self.slots.is_empty() && self.now == 0))
+			&& self.pending == nodes
+	}
+
+	/// The slots in the order they take effect, starting with the current step.
+	fn upcoming(&self) -> impl Iterator<Item = &Slot> {
+		let (past, upcoming) = self.slots.split_at(self.now);
+		upcoming.iter().chain(past)
+	}
+}
+
+// Wheels are equal if the same writes take effect in the same steps, regardless of which slot is
+// the current one.
+impl PartialEq for Wheel {
+	fn eq(&self, rhs: &Self) -> bool {
+		self.upcoming().eq(rhs.upcoming())
+	}
+}
+
+impl Eq for Wheel {}
+
+impl Hash for Wheel {
+	fn hash<H: Hasher>(&self, h: &mut H) {
+		self.upcoming().for_each(|s| s.hash(h));
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::simulator::*;
+	use core::num::{NonZeroU16, NonZeroU8};
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// `a XOR NOT(a)` with a slow inverter, which glitches to 0 whenever `a` changes.
+	fn glitch(delay: u8) -> G {
+		let mut graph = G::new();
+		let one = NonZeroU16::new(1).unwrap();
+		let i = graph.add(Box::new(In::new("A", one, 0)), ());
+		let mut not = NotGate::new();
+		not.delay = NonZeroU8::new(delay).unwrap();
+		let not = graph.add(Box::new(not), ());
+		let xor = graph.add(Box::new(XorGate::new(NonZeroOneU8::new(2).unwrap())), ());
+		let o = graph.add(Box::new(Out::new("O", one, 0)), ());
+		let [a, na, x] = [(); 3].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: i, port: 0 }, a);
+		connect(&mut graph, Port::Input { node: not, port: 0 }, a);
+		connect(&mut graph, Port::Output { node: not, port: 0 }, na);
+		connect(&mut graph, Port::Input { node: xor, port: 0 }, a);
+		connect(&mut graph, Port::Input { node: xor, port: 1 }, na);
+		connect(&mut graph, Port::Output { node: xor, port: 0 }, x);
+		connect(&mut graph, Port::Input { node: o, port: 0 }, x);
+		graph
+	}
+
+	/// Toggle the input after the circuit settled and record the output after each step.
+	fn trace(delay: u8) -> Vec<ir::Value> {
+		let mut state = Arc::new(glitch(delay).generate_ir()).new_state();
+		state.write_inputs(&[ir::Value::Set(0)]);
+		state.run(1024);
+		state.write_inputs(&[ir::Value::Set(1)]);
+		let mut trace = Vec::new();
+		loop {
+			let dirty = state.step();
+			let mut out = [ir::Value::Floating];
+			state.read_outputs(&mut out);
+			trace.push(out[0]);
+			if dirty == 0 {
+				break trace;
+			}
+		}
+	}
+
+	#[test]
+	fn unit_delay() {
+		use ir::Value::Set;
+		assert_eq!(trace(1), [Set(0), Set(1)]);
+	}
+
+	#[test]
+	fn glitch_length() {
+		use ir::Value::Set;
+		assert_eq!(trace(3), [Set(0), Set(0), Set(0), Set(1)]);
+		assert_eq!(trace(5), [Set(0), Set(0), Set(0), Set(0), Set(0), Set(1)]);
+	}
+
+	#[test]
+	fn short_pulses() {
+		// Pulses shorter than the delay still propagate.
+		let mut state = Arc::new(glitch(4).generate_ir()).new_state();
+		state.write_inputs(&[ir::Value::Set(0)]);
+		state.run(1024);
+		let mut trace = Vec::new();
+		for i in 0..8 {
+			if i < 2 {
+				state.write_inputs(&[ir::Value::Set(1 - i % 2)]);
+			}
+			state.step();
+			let mut out = [ir::Value::Floating];
+			state.read_outputs(&mut out);
+			trace.push(out[0]);
+		}
+		use ir::Value::Set;
+		let expected = [
+			Set(0),
+			Set(1),
+			Set(1),
+			Set(1),
+			Set(0),
+			Set(1),
+			Set(1),
+			Set(1),
+		];
+		assert_eq!(trace, expected);
+	}
+}
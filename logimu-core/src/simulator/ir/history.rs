@@ -0,0 +1,282 @@
+//! # History
+//!
+//! When enabled, the changes of each step are recorded so the state can be moved back and forth
+//! in time. Only the words of memory that changed are kept for each step, together with the
+//! nodes that needed an update, the pending writes of delayed nodes and the amount of ticks before
+//! the step.
+//!
+//! Changes made in between steps, e.g. by writing inputs, are recorded as part of the next step.
+//! Stepping after going back in time discards the steps that were undone.
+
+use super::super::NexusHandle;
+use super::delay::Wheel;
+use super::program::{words, State, Word};
+use std::collections::VecDeque;
+use std::mem;
+
+/// The changes of a single step.
+#[derive(Debug)]
+struct Delta {
+	/// The address of each word that changed, with its value before and after the step.
+	memory: Box<[(usize, Word, Word)]>,
+	/// The nodes that needed an update before the step, sorted.
+	dirty: Box<[usize]>,
+	wheel: Wheel,
+	ticks: u64,
+}
+
+#[derive(Debug)]
+pub(super) struct History {
+	/// The recorded steps, oldest first.
+	deltas: VecDeque<Delta>,
+	/// The maximum amount of steps to record.
+	capacity: usize,
+	/// The amount of steps that were undone.
+	undone: usize,
+	/// The memory, dirty nodes, pending writes & ticks after the newest step.
+	memory: Box<[Word]>,
+	dirty: Box<[usize]>,
+	wheel: Wheel,
+	ticks: u64,
+}
+
+impl State {
+	/// Record up to the given amount of steps, or disable recording if 0. Changing the amount of
+	/// steps discards the history.
+	pub fn set_history(&mut self, steps: usize) {
+		self.history = (steps > 0).then(|| History {
+			deltas: VecDeque::new(),
+			capacity: steps,
+			undone: 0,
+			memory: self.read.clone(),
+			dirty: self.sorted_dirty(),
+			wheel: self.wheel.clone(),
+			ticks: self.ticks,
+		});
+	}
+
+	/// The amount of recorded steps.
+	pub fn history_len(&self) -> usize {
+		self.history.as_ref().map_or(0, |h| h.deltas.len())
+	}
+
+	/// The position of the state in the history, which is equal to [`State::history_len`] unless
+	/// steps were undone.
+	pub fn history_position(&self) -> usize {
+		self.history
+			.as_ref()
+			.map_or(0, |h| h.deltas.len() - h.undone)
+	}
+
+	/// Undo the last step. Returns `false` if there is no step left to undo.
+	pub fn step_back(&mut self) -> bool {
+		let h = match self.history.as_mut() {
+			Some(h) => h,
+			None => return false,
+		};
+		if h.undone == h.deltas.len() {
+			return false;
+		}
+		h.undone += 1;
+		let d = &h.deltas[h.deltas.len() - h.undone];
+		for &(a, before, _) in d.memory.iter() {
+			(self.read[a], self.write[a]) = (before, before);
+		}
+		self.update_dirty = d.dirty.iter().copied().collect();
+		self.wheel = d.wheel.clone();
+		self.ticks = d.ticks;
+		self.sync_breakpoints();
+		self.sample_probes();
+		true
+	}
+
+	/// Redo the last undone step. Returns `false` if no step was undone.
+	pub fn step_forward(&mut self) -> bool {
+		let h = match self.history.as_mut() {
+			Some(h) => h,
+			None => return false,
+		};
+		if h.undone == 0 {
+			return false;
+		}
+		let i = h.deltas.len() - h.undone;
+		h.undone -= 1;
+		for &(a, _, after) in h.deltas[i].memory.iter() {
+			(self.read[a], self.write[a]) = (after, after);
+		}
+		let (dirty, wheel, ticks) = match h.deltas.get(i + 1) {
+			Some(d) => (&d.dirty, &d.wheel, d.ticks),
+			None => (&h.dirty, &h.wheel, h.ticks),
+		};
+		self.update_dirty = dirty.iter().copied().collect();
+		self.wheel = wheel.clone();
+		self.ticks = ticks;
+		self.sync_breakpoints();
+		self.sample_probes();
+		true
+	}
+
+	/// Move to the given position in the history, see [`State::history_position`].
+	///
+	/// # Panics
+	///
+	/// The position is past the end of the history.
+	pub fn seek(&mut self, position: usize) {
+		assert!(position <= self.history_len(), "position out of range");
+		while self.history_position() > position {
+			self.step_back();
+		}
+		while self.history_position() < position {
+			self.step_forward();
+		}
+	}
+
+	/// The position right before the last step that changed the given nexus, searching back from
+	/// the current position.
+	///
+	/// # Panics
+	///
+	/// The nexus is invalid.
+	pub fn last_change(&self, nexus: NexusHandle) -> Option<usize> {
+		let (a, bits) = self.program.nexus_map[nexus.index()];
+		let h = self.history.as_ref()?;
+		let end = h.deltas.len() - h.undone;
+		h.deltas.range(..end).rposition(|d| {
+			d.memory
+				.iter()
+				.any(|&(m, ..)| (a..a + words(bits)).contains(&m))
+		})
+	}
+
+	/// Record the changes since the newest step, discarding any undone steps first.
+	pub(super) fn record(&mut self) {
+		self.discard_undone();
+		if self.history.is_none() {
+			return;
+		}
+		let dirty = self.sorted_dirty();
+		let h = self.history.as_mut().unwrap();
+		let memory = h.memory.iter_mut().zip(self.read.iter()).enumerate();
+		let memory = memory
+			.filter(|(_, (b, a))| b != a)
+			.map(|(i, (b, &a))| (i, mem::replace(b, a), a))
+			.collect();
+		let delta = Delta {
+			memory,
+			dirty: mem::replace(&mut h.dirty, dirty),
+			wheel: mem::replace(&mut h.wheel, self.wheel.clone()),
+			ticks: mem::replace(&mut h.ticks, self.ticks),
+		};
+		if h.deltas.len() == h.capacity {
+			h.deltas.pop_front();
+		}
+		h.deltas.push_back(delta);
+	}
+
+	/// Drop the steps that were undone, making the current position the newest.
+	pub(super) fn discard_undone(&mut self) {
+		let h = match self.history.as_mut() {
+			Some(h) => h,
+			None => return,
+		};
+		for _ in 0..mem::take(&mut h.undone) {
+			let d = h.deltas.pop_back().unwrap();
+			for &(a, before, _) in d.memory.iter() {
+				h.memory[a] = before;
+			}
+			(h.dirty, h.wheel, h.ticks) = (d.dirty, d.wheel, d.ticks);
+		}
+	}
+
+	/// Take over the history of a state of another program, if the memory layout is the same.
+	pub(super) fn adapt_history(&mut self, history: Option<History>) {
+		match history {
+			Some(h) if h.memory.len() == self.read.len() => self.history = Some(h),
+			Some(h) => self.set_history(h.capacity),
+			None => (),
+		}
+	}
+
+	/// The capacity of the history, or 0 if it is disabled.
+	pub(super) fn history_capacity(&self) -> usize {
+		self.history.as_ref().map_or(0, |h| h.capacity)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::simulator::*;
+	use core::num::{NonZeroU16, NonZeroU32};
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// A clock driving a chain of two inverters. Returns the nexus of the clock and the output.
+	fn chain() -> (G, NexusHandle, NexusHandle) {
+		let mut graph = G::new();
+		let (one, two) = (NonZeroU32::new(1).unwrap(), NonZeroU32::new(2).unwrap());
+		let clock = graph.add(Box::new(Clock::new(two, one)), ());
+		let nots = [(); 2].map(|_| graph.add(Box::new(NotGate::new()), ()));
+		let o = graph.add(Box::new(Out::new("", NonZeroU16::new(1).unwrap(), 0)), ());
+		let [a, b, c] = [(); 3].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: clock, port: 0 }, a);
+		connect(&mut graph, Port::Input { node: nots[0], port: 0 }, a);
+		connect(&mut graph, Port::Output { node: nots[0], port: 0 }, b);
+		connect(&mut graph, Port::Input { node: nots[1], port: 0 }, b);
+		connect(&mut graph, Port::Output { node: nots[1], port: 0 }, c);
+		connect(&mut graph, Port::Input { node: o, port: 0 }, c);
+		(graph, a, c)
+	}
+
+	fn out(state: &State) -> ir::Value {
+		let mut o = [ir::Value::Floating];
+		state.read_outputs(&mut o);
+		o[0]
+	}
+
+	#[test]
+	fn step_back_and_forth() {
+		let mut state = Arc::new(chain().0.generate_ir()).new_state();
+		state.set_history(4);
+		let mut trace = vec![(state.ticks(), out(&state))];
+		for _ in 0..6 {
+			state.step();
+			trace.push((state.ticks(), out(&state)));
+		}
+		assert_eq!(state.history_len(), 4);
+
+		// Only the last 4 steps can be undone.
+		for k in (2..6).rev() {
+			assert!(state.step_back());
+			assert_eq!((state.ticks(), out(&state)), trace[k]);
+		}
+		assert!(!state.step_back());
+		assert_eq!(state.history_position(), 0);
+
+		state.seek(4);
+		assert_eq!((state.ticks(), out(&state)), trace[6]);
+		assert!(!state.step_forward());
+
+		// Stepping after going back discards the undone steps, but gives the same results.
+		state.seek(1);
+		state.step();
+		assert_eq!(state.history_len(), 2);
+		state.advance(2);
+		assert_eq!((state.ticks(), out(&state)), trace[6]);
+	}
+
+	#[test]
+	fn last_change() {
+		let (graph, clock, o) = chain();
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.set_history(16);
+		state.advance(6);
+		// The clock is low at tick 0 and 3 and high otherwise. The output lags two steps behind.
+		assert_eq!(state.last_change(clock), Some(4));
+		assert_eq!(state.last_change(o), Some(5));
+		state.seek(4);
+		assert_eq!(state.last_change(clock), Some(3));
+		assert_eq!(state.last_change(o), Some(2));
+	}
+}
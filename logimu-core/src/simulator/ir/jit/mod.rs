@@ -0,0 +1,208 @@
+//! # JIT compilers for the IR
+//!
+//! Each node of a program is compiled to a native function. [`Jit::new`] returns `None` if the
+//! program can't be compiled, in which case the interpreter has to be used instead. On
+//! architectures for which no JIT is implemented this is always the case. Otherwise it happens if
+//! the program uses ops the JIT doesn't support, such as those accessing RAM, arithmetic, dynamic
+//! shifts or edge detection.
+
+/// Whether a JIT is implemented for this platform.
+pub(super) const SUPPORTED: bool = cfg!(all(target_arch = "x86_64", target_os = "linux"));
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+mod x86_64;
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+pub(super) use x86_64::Jit;
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+pub(super) use unsupported::Jit;
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+mod unsupported {
+	use super::super::program::{Program, Word};
+	use crate::integer_set::IntegerSet;
+
+	#[derive(Debug)]
+	pub(crate) enum Jit {}
+
+	impl Jit {
+		pub(crate) fn new(_: &Program) -> Option<Self> {
+			None
+		}
+
+		pub(crate) fn run(&self, _: usize, _: &[Word], _: &mut [Word], _: &mut IntegerSet) {
+			match *self {}
+		}
+	}
+}
+
+#[cfg(all(test, target_arch = "x86_64", target_os = "linux"))]
+mod test {
+	use super::super::program::{Backend, IrOp, Node, Program, State, Word};
+	use crate::simulator::*;
+	use core::num::{NonZeroU16, NonZeroU8};
+	use std::sync::Arc;
+	use thin_dst::ThinArc;
+
+	/// A xorshift generator so the tests are reproducible.
+	struct Rng(u64);
+
+	impl Rng {
+		fn next(&mut self) -> usize {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			self.0 as usize
+		}
+
+		fn below(&mut self, n: usize) -> usize {
+			self.next() % n
+		}
+
+		/// Generate a word biased towards edge cases.
+		fn word(&mut self) -> Word {
+			let undefined = match self.below(4) {
+				0 => 0,
+				1 => usize::MAX,
+				_ => self.next(),
+			};
+			let value = match self.below(4) {
+				0 => 0,
+				1 => usize::MAX,
+				_ => self.next() & 0xff,
+			};
+			Word { value, undefined }
+		}
+
+		fn op(&mut self, memory_size: usize, nodes: usize, rom: &ThinArc<(), usize>) -> IrOp {
+			let a = self.below(memory_size);
+			match self.below(17) {
+				0 => {
+					let node = self.below(nodes);
+					let mask = if self.below(2) == 0 {
+						usize::MAX
+					} else {
+						self.next()
+					};
+					IrOp::CheckDirty { a, node, mask }
+				}
+				1 => IrOp::Save { out: a },
+				2 => IrOp::And { a },
+				3 => IrOp::Or { a },
+				4 => IrOp::Xor { a },
+				5 => IrOp::Andi { i: self.next() },
+				6 => IrOp::Xori { i: self.next() },
+				7 => IrOp::Slli { i: self.below(70) as u8 },
+				8 => IrOp::Srli { i: self.below(70) as u8 },
+				9 => IrOp::Load { value: self.next() & 0xff },
+				10 => IrOp::Copy { a },
+				11 => IrOp::Read { memory: rom.clone() },
+				12 => IrOp::SaveB { out: a },
+				13 => IrOp::OrB,
+				14 => IrOp::Resolve { a },
+				15 => IrOp::Srai { i: self.below(70) as u8 },
+				_ => IrOp::Enable { a },
+			}
+		}
+	}
+
+	/// Step both backends with the same initial memory and compare the results after each step.
+	fn compare(program: Program, memory: &[Word], steps: usize) {
+		let program = Arc::new(program);
+		let mut int = program.clone().new_state();
+		let mut jit = program.new_state();
+		assert!(jit.set_backend(Backend::Jit));
+		for s in [&mut int, &mut jit] {
+			s.read.copy_from_slice(memory);
+			s.write.copy_from_slice(memory);
+		}
+		for _ in 0..steps {
+			assert_eq!(int.step(), jit.step());
+			assert_eq!(int.read, jit.read);
+			assert_eq!(int.write, jit.write);
+		}
+	}
+
+	#[test]
+	fn random_programs() {
+		let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+		let rom = ThinArc::new((), (0..200).map(|i| i * 3 + 1));
+		for _ in 0..500 {
+			let memory_size = 1 + rng.below(16);
+			let nodes = 1 + rng.below(8);
+			let nodes = (0..nodes)
+				.map(|_| {
+					let len = rng.below(24);
+					let ir = (0..len).map(|_| rng.op(memory_size, nodes, &rom)).collect();
+					Node { ir, delay: NonZeroU8::new(1).unwrap() }
+				})
+				.collect();
+			let memory = (0..memory_size).map(|_| rng.word()).collect::<Vec<_>>();
+			let program = Program { nodes, memory_size, ..Default::default() };
+			compare(program, &memory, 8);
+		}
+	}
+
+	#[test]
+	fn graph() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+
+		let (one, two) = (NonZeroU16::new(1).unwrap(), NonZeroU16::new(2).unwrap());
+		let i0 = graph.add(Box::new(In::new("I0", two, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", two, 1)), ());
+		let en = graph.add(Box::new(In::new("EN", one, 2)), ());
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let xor = graph.add(Box::new(XorGate::new(inputs)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let buf = graph.add(Box::new(TriStateBuffer::new(two)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", two, 0)), ());
+
+		let [n0, n1, n2, n3, n4, n5] = [(); 6].map(|_| graph.new_nexus(()));
+		let connect =
+			|graph: &mut Graph<_, _, _>, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: i0, port: 0 }, n0);
+		connect(&mut graph, Port::Output { node: i1, port: 0 }, n1);
+		connect(&mut graph, Port::Output { node: en, port: 0 }, n2);
+		connect(&mut graph, Port::Input { node: and, port: 0 }, n0);
+		connect(&mut graph, Port::Input { node: and, port: 1 }, n1);
+		connect(&mut graph, Port::Input { node: xor, port: 0 }, n0);
+		connect(&mut graph, Port::Input { node: xor, port: 1 }, n1);
+		connect(&mut graph, Port::Output { node: and, port: 0 }, n3);
+		connect(&mut graph, Port::Input { node: not, port: 0 }, n3);
+		connect(&mut graph, Port::Output { node: not, port: 0 }, n4);
+		connect(&mut graph, Port::Input { node: buf, port: 0 }, n4);
+		connect(&mut graph, Port::Input { node: buf, port: 1 }, n2);
+		connect(&mut graph, Port::Output { node: buf, port: 0 }, n5);
+		connect(&mut graph, Port::Output { node: xor, port: 0 }, n5);
+		connect(&mut graph, Port::Input { node: o0, port: 0 }, n5);
+
+		let program = Arc::new(graph.generate_ir());
+		let mut int = program.clone().new_state();
+		let mut jit = program.new_state();
+		assert!(jit.set_backend(Backend::Jit));
+		let values = [
+			ir::Value::Floating,
+			ir::Value::Short,
+			ir::Value::Set(0),
+			ir::Value::Set(1),
+		];
+		let values = || values.iter().copied().chain((0..4).map(ir::Value::Set));
+		for a in values() {
+			for b in values() {
+				for e in values() {
+					let run = |s: &mut State| {
+						s.write_inputs(&[a, b, e]);
+						s.run(1024);
+						let mut out = [ir::Value::Floating];
+						s.read_outputs(&mut out);
+						out
+					};
+					assert_eq!(run(&mut int), run(&mut jit));
+					assert_eq!(int.read, jit.read);
+				}
+			}
+		}
+	}
+}
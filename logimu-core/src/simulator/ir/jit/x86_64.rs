@@ -0,0 +1,530 @@
+//! JIT for x86_64 using the System V calling convention.
+//!
+//! Each node is compiled to a function taking a pointer to the read memory, the write memory and
+//! the dirty set. The accumulator and the B register are kept in callee-saved registers so that
+//! they survive calls to [`insert_dirty`].
+
+use super::super::program::{IrOp, Program, Word};
+use crate::integer_set::IntegerSet;
+use core::{fmt, mem, ptr};
+use thin_dst::ThinArc;
+
+type NodeFn = unsafe extern "sysv64" fn(*const Word, *mut Word, *mut IntegerSet);
+
+const RAX: u8 = 0;
+const RCX: u8 = 1;
+const RDX: u8 = 2;
+const RBX: u8 = 3;
+const RSP: u8 = 4;
+const RBP: u8 = 5;
+const RSI: u8 = 6;
+const RDI: u8 = 7;
+const R8: u8 = 8;
+const R12: u8 = 12;
+const R13: u8 = 13;
+const R14: u8 = 14;
+const R15: u8 = 15;
+
+/// Pointer to the read memory.
+const RD: u8 = RBX;
+/// Pointer to the write memory.
+const WR: u8 = RBP;
+/// The accumulator.
+const ACC: (u8, u8) = (R12, R13);
+/// The B register.
+const B: (u8, u8) = (R14, R15);
+
+/// Registers saved in the prologue, in order.
+const SAVED: [u8; 6] = [RBX, RBP, R12, R13, R14, R15];
+
+const CC_AE: u8 = 0x3;
+const CC_Z: u8 = 0x4;
+const CC_NZ: u8 = 0x5;
+
+pub(crate) struct Jit {
+	/// Executable memory containing the code of all nodes.
+	code: Executable,
+	/// The entry point of each node.
+	nodes: Box<[NodeFn]>,
+	/// The amount of words the compiled code may access.
+	memory_size: usize,
+	/// Memory referenced by `Read` ops, which must outlive the code.
+	_memories: Box<[ThinArc<(), usize>]>,
+}
+
+// SAFETY: the code is never modified after creation and the memories it references are
+// immutable.
+unsafe impl Send for Jit {}
+unsafe impl Sync for Jit {}
+
+impl Jit {
+	/// Compile a program. Returns `None` if the program is too large, uses ops that can't be
+	/// compiled or executable memory could not be allocated.
+	pub(crate) fn new(program: &Program) -> Option<Self> {
+		let size = program.memory_size;
+		if size.checked_mul(mem::size_of::<Word>())? > i32::MAX as usize {
+			return None;
+		}
+		let mut asm = Assembler::default();
+		let mut memories = Vec::new();
+		let mut offsets = Vec::with_capacity(program.nodes.len());
+		for node in program.nodes.iter() {
+			offsets.push(asm.code.len());
+			asm.node(&node.ir, size, &mut memories)?;
+		}
+
+		let code = Executable::new(&asm.code)?;
+		let nodes = offsets
+			.into_iter()
+			// SAFETY: each offset points to the prologue of a node function.
+			.map(|o| unsafe { mem::transmute::<*mut u8, NodeFn>(code.0.add(o)) })
+			.collect();
+		Some(Self {
+			code,
+			nodes,
+			memory_size: program.memory_size,
+			_memories: memories.into(),
+		})
+	}
+
+	/// Run the code of a single node.
+	///
+	/// # Panics
+	///
+	/// The memory is smaller than the memory of the program.
+	pub(crate) fn run(&self, node: usize, rd: &[Word], wr: &mut [Word], dirty: &mut IntegerSet) {
+		assert!(rd.len() >= self.memory_size && wr.len() >= self.memory_size);
+		// SAFETY: all addresses were checked to be within the program's memory during
+		// compilation.
+		unsafe { (self.nodes[node])(rd.as_ptr(), wr.as_mut_ptr(), dirty) }
+	}
+}
+
+impl fmt::Debug for Jit {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct(stringify!(Jit))
+			.field("size", &self.code.1)
+			.field("nodes", &self.nodes.len())
+			.finish()
+	}
+}
+
+/// A region of executable memory.
+struct Executable(*mut u8, usize);
+
+impl Executable {
+	fn new(code: &[u8]) -> Option<Self> {
+		let size = code.len().max(1);
+		// SAFETY: we map fresh memory and only write within its bounds.
+		unsafe {
+			let p = libc::mmap(
+				ptr::null_mut(),
+				size,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+				-1,
+				0,
+			);
+			if p == libc::MAP_FAILED {
+				return None;
+			}
+			let e = Self(p.cast(), size);
+			ptr::copy_nonoverlapping(code.as_ptr(), e.0, code.len());
+			(libc::mprotect(p, size, libc::PROT_READ | libc::PROT_EXEC) == 0).then(|| e)
+		}
+	}
+}
+
+impl Drop for Executable {
+	fn drop(&mut self) {
+		// SAFETY: the memory was mapped by us and is no longer referenced.
+		unsafe { libc::munmap(self.0.cast(), self.1) };
+	}
+}
+
+extern "sysv64" fn insert_dirty(dirty: &mut IntegerSet, node: usize) {
+	dirty.insert(node);
+}
+
+/// A register or memory operand.
+#[derive(Clone, Copy)]
+enum Rm {
+	Reg(u8),
+	/// A base register with a displacement.
+	Mem(u8, i32),
+}
+
+/// The value part of the word at the given address.
+fn value(base: u8, a: usize) -> Rm {
+	Rm::Mem(base, (a * mem::size_of::<Word>()) as i32)
+}
+
+/// The undefined part of the word at the given address.
+fn undefined(base: u8, a: usize) -> Rm {
+	Rm::Mem(
+		base,
+		(a * mem::size_of::<Word>() + mem::size_of::<usize>()) as i32,
+	)
+}
+
+#[derive(Default)]
+struct Assembler {
+	code: Vec<u8>,
+}
+
+impl Assembler {
+	/// Compile the ops of a single node. Returns `None` if an address is out of range or an op
+	/// isn't supported.
+	fn node(
+		&mut self,
+		ops: &[IrOp],
+		memory_size: usize,
+		memories: &mut Vec<ThinArc<(), usize>>,
+	) -> Option<()> {
+		let check = |a: usize| (a < memory_size).then(|| ());
+
+		SAVED.iter().for_each(|&r| self.push(r));
+		// Keep the stack aligned to 16 bytes and make space for the dirty set pointer.
+		self.op(&[0x83], 5, Rm::Reg(RSP));
+		self.code.push(8);
+		self.store(Rm::Mem(RSP, 0), RDX);
+		self.mov(RD, Rm::Reg(RDI));
+		self.mov(WR, Rm::Reg(RSI));
+		self.load_imm(ACC, 0, 0);
+		self.load_imm(B, 0, usize::MAX);
+
+		for op in ops {
+			match op {
+				&IrOp::CheckDirty { a, node, mask } => {
+					check(a)?;
+					self.mov(RAX, value(WR, a));
+					self.alu(0x33, RAX, value(RD, a));
+					self.mov(RCX, undefined(WR, a));
+					self.alu(0x33, RCX, undefined(RD, a));
+					self.alu(0x0b, RAX, Rm::Reg(RCX));
+					if mask != usize::MAX {
+						self.mov_imm(RCX, mask);
+						self.alu(0x23, RAX, Rm::Reg(RCX));
+					}
+					let skip = self.jcc(CC_Z);
+					self.mov(RDI, Rm::Mem(RSP, 0));
+					self.mov_imm(RSI, node);
+					self.mov_imm(RAX, insert_dirty as *const () as usize);
+					self.op(&[0xff], 2, Rm::Reg(RAX));
+					self.bind(skip);
+				}
+				&IrOp::Save { out } => {
+					check(out)?;
+					self.save(out, ACC);
+				}
+				&IrOp::SaveB { out } => {
+					check(out)?;
+					self.save(out, B);
+				}
+				&IrOp::Copy { a } => {
+					check(a)?;
+					self.mov(ACC.0, value(RD, a));
+					self.mov(ACC.1, undefined(RD, a));
+				}
+				&IrOp::Load { value } => self.load_imm(ACC, value, 0),
+				&IrOp::And { a } => {
+					check(a)?;
+					self.load_rhs(a);
+					self.and();
+				}
+				&IrOp::Or { a } => {
+					check(a)?;
+					self.load_rhs(a);
+					self.or();
+				}
+				&IrOp::Xor { a } => {
+					check(a)?;
+					self.load_rhs(a);
+					self.alu(0x33, ACC.0, Rm::Reg(RAX));
+					self.alu(0x0b, ACC.1, Rm::Reg(RCX));
+					self.alu(0x0b, ACC.0, Rm::Reg(ACC.1));
+				}
+				&IrOp::Andi { i } => {
+					self.mov_imm(RAX, i);
+					self.alu(0x23, ACC.0, Rm::Reg(RAX));
+					self.not(RAX);
+					self.alu(0x0b, ACC.1, Rm::Reg(RAX));
+				}
+				&IrOp::Xori { i } => {
+					self.mov_imm(RAX, i);
+					self.mov(RDX, Rm::Reg(ACC.1));
+					self.not(RDX);
+					self.alu(0x23, RDX, Rm::Reg(RAX));
+					self.alu(0x33, ACC.0, Rm::Reg(RDX));
+					self.alu(0x23, RAX, Rm::Reg(ACC.1));
+					self.alu(0x0b, ACC.0, Rm::Reg(RAX));
+				}
+				&IrOp::Slli { i } => self.shift(4, i),
+				&IrOp::Srli { i } => self.shift(5, i),
+				&IrOp::Srai { i } => self.sar(i),
+				IrOp::Read { memory } => self.read(memory, memories),
+				IrOp::OrB => self.resolve(B, ACC),
+				&IrOp::Resolve { a } => {
+					check(a)?;
+					self.load_rhs(a);
+					self.resolve(ACC, (RAX, RCX));
+				}
+				&IrOp::Enable { a } => {
+					check(a)?;
+					self.load_rhs(a);
+					self.enable();
+				}
+				// Ops indexing memory dynamically or writing conditionally are left to the
+				// interpreter.
+				IrOp::Fetch { .. }
+				| IrOp::Store { .. }
+				| IrOp::SaveIf { .. }
+				| IrOp::SetIf { .. }
+				| IrOp::Eqi { .. }
+				| IrOp::Add { .. }
+				| IrOp::Sub { .. }
+				| IrOp::Mul { .. }
+				| IrOp::Div { .. }
+				| IrOp::Rem { .. }
+				| IrOp::Lt { .. }
+				| IrOp::Eq { .. }
+				| IrOp::Sll { .. }
+				| IrOp::Srl { .. }
+				| IrOp::Sra { .. }
+				| IrOp::Rotl { .. }
+				| IrOp::Rotr { .. }
+				| IrOp::Popcnt { .. }
+				| IrOp::Clz { .. }
+				| IrOp::Edge { .. } => return None,
+			}
+		}
+
+		self.op(&[0x83], 0, Rm::Reg(RSP));
+		self.code.push(8);
+		SAVED.iter().rev().for_each(|&r| self.pop(r));
+		self.code.push(0xc3);
+		Some(())
+	}
+
+	/// Load the word at the given address in `rax` and `rcx`.
+	fn load_rhs(&mut self, a: usize) {
+		self.mov(RAX, value(RD, a));
+		self.mov(RCX, undefined(RD, a));
+	}
+
+	fn save(&mut self, out: usize, (v, u): (u8, u8)) {
+		self.store(value(WR, out), v);
+		self.store(undefined(WR, out), u);
+	}
+
+	/// `acc = acc.and(rhs)` with the rhs in `rax` and `rcx`.
+	fn and(&mut self) {
+		// undefined = (u | rhs.u) & (u | v) & (rhs.u | rhs.v)
+		self.mov(RDX, Rm::Reg(ACC.1));
+		self.alu(0x0b, RDX, Rm::Reg(ACC.0));
+		self.mov(RSI, Rm::Reg(RCX));
+		self.alu(0x0b, RSI, Rm::Reg(RAX));
+		self.alu(0x23, RDX, Rm::Reg(RSI));
+		self.mov(RSI, Rm::Reg(ACC.1));
+		self.alu(0x0b, RSI, Rm::Reg(RCX));
+		self.alu(0x23, RDX, Rm::Reg(RSI));
+		self.alu(0x23, ACC.0, Rm::Reg(RAX));
+		self.alu(0x0b, ACC.0, Rm::Reg(RDX));
+		self.mov(ACC.1, Rm::Reg(RDX));
+	}
+
+	/// `acc = acc.or(rhs)` with the rhs in `rax` and `rcx`.
+	fn or(&mut self) {
+		// undefined = (u | rhs.u) & (u | !v) & (rhs.u | !rhs.v)
+		self.mov(RDX, Rm::Reg(ACC.0));
+		self.not(RDX);
+		self.alu(0x0b, RDX, Rm::Reg(ACC.1));
+		self.mov(RSI, Rm::Reg(RAX));
+		self.not(RSI);
+		self.alu(0x0b, RSI, Rm::Reg(RCX));
+		self.alu(0x23, RDX, Rm::Reg(RSI));
+		self.mov(RSI, Rm::Reg(ACC.1));
+		self.alu(0x0b, RSI, Rm::Reg(RCX));
+		self.alu(0x23, RDX, Rm::Reg(RSI));
+		self.alu(0x0b, ACC.0, Rm::Reg(RAX));
+		self.alu(0x0b, ACC.0, Rm::Reg(RDX));
+		self.mov(ACC.1, Rm::Reg(RDX));
+	}
+
+	/// Shift the accumulator left (`ext = 4`) or right (`ext = 5`). Shifted in bits are floating.
+	fn shift(&mut self, ext: u8, i: u8) {
+		if i >= 64 {
+			self.load_imm(ACC, 0, usize::MAX);
+		} else {
+			self.op(&[0xc1], ext, Rm::Reg(ACC.0));
+			self.code.push(i);
+			self.not(ACC.1);
+			self.op(&[0xc1], ext, Rm::Reg(ACC.1));
+			self.code.push(i);
+			self.not(ACC.1);
+		}
+	}
+
+	/// Shift the accumulator right, copying the highest bit.
+	fn sar(&mut self, i: u8) {
+		for r in [ACC.0, ACC.1] {
+			self.op(&[0xc1], 7, Rm::Reg(r));
+			self.code.push(i.min(63));
+		}
+	}
+
+	/// `s = s.resolve(r)`. `r` must not be in `rdx`, `rsi`, `rdi` or `r8`.
+	fn resolve(&mut self, (sv, su): (u8, u8), (rv, ru): (u8, u8)) {
+		// a = s.driven(), b = r.driven()
+		self.mov(RDX, Rm::Reg(su));
+		self.not(RDX);
+		self.alu(0x0b, RDX, Rm::Reg(sv));
+		self.mov(RSI, Rm::Reg(ru));
+		self.not(RSI);
+		self.alu(0x0b, RSI, Rm::Reg(rv));
+		// only_b = b & !a, both = a & b
+		self.mov(RDI, Rm::Reg(RDX));
+		self.not(RDI);
+		self.alu(0x23, RDI, Rm::Reg(RSI));
+		self.alu(0x23, RDX, Rm::Reg(RSI));
+		for (s, r) in [(sv, rv), (su, ru)] {
+			self.mov(R8, Rm::Reg(RDI));
+			self.not(R8);
+			self.alu(0x23, s, Rm::Reg(R8));
+			self.mov(R8, Rm::Reg(r));
+			self.alu(0x23, R8, Rm::Reg(RDI));
+			self.alu(0x0b, s, Rm::Reg(R8));
+			self.alu(0x0b, s, Rm::Reg(RDX));
+		}
+	}
+
+	/// `acc = acc.enable(rhs)` with the rhs in `rax` and `rcx`.
+	fn enable(&mut self) {
+		self.test_imm(RCX, 1);
+		let undefined = self.jcc(CC_NZ);
+		self.test_imm(RAX, 1);
+		let end_a = self.jcc(CC_NZ);
+		self.load_imm(ACC, 0, usize::MAX);
+		let end_b = self.jmp();
+		self.bind(undefined);
+		self.not(ACC.1);
+		self.alu(0x0b, ACC.1, Rm::Reg(ACC.0));
+		self.mov(ACC.0, Rm::Reg(ACC.1));
+		self.mov_imm(ACC.1, usize::MAX);
+		self.bind(end_a);
+		self.bind(end_b);
+	}
+
+	/// Read the word at the address in the accumulator.
+	fn read(&mut self, memory: &ThinArc<(), usize>, memories: &mut Vec<ThinArc<(), usize>>) {
+		// Floating address bits are read as 0.
+		self.mov(RAX, Rm::Reg(ACC.1));
+		self.alu(0x23, RAX, Rm::Reg(ACC.0));
+		let short = self.jcc(CC_NZ);
+		self.mov_imm(RAX, memory.slice.len());
+		self.alu(0x3b, ACC.0, Rm::Reg(RAX));
+		let out_of_range = self.jcc(CC_AE);
+		self.mov_imm(RAX, memory.slice.as_ptr() as usize);
+		self.op(&[0xc1], 4, Rm::Reg(ACC.0));
+		self.code.push(3);
+		self.alu(0x03, RAX, Rm::Reg(ACC.0));
+		self.mov(ACC.0, Rm::Mem(RAX, 0));
+		self.mov_imm(ACC.1, 0);
+		let end_a = self.jmp();
+		self.bind(out_of_range);
+		self.load_imm(ACC, 0, 0);
+		let end_b = self.jmp();
+		self.bind(short);
+		self.load_imm(ACC, usize::MAX, usize::MAX);
+		self.bind(end_a);
+		self.bind(end_b);
+		memories.push(memory.clone());
+	}
+
+	/// Emit an instruction with a ModR/M byte and a 64-bit operand size.
+	fn op(&mut self, opcode: &[u8], reg: u8, rm: Rm) {
+		let base = match rm {
+			Rm::Reg(r) | Rm::Mem(r, _) => r,
+		};
+		self.code.push(0x48 | (reg >> 3) << 2 | base >> 3);
+		self.code.extend(opcode);
+		match rm {
+			Rm::Reg(r) => self.code.push(0xc0 | (reg & 7) << 3 | r & 7),
+			Rm::Mem(r, disp) => {
+				self.code.push(0x80 | (reg & 7) << 3 | r & 7);
+				if r & 7 == RSP {
+					// SIB without index
+					self.code.push(0x24);
+				}
+				self.code.extend(disp.to_le_bytes());
+			}
+		}
+	}
+
+	/// `mov reg, rm`
+	fn mov(&mut self, reg: u8, rm: Rm) {
+		self.op(&[0x8b], reg, rm);
+	}
+
+	/// `mov rm, reg`
+	fn store(&mut self, rm: Rm, reg: u8) {
+		self.op(&[0x89], reg, rm);
+	}
+
+	/// An arithmetic instruction of the form `op reg, rm`.
+	fn alu(&mut self, opcode: u8, reg: u8, rm: Rm) {
+		self.op(&[opcode], reg, rm);
+	}
+
+	fn not(&mut self, reg: u8) {
+		self.op(&[0xf7], 2, Rm::Reg(reg));
+	}
+
+	fn test_imm(&mut self, reg: u8, imm: i32) {
+		self.op(&[0xf7], 0, Rm::Reg(reg));
+		self.code.extend(imm.to_le_bytes());
+	}
+
+	fn mov_imm(&mut self, reg: u8, imm: usize) {
+		self.code.extend([0x48 | reg >> 3, 0xb8 | reg & 7]);
+		self.code.extend(imm.to_le_bytes());
+	}
+
+	/// Load a word in a pair of registers.
+	fn load_imm(&mut self, (v, u): (u8, u8), value: usize, undefined: usize) {
+		self.mov_imm(v, value);
+		self.mov_imm(u, undefined);
+	}
+
+	fn push(&mut self, reg: u8) {
+		if reg >= 8 {
+			self.code.push(0x41);
+		}
+		self.code.push(0x50 | reg & 7);
+	}
+
+	fn pop(&mut self, reg: u8) {
+		if reg >= 8 {
+			self.code.push(0x41);
+		}
+		self.code.push(0x58 | reg & 7);
+	}
+
+	/// Emit a conditional jump to be resolved with [`Self::bind`].
+	fn jcc(&mut self, cc: u8) -> usize {
+		self.code.extend([0x0f, 0x80 | cc, 0, 0, 0, 0]);
+		self.code.len()
+	}
+
+	/// Emit a jump to be resolved with [`Self::bind`].
+	fn jmp(&mut self) -> usize {
+		self.code.extend([0xe9, 0, 0, 0, 0]);
+		self.code.len()
+	}
+
+	/// Make a jump target the current position.
+	fn bind(&mut self, jump: usize) {
+		let rel = (self.code.len() - jump) as i32;
+		self.code[jump - 4..jump].copy_from_slice(&rel.to_le_bytes());
+	}
+}
@@ -0,0 +1,82 @@
+//! # Component memory
+//!
+//! Components such as RAM keep their contents in the memory of the state, so it is stepped back,
+//! saved in snapshots and kept when adapting to a new program like any other memory. Components
+//! register the memory they index so it can be inspected and edited while simulating.
+//!
+//! A region holds `len` entries. Word `k` of entry `i` is at `base + k * len + i`, which keeps
+//! each word of all entries contiguous so it can be indexed with a single op.
+
+use super::super::GraphNodeHandle;
+use super::program::{word_mask, words, State, Value, Word};
+
+/// Memory of a component that can be inspected and edited while simulating.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Region {
+	/// The first word of the region.
+	pub(crate) base: usize,
+	/// The amount of entries.
+	pub(crate) len: usize,
+	/// The amount of bits of each entry.
+	pub(crate) bits: u16,
+}
+
+impl Region {
+	/// The address of each word of an entry.
+	fn entry(&self, index: usize) -> impl Iterator<Item = (usize, usize)> {
+		assert!(index < self.len, "index out of range");
+		let (base, len, bits) = (self.base, self.len, self.bits);
+		(0..words(bits)).map(move |k| (base + k * len + index, word_mask(bits, k)))
+	}
+}
+
+impl State {
+	/// The region of memory of a component, if it has any.
+	fn region(&self, component: GraphNodeHandle) -> Option<Region> {
+		let mut m = self.program.memories.iter();
+		m.find(|(c, _)| *c == component).map(|&(_, r)| r)
+	}
+
+	/// The amount of entries in the memory of a component, or `None` if it has no memory that
+	/// can be inspected.
+	pub fn memory_len(&self, component: GraphNodeHandle) -> Option<usize> {
+		self.region(component).map(|r| r.len)
+	}
+
+	/// The amount of bits of each entry in the memory of a component.
+	pub fn memory_bits(&self, component: GraphNodeHandle) -> Option<u16> {
+		self.region(component).map(|r| r.bits)
+	}
+
+	/// Read an entry of the memory of a component.
+	///
+	/// # Panics
+	///
+	/// The component has no memory or the index is out of range.
+	pub fn read_memory(&self, component: GraphNodeHandle, index: usize) -> Value {
+		let r = self.region(component).expect("component has no memory");
+		Value::from_words(r.entry(index).map(|(a, m)| self.read[a].to_value(m)))
+	}
+
+	/// Write an entry of the memory of a component. The nodes of the component are updated in
+	/// the next step.
+	///
+	/// # Panics
+	///
+	/// The component has no memory or the index is out of range.
+	pub fn write_memory(&mut self, component: GraphNodeHandle, index: usize, value: Value) {
+		let r = self.region(component).expect("component has no memory");
+		let mut changed = false;
+		for (k, (a, m)) in r.entry(index).enumerate() {
+			let w = Word::from_value(value.word(k), m);
+			changed |= self.read[a] != w;
+			(self.read[a], self.write[a]) = (w, w);
+		}
+		if changed {
+			let nodes = self.program.components.iter().enumerate();
+			for (n, _) in nodes.filter(|(_, c)| **c == Some(component)) {
+				self.update_dirty.insert(n);
+			}
+		}
+	}
+}
@@ -0,0 +1,31 @@
+//! # Intermediate representation of circuits
+//!
+//! Converting circuits to an IR makes it much easier to convert it later on
+//! to machine code, allowing very fast simulation of large circuits.
+//!
+//! An interpreter is also supplied to support architectures for which no JIT
+//! has been implemented yet. Currently only x86_64 on Linux has a JIT.
+
+pub mod batch;
+mod breakpoint;
+mod delay;
+mod history;
+mod jit;
+mod memory;
+mod optimize;
+mod oscillation;
+mod parallel;
+mod probe;
+pub mod program;
+mod snapshot;
+mod vcd;
+
+pub use breakpoint::{Breakpoint, Condition, Watch};
+pub(crate) use memory::Region;
+pub use oscillation::{Oscillation, RunResult};
+pub(super) use program::Node;
+pub use program::{
+	word_mask, words, Backend, IrOp, Program, State, Value, MAX_BITS, MAX_WORDS, WORD_BITS,
+};
+pub use snapshot::{RestoreError, Snapshot};
+pub use vcd::Vcd;
@@ -0,0 +1,647 @@
+//! # Optimization passes
+//!
+//! All passes preserve the values of the outputs once the circuit has settled, though the amount
+//! of steps needed to settle may change. Other memory may not be kept up to date, so optimized
+//! programs should only be used where nothing but the outputs is inspected, e.g. inside ICs.
+//!
+//! The memory layout is never changed.
+
+use super::program::{run, words, Direct, IrOp, Program, Word};
+use crate::integer_set::IntegerSet;
+use core::mem;
+use core::num::NonZeroU8;
+use std::collections::{HashMap, HashSet};
+
+impl Program {
+	/// Run all optimization passes.
+	pub fn optimize(&mut self) {
+		self.propagate_constants();
+		self.remove_dead_nodes();
+		self.merge_chains();
+		self.remove_redundant_checks();
+	}
+
+	/// Evaluate nodes that only depend on constants and replace copies of their results with
+	/// immediate loads. Results that are still needed are saved by a single node which only runs
+	/// once.
+	fn propagate_constants(&mut self) {
+		// The node writing to each address, `usize::MAX` if there is none and `MANY` if there are
+		// multiple or the address is written to externally.
+		const MANY: usize = usize::MAX - 1;
+		let mut writer = vec![usize::MAX; self.memory_size];
+		for (n, node) in self.nodes.iter().enumerate() {
+			for a in node.ir.iter().filter_map(IrOp::writes) {
+				writer[a] = if writer[a] == usize::MAX || writer[a] == n {
+					n
+				} else {
+					MANY
+				};
+			}
+		}
+		for a in io_memory(&self.input_map) {
+			writer[a] = MANY;
+		}
+
+		// Evaluate constant nodes until no more constants are found.
+		let mut constant = vec![false; self.memory_size];
+		let mut removed = vec![false; self.nodes.len()];
+		let mut memory = vec![Word::FLOATING; self.memory_size];
+		let mut scratch = memory.clone();
+		let mut changed = true;
+		while mem::take(&mut changed) {
+			for (n, node) in self.nodes.iter().enumerate() {
+				// The contents of a region may change at any time.
+				let is_constant = |op: &IrOp| {
+					op.reads().all(|a| constant[a])
+						&& op.writes().map_or(true, |a| writer[a] == n)
+						&& op.region().is_none()
+				};
+				if removed[n] || !node.ir.iter().all(is_constant) {
+					continue;
+				}
+				let dirty = &mut IntegerSet::default();
+				run::<false>(&node.ir, &memory, &mut Direct { wr: &mut scratch, dirty });
+				for a in node.ir.iter().filter_map(IrOp::writes) {
+					(memory[a], constant[a]) = (scratch[a], true);
+				}
+				(removed[n], changed) = (true, true);
+			}
+		}
+		if !removed.contains(&true) {
+			return;
+		}
+
+		// Fold constants into the remaining nodes.
+		let mut readers = vec![Vec::new(); self.memory_size];
+		for (n, node) in self.nodes.iter_mut().enumerate() {
+			if removed[n] {
+				continue;
+			}
+			let mut ir = Vec::with_capacity(node.ir.len());
+			for op in node.ir.iter() {
+				match *op {
+					IrOp::Copy { a } if constant[a] => ir.extend(load_word(memory[a])),
+					_ => {
+						op.reads()
+							.filter(|&a| constant[a])
+							.for_each(|a| readers[a].push(n));
+						ir.push(op.clone());
+					}
+				}
+			}
+			node.ir = ir.into();
+		}
+		let mut ir = Vec::new();
+		let outputs = io_memory(&self.output_map).collect::<HashSet<_>>();
+		for a in 0..self.memory_size {
+			if constant[a] && (outputs.contains(&a) || !readers[a].is_empty()) {
+				ir.extend(load_word(memory[a]));
+				ir.push(IrOp::Save { out: a });
+				let checks =
+					readers[a]
+						.iter()
+						.map(|&node| IrOp::CheckDirty { a, node, mask: usize::MAX });
+				ir.extend(checks);
+			}
+		}
+
+		let mut target = (0..self.nodes.len())
+			.map(|n| (!removed[n]).then(|| n))
+			.collect::<Vec<_>>();
+		if !ir.is_empty() {
+			target.push(Some(self.nodes.len()));
+			let mut nodes = mem::take(&mut self.nodes).into_vec();
+			let delay = NonZeroU8::new(1).unwrap();
+			nodes.push(super::Node { ir: ir.into(), delay });
+			self.nodes = nodes.into();
+		}
+		self.compact(&target);
+	}
+
+	/// Remove nodes and writes that do not affect any output.
+	fn remove_dead_nodes(&mut self) {
+		let mut writers = vec![Vec::new(); self.memory_size];
+		for (n, node) in self.nodes.iter().enumerate() {
+			node.ir
+				.iter()
+				.filter_map(IrOp::writes)
+				.for_each(|a| writers[a].push(n));
+			// Any entry of a region may be written by a store.
+			for op in node.ir.iter().filter(|op| matches!(op, IrOp::Store { .. })) {
+				let (base, len) = op.region().unwrap();
+				(base..base + len).for_each(|a| writers[a].push(n));
+			}
+		}
+
+		let mut live_memory = vec![false; self.memory_size];
+		let mut live = vec![false; self.nodes.len()];
+		let mut stack = Vec::new();
+		for a in io_memory(&self.output_map) {
+			live_memory[a] = true;
+			stack.push(a);
+		}
+		while let Some(a) = stack.pop() {
+			for &n in writers[a].iter() {
+				if !mem::replace(&mut live[n], true) {
+					let ir = self.nodes[n].ir.iter();
+					let fetches = ir.clone().filter(|op| matches!(op, IrOp::Fetch { .. }));
+					let regions = fetches.flat_map(|op| {
+						let (base, len) = op.region().unwrap();
+						base..base + len
+					});
+					for b in ir.flat_map(IrOp::reads).chain(regions) {
+						if !mem::replace(&mut live_memory[b], true) {
+							stack.push(b);
+						}
+					}
+				}
+			}
+		}
+
+		for (n, node) in self.nodes.iter_mut().enumerate() {
+			if live[n] {
+				node.ir = node
+					.ir
+					.iter()
+					.filter(|op| match op {
+						IrOp::Save { out: a }
+						| IrOp::SaveB { out: a }
+						| IrOp::SaveIf { out: a, .. }
+						| IrOp::SetIf { out: a, .. } => live_memory[*a],
+						IrOp::CheckDirty { a, .. } => live_memory[*a],
+						_ => true,
+					})
+					.cloned()
+					.collect();
+			}
+		}
+		let target = (0..self.nodes.len())
+			.map(|n| live[n].then(|| n))
+			.collect::<Vec<_>>();
+		self.compact(&target);
+	}
+
+	/// Merge nodes computing a single value into the only node reading that value.
+	///
+	/// The value is still saved so it can be used as an output.
+	fn merge_chains(&mut self) {
+		let mut input = vec![false; self.memory_size];
+		for a in io_memory(&self.input_map) {
+			input[a] = true;
+		}
+		loop {
+			let mut writers = vec![Vec::new(); self.memory_size];
+			let mut readers = vec![Vec::new(); self.memory_size];
+			for (n, node) in self.nodes.iter().enumerate() {
+				node.ir
+					.iter()
+					.filter_map(IrOp::writes)
+					.for_each(|a| writers[a].push(n));
+				node.ir
+					.iter()
+					.flat_map(IrOp::reads)
+					.for_each(|a| readers[a].push(n));
+			}
+
+			let mut target = (0..self.nodes.len()).map(Some).collect::<Vec<_>>();
+			let mut used = vec![false; self.nodes.len()];
+			let mut merged = false;
+			for a in 0..self.nodes.len() {
+				let x = match final_save(&self.nodes[a].ir) {
+					Some(x) if !used[a] && !input[x] && writers[x] == [a] => x,
+					_ => continue,
+				};
+				// Merging changes the timing of the nodes, so leave delayed nodes alone.
+				let delays = |n: usize| self.nodes[n].delay.get();
+				let b = match readers[x][..] {
+					[b] if b != a && !used[b] && delays(a) == 1 && delays(b) == 1 => b,
+					_ => continue,
+				};
+				if let Some(ir) = inline(&self.nodes[a].ir, &self.nodes[b].ir, x) {
+					self.nodes[b].ir = ir;
+					self.nodes[a].ir = Box::default();
+					target[a] = Some(b);
+					(used[a], used[b], merged) = (true, true, true);
+				}
+			}
+			if !merged {
+				break;
+			}
+			self.compact(&target);
+		}
+	}
+
+	/// Remove duplicate `CheckDirty` ops, ops checking memory that isn't written by the node
+	/// itself and ops marking nodes that don't read the memory. The masks of duplicates are
+	/// combined.
+	fn remove_redundant_checks(&mut self) {
+		let reads = self
+			.nodes
+			.iter()
+			.map(|node| node.ir.iter().flat_map(IrOp::reads).collect::<HashSet<_>>())
+			.collect::<Vec<_>>();
+		for node in self.nodes.iter_mut() {
+			let writes = node
+				.ir
+				.iter()
+				.filter_map(IrOp::writes)
+				.collect::<HashSet<_>>();
+			let mut masks = HashMap::<_, usize>::new();
+			for op in node.ir.iter() {
+				if let IrOp::CheckDirty { a, node, mask } = *op {
+					*masks.entry((a, node)).or_default() |= mask;
+				}
+			}
+			// Only the last check of a duplicate is kept as it sees the final value.
+			let mut ir = node
+				.ir
+				.iter()
+				.rev()
+				.filter_map(|op| match *op {
+					IrOp::CheckDirty { a, node, .. } => {
+						let mask = masks.remove(&(a, node))?;
+						(writes.contains(&a) && reads[node].contains(&a))
+							.then(|| IrOp::CheckDirty { a, node, mask })
+					}
+					_ => Some(op.clone()),
+				})
+				.collect::<Vec<_>>();
+			ir.reverse();
+			node.ir = ir.into();
+		}
+	}
+
+	/// Remove nodes and redirect references to them.
+	///
+	/// Nodes for which `target[n] == Some(n)` are kept. References to any other node `n` are
+	/// redirected to `target[n]`, which must be kept, or removed if it is `None`.
+	fn compact(&mut self, target: &[Option<usize>]) {
+		let mut index = Vec::with_capacity(target.len());
+		let mut kept = 0;
+		for (n, t) in target.iter().enumerate() {
+			index.push(kept);
+			kept += usize::from(*t == Some(n));
+		}
+		let map = |n: usize| target[n].map(|t| index[t]);
+
+		self.nodes = mem::take(&mut self.nodes)
+			.into_vec()
+			.into_iter()
+			.enumerate()
+			.filter(|&(n, _)| target[n] == Some(n))
+			.map(|(_, mut node)| {
+				node.ir = node
+					.ir
+					.iter()
+					.filter_map(|op| match *op {
+						IrOp::CheckDirty { a, node, mask } => {
+							map(node).map(|node| IrOp::CheckDirty { a, node, mask })
+						}
+						_ => Some(op.clone()),
+					})
+					.collect();
+				node
+			})
+			.collect();
+		// Merged nodes keep the component of the node they were merged into.
+		self.components = mem::take(&mut self.components)
+			.into_vec()
+			.into_iter()
+			.enumerate()
+			.filter(|&(n, _)| target.get(n) == Some(&Some(n)))
+			.map(|(_, c)| c)
+			.collect();
+		for nodes in self.input_nodes_map.iter_mut() {
+			let mut v = Vec::<(usize, usize)>::with_capacity(nodes.len());
+			for (n, mask) in nodes.iter().filter_map(|&(n, m)| Some((map(n)?, m))) {
+				match v.iter_mut().find(|e| e.0 == n) {
+					Some(e) => e.1 |= mask,
+					None => v.push((n, mask)),
+				}
+			}
+			*nodes = v.into();
+		}
+		for nodes in self.clocks.iter_mut().map(|c| &mut c.nodes) {
+			let mut v = Vec::with_capacity(nodes.len());
+			for n in nodes.iter().filter_map(|&n| map(n)) {
+				(!v.contains(&n)).then(|| v.push(n));
+			}
+			*nodes = v.into();
+		}
+	}
+}
+
+/// The memory of all words of the inputs or outputs in a map.
+fn io_memory(map: &[(usize, u16)]) -> impl Iterator<Item = usize> + '_ {
+	map.iter()
+		.filter(|(a, _)| *a != usize::MAX)
+		.flat_map(|&(a, bits)| a..a + words(bits))
+}
+
+/// Ops loading the given word in the accumulator.
+fn load_word(w: Word) -> impl Iterator<Item = IrOp> {
+	let short = w.value & w.undefined;
+	[
+		Some(IrOp::Load { value: w.value & !w.undefined }),
+		// Make the undefined bits floating
+		(w.undefined != 0).then(|| IrOp::Andi { i: !w.undefined }),
+		// Turn floating bits into shorted bits
+		(short != 0).then(|| IrOp::Xori { i: short }),
+	]
+	.into_iter()
+	.flatten()
+}
+
+/// If a node ends with saving the accumulator and doesn't use the B register, return the address
+/// it saves to.
+fn final_save(ir: &[IrOp]) -> Option<usize> {
+	let mut it = ir
+		.iter()
+		.filter(|op| !matches!(op, IrOp::CheckDirty { .. }));
+	match *it.next_back()? {
+		IrOp::Save { out } if !it.any(|op| matches!(op, IrOp::OrB | IrOp::SaveB { .. })) => {
+			Some(out)
+		}
+		_ => None,
+	}
+}
+
+/// Inline the IR of node `a`, which computes `x`, into node `b`, which reads `x` once.
+///
+/// `x` must be the first value copied by `b`. If it isn't, it is made so by swapping operands
+/// of associative and commutative ops.
+fn inline(a: &[IrOp], b: &[IrOp], x: usize) -> Option<Box<[IrOp]>> {
+	let mut b = b.to_vec();
+	let f = b
+		.iter()
+		.position(|op| !matches!(op, IrOp::CheckDirty { .. }))?;
+	let k = b.iter().position(|op| op.reads().any(|a| a == x))?;
+	let y = match b[f] {
+		IrOp::Copy { a } => a,
+		_ => return None,
+	};
+	if k != f {
+		let kind = mem::discriminant(&b[k]);
+		let reorder = b[f + 1..k]
+			.iter()
+			.all(|op| matches!(op, IrOp::CheckDirty { .. }) || mem::discriminant(op) == kind);
+		match &mut b[k] {
+			IrOp::And { a } | IrOp::Or { a } | IrOp::Xor { a } | IrOp::Resolve { a } if reorder => {
+				*a = y
+			}
+			_ => return None,
+		}
+	}
+	b.remove(f);
+	Some(
+		a.iter()
+			.filter(|op| !matches!(op, IrOp::CheckDirty { a, .. } if *a == x))
+			.cloned()
+			.chain(b)
+			.collect(),
+	)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::NonZeroU16;
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// Connect an output port to input ports with a new nexus.
+	fn wire(graph: &mut G, from: (GraphNodeHandle, usize), to: &[(GraphNodeHandle, usize)]) {
+		let n = Some(graph.new_nexus(()));
+		let (node, port) = from;
+		graph.connect(Port::Output { node, port }, n).unwrap();
+		for &(node, port) in to {
+			graph.connect(Port::Input { node, port }, n).unwrap();
+		}
+	}
+
+	/// All combinations of the values of inputs with the given widths, including floating.
+	fn all_inputs(bits: &[u8]) -> Vec<Vec<ir::Value>> {
+		bits.iter().fold(vec![Vec::new()], |v, &b| {
+			v.into_iter()
+				.flat_map(|i| {
+					let values = (0..1 << b).map(ir::Value::Set);
+					values.chain(Some(ir::Value::Floating)).map(move |e| {
+						let mut i = i.clone();
+						i.push(e);
+						i
+					})
+				})
+				.collect()
+		})
+	}
+
+	/// Check that a pass doesn't change the outputs for the given inputs. Returns the original
+	/// and optimized program.
+	fn compare(graph: &G, pass: fn(&mut Program), bits: &[u8]) -> (Arc<Program>, Arc<Program>) {
+		let original = Arc::new(graph.generate_ir());
+		let mut optimized = graph.generate_ir();
+		pass(&mut optimized);
+		let optimized = Arc::new(optimized);
+		let mut states = [original.clone().new_state(), optimized.clone().new_state()];
+		let mut outputs = [(); 2].map(|_| vec![ir::Value::Floating; original.output_map.len()]);
+		for inputs in all_inputs(bits) {
+			for (s, o) in states.iter_mut().zip(outputs.iter_mut()) {
+				s.write_inputs(&inputs);
+				s.run(1024);
+				s.read_outputs(o);
+			}
+			assert_eq!(outputs[0], outputs[1], "inputs: {:?}", inputs);
+		}
+		(original, optimized)
+	}
+
+	fn check_count(program: &Program) -> usize {
+		let ir = program.nodes.iter().flat_map(|n| n.ir.iter());
+		ir.filter(|op| matches!(op, IrOp::CheckDirty { .. }))
+			.count()
+	}
+
+	/// ```
+	/// c0 --v
+	///     AND --> NOT --v
+	/// c1 --^           XOR --> o0
+	/// i0 --------------^
+	///
+	/// c1 --> o1
+	/// ```
+	#[test]
+	fn propagate_constants() {
+		let mut graph = G::new();
+		let bits = NonZeroU16::new(4).unwrap();
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let c0 = graph.add(Box::new(Constant::new(bits, 0b1100)), ());
+		let c1 = graph.add(Box::new(Constant::new(bits, 0b1010)), ());
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let xor = graph.add(Box::new(XorGate::new(inputs)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		let o1 = graph.add(Box::new(Out::new("O1", bits, 1)), ());
+		wire(&mut graph, (c0, 0), &[(and, 0)]);
+		wire(&mut graph, (c1, 0), &[(and, 1), (o1, 0)]);
+		wire(&mut graph, (and, 0), &[(not, 0)]);
+		wire(&mut graph, (not, 0), &[(xor, 0)]);
+		wire(&mut graph, (i0, 0), &[(xor, 1)]);
+		wire(&mut graph, (xor, 0), &[(o0, 0)]);
+
+		let (original, optimized) = compare(&graph, Program::propagate_constants, &[4]);
+		assert_eq!(original.nodes.len(), 5);
+		// The XOR gate and a node saving c1 for o1.
+		assert_eq!(optimized.nodes.len(), 2);
+	}
+
+	/// ```
+	/// i0 --+--v
+	///      | AND --> o0
+	/// i1 --|--^
+	///      |
+	///      +-> NOT --v
+	///               XOR -->
+	/// i1 -----------^
+	/// ```
+	#[test]
+	fn remove_dead_nodes() {
+		let mut graph = G::new();
+		let bits = NonZeroU16::new(2).unwrap();
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", bits, 1)), ());
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let xor = graph.add(Box::new(XorGate::new(inputs)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		wire(&mut graph, (i0, 0), &[(and, 0), (not, 0)]);
+		wire(&mut graph, (i1, 0), &[(and, 1), (xor, 1)]);
+		wire(&mut graph, (not, 0), &[(xor, 0)]);
+		wire(&mut graph, (xor, 0), &[]);
+		wire(&mut graph, (and, 0), &[(o0, 0)]);
+
+		let (original, optimized) = compare(&graph, Program::remove_dead_nodes, &[2, 2]);
+		assert_eq!(original.nodes.len(), 3);
+		assert_eq!(optimized.nodes.len(), 1);
+		assert!(optimized
+			.input_nodes_map
+			.iter()
+			.all(|n| n.len() == 1 && n[0].0 == 0));
+	}
+
+	/// ```
+	/// i0 --v
+	///     AND --> NOT --v
+	/// i1 --^            OR --> o0
+	/// i2 ---------------^
+	///
+	/// i3 --> BUF --v
+	/// i4 ----^    bus --> o1
+	/// i0 --> NOT --^
+	/// ```
+	#[test]
+	fn merge_chains() {
+		let mut graph = G::new();
+		let (one, bits) = (NonZeroU16::new(1).unwrap(), NonZeroU16::new(2).unwrap());
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let i = [bits, bits, bits, bits, one]
+			.iter()
+			.enumerate()
+			.map(|(i, &b)| graph.add(Box::new(In::new("", b, i)), ()))
+			.collect::<Vec<_>>();
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let or = graph.add(Box::new(OrGate::new(inputs)), ());
+		let buf = graph.add(Box::new(TriStateBuffer::new(bits)), ());
+		let not2 = graph.add(Box::new(NotGate::new()), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		let o1 = graph.add(Box::new(Out::new("O1", bits, 1)), ());
+		wire(&mut graph, (i[0], 0), &[(and, 0), (not2, 0)]);
+		wire(&mut graph, (i[1], 0), &[(and, 1)]);
+		wire(&mut graph, (i[2], 0), &[(or, 0)]);
+		wire(&mut graph, (i[3], 0), &[(buf, 0)]);
+		wire(&mut graph, (i[4], 0), &[(buf, 1)]);
+		wire(&mut graph, (and, 0), &[(not, 0)]);
+		wire(&mut graph, (not, 0), &[(or, 1)]);
+		wire(&mut graph, (or, 0), &[(o0, 0)]);
+		let bus = graph.new_nexus(());
+		graph
+			.connect(Port::Output { node: buf, port: 0 }, Some(bus))
+			.unwrap();
+		graph
+			.connect(Port::Output { node: not2, port: 0 }, Some(bus))
+			.unwrap();
+		graph
+			.connect(Port::Input { node: o1, port: 0 }, Some(bus))
+			.unwrap();
+
+		let (original, optimized) = compare(&graph, Program::merge_chains, &[2, 2, 1, 2, 1]);
+		assert_eq!(original.nodes.len(), 6);
+		// One node for o0 and two for the bus, as only one driver can be inlined in the resolver.
+		assert_eq!(optimized.nodes.len(), 3);
+	}
+
+	/// ```
+	/// i0 --> NOT --+--v
+	///              | AND --v
+	/// i1 ----------|--^    OR --> o0
+	///              +-------^
+	/// ```
+	#[test]
+	fn remove_redundant_checks() {
+		let mut graph = G::new();
+		let bits = NonZeroU16::new(2).unwrap();
+		let inputs = NonZeroOneU8::new(2).unwrap();
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", bits, 1)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let or = graph.add(Box::new(OrGate::new(inputs)), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		wire(&mut graph, (i0, 0), &[(not, 0)]);
+		wire(&mut graph, (i1, 0), &[(and, 1)]);
+		wire(&mut graph, (not, 0), &[(and, 0), (or, 1)]);
+		wire(&mut graph, (and, 0), &[(or, 0)]);
+		wire(&mut graph, (or, 0), &[(o0, 0)]);
+
+		// Merging AND into OR makes NOT mark the merged node dirty twice.
+		let (_, merged) = compare(&graph, Program::merge_chains, &[2, 2]);
+		let pass = |p: &mut Program| {
+			p.merge_chains();
+			p.remove_redundant_checks();
+		};
+		let (_, optimized) = compare(&graph, pass, &[2, 2]);
+		assert_eq!(check_count(&merged), 2);
+		assert_eq!(check_count(&optimized), 1);
+	}
+
+	#[test]
+	fn optimize() {
+		let mut graph = G::new();
+		let bits = NonZeroU16::new(3).unwrap();
+		let inputs = NonZeroOneU8::new(3).unwrap();
+		let c0 = graph.add(Box::new(Constant::new(bits, 0b101)), ());
+		let i0 = graph.add(Box::new(In::new("I0", bits, 0)), ());
+		let i1 = graph.add(Box::new(In::new("I1", bits, 1)), ());
+		let xor = graph.add(Box::new(XorGate::new(inputs)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let and = graph.add(Box::new(AndGate::new(inputs)), ());
+		let dead = graph.add(Box::new(NotGate::new()), ());
+		let o0 = graph.add(Box::new(Out::new("O0", bits, 0)), ());
+		wire(&mut graph, (c0, 0), &[(xor, 2), (and, 1)]);
+		wire(&mut graph, (i0, 0), &[(xor, 0), (dead, 0)]);
+		wire(&mut graph, (i1, 0), &[(xor, 1), (and, 2)]);
+		wire(&mut graph, (xor, 0), &[(not, 0)]);
+		wire(&mut graph, (not, 0), &[(and, 0)]);
+		wire(&mut graph, (and, 0), &[(o0, 0)]);
+		wire(&mut graph, (dead, 0), &[]);
+
+		let (original, optimized) = compare(&graph, Program::optimize, &[3, 3]);
+		assert_eq!(original.nodes.len(), 5);
+		// One node for o0 and one saving the constant.
+		assert_eq!(optimized.nodes.len(), 2);
+	}
+}
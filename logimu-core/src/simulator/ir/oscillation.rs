@@ -0,0 +1,181 @@
+//! # Oscillation detection
+//!
+//! A circuit without clocks is fully described by its memory, the nodes that need an update and
+//! the writes of delayed nodes that haven't taken effect yet. If it returns to the same state it
+//! will keep repeating the same steps forever, i.e. it oscillates.
+//!
+//! Clocks are part of the state too, but circuits that only keep changing because of a clock are
+//! not considered to oscillate.
+//!
+//! Comparing states is expensive, so only circuits that haven't settled after half of the
+//! allowed iterations are checked. A hash of each state is kept and on a match the steps in
+//! between are replayed to confirm the cycle and find the nexuses and components involved in it.
+
+use super::super::{GraphNodeHandle, NexusHandle};
+use super::delay::Wheel;
+use super::program::{State, Word};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// The outcome of [`State::run`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunResult {
+	/// No more nodes need an update after the given amount of steps.
+	Settled { steps: usize },
+	/// The circuit returned to an earlier state and would keep changing forever.
+	Oscillated(Oscillation),
+	/// The circuit was still changing when the iteration limit was hit.
+	Limit,
+	/// The breakpoint with the given index fired after the given amount of steps.
+	Break { steps: usize, breakpoint: usize },
+}
+
+impl RunResult {
+	pub fn is_settled(&self) -> bool {
+		matches!(self, Self::Settled { .. })
+	}
+}
+
+/// The nexuses and components that keep changing in an oscillating circuit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Oscillation {
+	/// The amount of steps after which the circuit returns to the same state.
+	pub period: usize,
+	/// Nexuses that changed during the cycle, sorted.
+	pub nexuses: Box<[NexusHandle]>,
+	/// Components that were updated during the cycle, sorted.
+	pub components: Box<[GraphNodeHandle]>,
+}
+
+/// Everything that determines the next steps of a state.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+	memory: Box<[Word]>,
+	dirty: Box<[usize]>,
+	wheel: Wheel,
+	/// The position of the current tick in the period of each clock.
+	phases: Box<[u64]>,
+}
+
+/// Detects cycles by remembering the hash of each state.
+#[derive(Default)]
+pub(super) struct Detector {
+	seen: HashMap<u64, usize>,
+}
+
+impl Detector {
+	/// Record the state after the given amount of steps. Returns the period of the cycle if the
+	/// state was seen before.
+	pub(super) fn check(&mut self, state: &State, steps: usize) -> Option<usize> {
+		let mut h = DefaultHasher::new();
+		state.key().hash(&mut h);
+		self.seen.insert(h.finish(), steps).map(|s| steps - s)
+	}
+}
+
+impl State {
+	fn key(&self) -> Key {
+		let phases = self.program.clocks.iter().map(|c| {
+			let period = u64::from(c.high.get()) + u64::from(c.low.get());
+			self.ticks % period
+		});
+		Key {
+			memory: self.read.clone(),
+			dirty: self.sorted_dirty(),
+			wheel: self.wheel.clone(),
+			phases: phases.collect(),
+		}
+	}
+
+	/// Step the circuit `period` times and check whether it returns to the current state.
+	///
+	/// Returns the nexuses and components involved if it does and no clock changed.
+	pub(super) fn trace_cycle(&mut self, period: usize) -> Option<Oscillation> {
+		let start = self.key();
+		let (mut memory, mut nodes) = (BTreeSet::new(), BTreeSet::new());
+		for _ in 0..period {
+			nodes.extend(self.update_dirty.iter().copied());
+			let prev = self.read.clone();
+			self.step();
+			let changed = prev.iter().zip(self.read.iter()).map(|(p, r)| p != r);
+			memory.extend(changed.enumerate().filter(|&(_, c)| c).map(|(a, _)| a));
+		}
+		let clocked = self.program.clocks.iter().any(|c| memory.contains(&c.out));
+		(!clocked && self.key() == start).then(|| {
+			let program = &self.program;
+			let nexuses = memory.iter().filter_map(|&a| *program.nexuses.get(a)?);
+			let components = nodes.iter().filter_map(|&n| *program.components.get(n)?);
+			Oscillation {
+				period,
+				nexuses: nexuses.collect::<BTreeSet<_>>().into_iter().collect(),
+				components: components.collect::<BTreeSet<_>>().into_iter().collect(),
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::NonZeroU16;
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	#[test]
+	fn ring_oscillator() {
+		let mut graph = G::new();
+		let one = NonZeroU16::new(1).unwrap();
+		let i = graph.add(Box::new(In::new("", one, 0)), ());
+		let and = graph.add(Box::new(AndGate::new(NonZeroOneU8::new(2).unwrap())), ());
+		let nots = [(); 3].map(|_| graph.add(Box::new(NotGate::new()), ()));
+		let [en, a, b, c, d] = [(); 5].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: i, port: 0 }, en);
+		connect(&mut graph, Port::Input { node: and, port: 0 }, en);
+		connect(&mut graph, Port::Input { node: and, port: 1 }, d);
+		connect(&mut graph, Port::Output { node: and, port: 0 }, a);
+		for (k, (x, y)) in [(a, b), (b, c), (c, d)].into_iter().enumerate() {
+			connect(&mut graph, Port::Input { node: nots[k], port: 0 }, x);
+			connect(&mut graph, Port::Output { node: nots[k], port: 0 }, y);
+		}
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.write_inputs(&[ir::Value::Set(0)]);
+		assert!(state.run(1024).is_settled());
+		// Enabling the AND gate closes a loop with an odd amount of inverters.
+		state.write_inputs(&[ir::Value::Set(1)]);
+		match state.run(1024) {
+			RunResult::Oscillated(o) => {
+				assert_eq!(o.period, 8);
+				assert_eq!(&*o.nexuses, &[a, b, c, d]);
+				let mut components = [and, nots[0], nots[1], nots[2]];
+				components.sort();
+				assert_eq!(&*o.components, &components);
+			}
+			r => panic!("expected oscillation, got {:?}", r),
+		}
+		state.write_inputs(&[ir::Value::Set(0)]);
+		assert!(state.run(1024).is_settled());
+	}
+
+	#[test]
+	fn fast_clock() {
+		// A clock toggling every step keeps the circuit busy, but doesn't make it oscillate.
+		let mut graph = G::new();
+		let n = core::num::NonZeroU32::new(1).unwrap();
+		let clock = graph.add(Box::new(Clock::new(n, n)), ());
+		let nots = [(); 2].map(|_| graph.add(Box::new(NotGate::new()), ()));
+		let [a, b, c] = [(); 3].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: clock, port: 0 }, a);
+		connect(&mut graph, Port::Input { node: nots[0], port: 0 }, a);
+		connect(&mut graph, Port::Output { node: nots[0], port: 0 }, b);
+		connect(&mut graph, Port::Input { node: nots[1], port: 0 }, b);
+		connect(&mut graph, Port::Output { node: nots[1], port: 0 }, c);
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		assert_eq!(state.run(1024), RunResult::Limit);
+	}
+}
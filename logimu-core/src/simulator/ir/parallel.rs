@@ -0,0 +1,99 @@
+//! # Parallel stepping
+//!
+//! All nodes read from the same memory, so the nodes of a single step can run in any order. The
+//! dirty nodes are split in chunks that each run on a separate thread. Workers don't write to
+//! memory directly but keep a list of writes and nodes to mark dirty, which are merged in the
+//! order of the chunks afterwards. This makes the results independent of the amount of threads.
+
+use super::program::{run_in, Output, Program, Word};
+use crate::integer_set::IntegerSet;
+
+/// The minimum amount of dirty nodes for each thread. Spawning threads isn't worth it for less.
+pub(super) const MIN_NODES_PER_THREAD: usize = 512;
+
+#[derive(Default)]
+struct Worker {
+	/// All writes done by this worker, in order.
+	writes: Vec<(usize, Word)>,
+	/// The index of the first write of the node that is currently running.
+	node_start: usize,
+	/// Nodes marked dirty by this worker.
+	dirty: IntegerSet,
+	/// Checks of memory not written by the node doing the check. These can only be done once the
+	/// writes of all workers are known.
+	deferred: Vec<(usize, usize, usize)>,
+}
+
+impl Output for Worker {
+	fn save(&mut self, a: usize, w: Word) {
+		self.writes.push((a, w));
+	}
+
+	fn check_dirty(&mut self, rd: &[Word], a: usize, node: usize, mask: usize) {
+		let own = self.writes[self.node_start..]
+			.iter()
+			.rev()
+			.find(|e| e.0 == a);
+		match own {
+			Some(&(_, w)) if w.changed(rd[a]) & mask != 0 => {
+				self.dirty.insert(node);
+			}
+			Some(_) => (),
+			None => self.deferred.push((a, node, mask)),
+		}
+	}
+}
+
+impl Worker {
+	fn run(&mut self, program: &Program, nodes: &[usize], rd: &[Word], lanes: bool) {
+		for &n in nodes {
+			self.node_start = self.writes.len();
+			run_in(lanes, &program.nodes[n].ir, rd, self);
+		}
+	}
+}
+
+/// Run the given nodes on up to `threads` threads. `lanes` selects batch mode.
+pub(super) fn step(
+	program: &Program,
+	nodes: &[usize],
+	rd: &[Word],
+	wr: &mut [Word],
+	dirty: &mut IntegerSet,
+	threads: usize,
+	lanes: bool,
+) {
+	let chunk = (nodes.len() + threads - 1) / threads;
+	let mut chunks = nodes.chunks(chunk);
+	let first = chunks.next().unwrap_or_default();
+	let workers = crossbeam_utils::thread::scope(|s| {
+		let handles = chunks
+			.map(|nodes| {
+				s.spawn(move |_| {
+					let mut w = Worker::default();
+					w.run(program, nodes, rd, lanes);
+					w
+				})
+			})
+			.collect::<Vec<_>>();
+		let mut w = Worker::default();
+		w.run(program, first, rd, lanes);
+		let others = handles.into_iter().map(|h| h.join().unwrap());
+		Some(w).into_iter().chain(others).collect::<Vec<_>>()
+	})
+	.unwrap();
+
+	for w in workers.iter() {
+		w.writes.iter().for_each(|&(a, v)| wr[a] = v);
+	}
+	for w in workers.iter() {
+		w.dirty.iter().for_each(|&n| {
+			dirty.insert(n);
+		});
+	}
+	for &(a, node, mask) in workers.iter().flat_map(|w| w.deferred.iter()) {
+		if wr[a].changed(rd[a]) & mask != 0 {
+			dirty.insert(node);
+		}
+	}
+}
@@ -0,0 +1,107 @@
+//! # Probes
+//!
+//! Probes record the value of a nexus, input or output after each step so it can be plotted over
+//! time. Only changes are recorded, together with the tick at which they were first seen. The
+//! samples are kept until they are taken with [`State::take_samples`].
+//!
+//! Moving back in time, e.g. with [`State::step_back`], records the value at the new tick too.
+//! Samples can thus go back in time, in which case later samples should be discarded.
+
+use super::breakpoint::Watch;
+use super::program::{State, Value};
+
+#[derive(Debug)]
+pub(super) struct Probe {
+	watch: Watch,
+	/// The last value recorded.
+	value: Option<Value>,
+	samples: Vec<(u64, Value)>,
+}
+
+impl State {
+	/// Replace all probes. Samples of probes that watch the same value as before are kept. The
+	/// current value is recorded for the other probes.
+	pub fn set_probes(&mut self, probes: impl IntoIterator<Item = Watch>) {
+		let mut prev = core::mem::take(&mut self.probes);
+		let probes =
+			probes
+				.into_iter()
+				.map(|watch| match prev.iter().position(|p| p.watch == watch) {
+					Some(i) => prev.swap_remove(i),
+					None => Probe { watch, value: None, samples: Vec::new() },
+				});
+		self.probes = probes.collect();
+		self.sample_probes();
+	}
+
+	/// All probes, in the order they were set.
+	pub fn probes(&self) -> impl Iterator<Item = Watch> + '_ {
+		self.probes.iter().map(|p| p.watch)
+	}
+
+	/// Take the samples recorded since the last call, for each probe in the order they were set.
+	pub fn take_samples(&mut self) -> Vec<Vec<(u64, Value)>> {
+		let samples = self.probes.iter_mut();
+		samples.map(|p| core::mem::take(&mut p.samples)).collect()
+	}
+
+	/// Record the values of probes that changed.
+	pub(super) fn sample_probes(&mut self) {
+		for i in 0..self.probes.len() {
+			let value = self.read_watch(self.probes[i].watch);
+			let p = &mut self.probes[i];
+			if p.value != Some(value) {
+				p.value = Some(value);
+				p.samples.push((self.ticks, value));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::{NonZeroU16, NonZeroU32};
+	use std::sync::Arc;
+
+	#[test]
+	fn samples() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+		let (one, two) = (NonZeroU32::new(1).unwrap(), NonZeroU32::new(2).unwrap());
+		let clock = graph.add(Box::new(Clock::new(two, one)), ());
+		let mut not = NotGate::new();
+		not.bits = NonZeroU16::new(1).unwrap();
+		let not = graph.add(Box::new(not), ());
+		let o = graph.add(Box::new(Out::new("", NonZeroU16::new(1).unwrap(), 0)), ());
+		let [a, b] = [(); 2].map(|_| graph.new_nexus(()));
+		graph
+			.connect(Port::Output { node: clock, port: 0 }, Some(a))
+			.unwrap();
+		graph
+			.connect(Port::Input { node: not, port: 0 }, Some(a))
+			.unwrap();
+		graph
+			.connect(Port::Output { node: not, port: 0 }, Some(b))
+			.unwrap();
+		graph
+			.connect(Port::Input { node: o, port: 0 }, Some(b))
+			.unwrap();
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.set_history(16);
+		state.set_probes([Watch::Nexus(a)]);
+		state.advance(3);
+		state.set_probes([Watch::Output(0), Watch::Nexus(a)]);
+		state.advance(2);
+		// The clock is low at tick 0 and 3 and high otherwise. The output lags one step behind.
+		let (f, set) = (Value::Floating, Value::Set);
+		let clock = vec![(0, f), (1, set(0)), (2, set(1)), (4, set(0)), (5, set(1))];
+		let out = vec![(3, set(0)), (4, set(1)), (5, set(0))];
+		assert_eq!(state.take_samples(), [out, clock]);
+		assert_eq!(state.take_samples(), [vec![], vec![]]);
+
+		state.step_back();
+		assert_eq!(state.take_samples(), [vec![(4, set(1))], vec![(4, set(0))]]);
+	}
+}
@@ -0,0 +1,1361 @@
+use super::super::{GraphNodeHandle, NexusHandle};
+use super::breakpoint::Breakpoint;
+use super::delay::Wheel;
+use super::history::History;
+use super::jit::{self, Jit};
+use super::memory::Region;
+use super::oscillation::{Detector, RunResult};
+use super::parallel;
+use super::probe::Probe;
+use super::vcd::Vcd;
+use crate::integer_set::IntegerSet;
+use core::hash::{Hash, Hasher};
+use core::num::{NonZeroU32, NonZeroU8, NonZeroUsize};
+use core::{fmt, iter, mem};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thin_dst::ThinArc;
+
+#[derive(Debug)]
+pub(crate) struct Node {
+	/// IR to simulate this node.
+	pub(crate) ir: Box<[IrOp]>,
+	/// The amount of steps it takes for the writes of this node to take effect.
+	pub(crate) delay: NonZeroU8,
+}
+
+#[derive(Debug, Default)]
+pub struct Program {
+	/// All nodes that can affect the circuit.
+	pub(crate) nodes: Box<[Node]>,
+	/// The amount of memory needed to run this program.
+	pub(crate) memory_size: usize,
+	/// Input & amount of bits to nexus map.
+	pub(crate) input_map: Box<[(usize, u16)]>,
+	/// Output & amount of bits to nexus map.
+	pub(crate) output_map: Box<[(usize, u16)]>,
+	/// Input to node & mask of the bits each node reads map. The mask applies to all words of
+	/// the input.
+	pub(crate) input_nodes_map: Box<[Box<[(usize, usize)]>]>,
+	/// The first word of memory & amount of bits of each nexus, by index.
+	pub(crate) nexus_map: Box<[(usize, u16)]>,
+	/// The first word of memory & amount of words of the internal memory of each component,
+	/// such as the value of a register or the contents of a RAM, by node index.
+	pub(crate) component_map: Box<[(usize, usize)]>,
+	/// Nexuses with multiple drivers mapped to the memory each driver writes to, sorted by
+	/// nexus. Each driver has as many words as the nexus.
+	pub(crate) drivers: Box<[(usize, Box<[usize]>)]>,
+	/// Clocks driven by the state.
+	pub(crate) clocks: Box<[ClockSource]>,
+	/// The component each node was generated from. Resolvers don't belong to a component.
+	pub(crate) components: Box<[Option<GraphNodeHandle>]>,
+	/// The nexus each word of memory belongs to, including the memory of each driver of a nexus.
+	pub(crate) nexuses: Box<[Option<NexusHandle>]>,
+	/// Memory of components that can be inspected and edited while simulating.
+	pub(crate) memories: Box<[(GraphNodeHandle, Region)]>,
+}
+
+/// A periodic signal that is low for `low` ticks, then high for `high` ticks.
+#[derive(Debug)]
+pub(crate) struct ClockSource {
+	/// The memory the clock writes to.
+	pub(crate) out: usize,
+	pub(crate) high: NonZeroU32,
+	pub(crate) low: NonZeroU32,
+	/// Nodes reading the clock.
+	pub(crate) nodes: Box<[usize]>,
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+	/// The program associated with this state.
+	pub(super) program: Arc<Program>,
+	/// All nodes that need an update in the next step.
+	pub(super) update_dirty: IntegerSet,
+	/// A hashset that will be populated with nodes to be updated on the next step.
+	///
+	/// This set is swapped with the dirty set at the end of each step.
+	mark_dirty: IntegerSet,
+	/// Memory to write to in the next step.
+	pub(super) write: Box<[Word]>,
+	/// Memory to read from in the next step.
+	pub(super) read: Box<[Word]>,
+	/// The compiled program, if the JIT backend is used.
+	jit: Option<Jit>,
+	/// The maximum amount of threads to run a single step with.
+	threads: usize,
+	/// Whether each bit of a word is an independent lane. See [`Program::new_batch_state`].
+	pub(super) batch: bool,
+	/// Writes of nodes with a delay of more than one step that haven't taken effect yet.
+	pub(super) wheel: Wheel,
+	/// The amount of steps done since the state was created.
+	pub(super) ticks: u64,
+	/// The changes of recent steps, if recording is enabled.
+	pub(super) history: Option<History>,
+	/// Breakpoints with the value each watched after the last step.
+	pub(super) breakpoints: Vec<(Breakpoint, Value)>,
+	/// The breakpoint that fired in the last step.
+	pub(super) breakpoint_hit: Option<usize>,
+	/// The file each step is traced to, if any.
+	pub(super) vcd: Option<Vcd>,
+	/// Values recorded after each step.
+	pub(super) probes: Vec<Probe>,
+}
+
+/// The backend used to run the nodes of a program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+	/// Interpret the IR. Available on all architectures.
+	Interpreter,
+	/// Compile the IR to machine code. Only available on x86_64 Linux.
+	Jit,
+}
+
+impl Backend {
+	/// Whether this backend is implemented for this platform. The JIT may still not support
+	/// every program.
+	pub fn is_available(self) -> bool {
+		match self {
+			Self::Interpreter => true,
+			Self::Jit => jit::SUPPORTED,
+		}
+	}
+}
+
+/// A single word in memory.
+///
+/// Each bit is either cleared, set, floating or shorted. Floating and shorted bits have their
+/// `undefined` bit set, in which case the `value` bit is cleared for floating bits and set for
+/// shorted bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(C)]
+pub(crate) struct Word {
+	pub(crate) value: usize,
+	pub(crate) undefined: usize,
+}
+
+impl State {
+	/// Write the given inputs to memory.
+	///
+	/// # Panics
+	///
+	/// The inputs slice doesn't match the actual amount of inputs.
+	pub fn write_inputs(&mut self, inputs: &[Value]) {
+		for (i, o) in inputs.iter().enumerate() {
+			// TODO
+			if i >= self.program.input_map.len() {
+				continue;
+			}
+			let (k, bits) = self.program.input_map[i];
+			if k == usize::MAX {
+				// The input doesn't map to a memory location
+				continue;
+			}
+			let mut changed = 0;
+			for w in 0..words(bits) {
+				let v = Word::from_value(o.word(w), self.mask(word_mask(bits, w)));
+				changed |= self.read[k + w].changed(v);
+				self.read[k + w] = v;
+				self.write[k + w] = v;
+			}
+			for &(n, mask) in self.program.input_nodes_map[i].iter() {
+				if changed & self.mask(mask) != 0 {
+					self.update_dirty.insert(n);
+				}
+			}
+		}
+	}
+
+	/// Read the outputs from memory.
+	///
+	/// # Panics
+	///
+	/// The outputs slice doesn't match the actual amount of outputs.
+	pub fn read_outputs(&self, outputs: &mut [Value]) {
+		for (i, o) in outputs.iter_mut().enumerate() {
+			// TODO
+			if i >= self.program.output_map.len() {
+				continue;
+			}
+			let (k, bits) = self.program.output_map[i];
+			*o = if k == usize::MAX {
+				Value::Floating
+			} else {
+				self.read_value(k, bits)
+			};
+		}
+	}
+
+	/// Read the inputs from memory, e.g. after restoring a snapshot. Inputs that don't map to a
+	/// memory location are left unchanged.
+	pub fn read_inputs(&self, inputs: &mut [Value]) {
+		for (i, &(k, bits)) in inputs.iter_mut().zip(self.program.input_map.iter()) {
+			if k != usize::MAX {
+				*i = self.read_value(k, bits);
+			}
+		}
+	}
+
+	/// Read a value of the given amount of bits starting at address `k`.
+	pub(super) fn read_value(&self, k: usize, bits: u16) -> Value {
+		let words = (0..words(bits)).map(|w| (k + w, word_mask(bits, w)));
+		Value::from_words(words.map(|(a, m)| self.read[a].to_value(self.mask(m))))
+	}
+
+	/// Get the value of the given nexus.
+	///
+	/// # Panics
+	///
+	/// The nexus is invalid.
+	pub fn read_nexus(&self, nexus: NexusHandle) -> Value {
+		let (a, bits) = self.program.nexus_map[nexus.index()];
+		self.read_value(a, bits)
+	}
+
+	/// Check whether multiple drivers are driving the same bits of the given nexus.
+	///
+	/// # Panics
+	///
+	/// The nexus is invalid.
+	pub fn is_contended(&self, nexus: NexusHandle) -> bool {
+		let d = &self.program.drivers;
+		let (a, bits) = self.program.nexus_map[nexus.index()];
+		d.binary_search_by_key(&a, |&(n, _)| n).map_or(false, |i| {
+			(0..words(bits)).any(|k| {
+				let mut driven = 0;
+				d[i].1.iter().any(|&a| {
+					let w = self.read[a + k].driven();
+					let c = driven & w != 0;
+					driven |= w;
+					c
+				})
+			})
+		})
+	}
+
+	/// The program associated with this state.
+	pub fn program(&self) -> &Arc<Program> {
+		&self.program
+	}
+
+	/// The mask to use for an input or output. In batch mode each bit is a separate lane.
+	fn mask(&self, mask: usize) -> usize {
+		if self.batch {
+			usize::MAX
+		} else {
+			mask
+		}
+	}
+
+	/// The backend used to run the program.
+	pub fn backend(&self) -> Backend {
+		if self.jit.is_some() {
+			Backend::Jit
+		} else {
+			Backend::Interpreter
+		}
+	}
+
+	/// Select the backend used to run the program.
+	///
+	/// Returns `false` if the backend is not supported on this platform, by the program or in
+	/// batch mode, in which case the interpreter is used instead.
+	pub fn set_backend(&mut self, backend: Backend) -> bool {
+		self.jit = match backend {
+			Backend::Jit if !self.batch => Jit::new(&self.program),
+			_ => None,
+		};
+		self.backend() == backend
+	}
+
+	/// Modify this state to be compatible with a new program whilst losing as little information
+	/// as possible.
+	///
+	/// The value of each nexus and the internal memory of each component are kept if their size
+	/// didn't change, everything else is reset. Batch mode is kept only if the new program
+	/// supports it. The history is kept if the memory layout didn't change. If the program is
+	/// equivalent the state is kept as is.
+	pub fn adapt(mut self, program: impl Into<Arc<Program>> + AsRef<Program>) -> Self {
+		let program = program.into();
+		if Arc::as_ptr(&program) == Arc::as_ptr(&self.program) {
+			return self;
+		}
+		if program.fingerprint() == self.program.fingerprint() {
+			// The compiled code is still valid: memories read by the program are part of the
+			// fingerprint and the JIT keeps its own references to them.
+			self.program = program;
+			return self;
+		}
+		let mut s = program.new_state();
+		s.batch = self.batch && s.program.supports_batch();
+		s.set_backend(self.backend());
+		s.threads = self.threads;
+		s.ticks = self.ticks;
+		s.update_dirty |= self.update_dirty;
+		let (nexus_map, component_map) = (&self.program.nexus_map, &self.program.component_map);
+		s.program
+			.remap_memory(nexus_map, component_map, &self.read, &mut s.read);
+		s.write.copy_from_slice(&s.read);
+		s.adapt_history(self.history);
+		s.breakpoints = self.breakpoints;
+		s.sync_breakpoints();
+		s.vcd = self.vcd;
+		s.probes = self.probes;
+		s.sample_probes();
+		s
+	}
+
+	/// Set the maximum amount of threads to run a single step with.
+	///
+	/// Steps with few dirty nodes always run on the current thread only. Steps running on
+	/// multiple threads always use the interpreter. The results do not depend on the amount of
+	/// threads.
+	pub fn set_threads(&mut self, threads: NonZeroUsize) {
+		self.threads = threads.get();
+	}
+
+	/// The maximum amount of threads to run a single step with.
+	pub fn threads(&self) -> NonZeroUsize {
+		NonZeroUsize::new(self.threads).unwrap_or(NonZeroUsize::new(1).unwrap())
+	}
+
+	/// The amount of steps done since the state was created, which drives clocks.
+	pub fn ticks(&self) -> u64 {
+		self.ticks
+	}
+
+	/// Step the circuit once.
+	///
+	/// Returns the amount of nodes that need an update in the next step plus the amount of
+	/// updates of delayed nodes that haven't taken effect yet. The circuit is stable if this is 0.
+	pub fn step(&mut self) -> usize {
+		debug_assert!(self.mark_dirty.is_empty());
+		self.update_clocks();
+		let threads = self
+			.threads
+			.min(self.update_dirty.len() / parallel::MIN_NODES_PER_THREAD);
+		let nodes = &self.program.nodes;
+		if threads > 1 {
+			let dirty = self.update_dirty.iter().as_slice();
+			// Delayed nodes write to the wheel, so they always run on the current thread.
+			let (unit, delayed) = if self.wheel.is_empty() {
+				(dirty.into(), Vec::new())
+			} else {
+				dirty.iter().partition(|&&n| nodes[n].delay.get() == 1)
+			};
+			parallel::step(
+				&self.program,
+				&unit,
+				&self.read,
+				&mut self.write,
+				&mut self.mark_dirty,
+				threads,
+				self.batch,
+			);
+			for n in delayed {
+				let out = self.wheel.schedule(nodes[n].delay);
+				run_in(self.batch, &nodes[n].ir, &self.read, out);
+			}
+			self.update_dirty.drain();
+		} else {
+			for n in self.update_dirty.drain() {
+				let node = &nodes[n];
+				if node.delay.get() > 1 {
+					let out = self.wheel.schedule(node.delay);
+					run_in(self.batch, &node.ir, &self.read, out);
+				} else if let Some(jit) = &self.jit {
+					jit.run(n, &self.read, &mut self.write, &mut self.mark_dirty);
+				} else {
+					let out = &mut Direct { wr: &mut self.write, dirty: &mut self.mark_dirty };
+					run_in(self.batch, &node.ir, &self.read, out);
+				}
+			}
+		}
+		self.wheel
+			.advance(&self.read, &mut self.write, &mut self.mark_dirty);
+		self.read.copy_from_slice(&self.write);
+		mem::swap(&mut self.write, &mut self.read);
+		mem::swap(&mut self.update_dirty, &mut self.mark_dirty);
+		self.ticks += 1;
+		self.record();
+		if !self.breakpoints.is_empty() {
+			self.check_breakpoints();
+		}
+		self.trace();
+		self.sample_probes();
+		self.update_dirty.len() + self.wheel.pending()
+	}
+
+	/// Write the value of each clock at the current tick to memory.
+	fn update_clocks(&mut self) {
+		for c in self.program.clocks.iter() {
+			let period = u64::from(c.high.get()) + u64::from(c.low.get());
+			let high = self.ticks % period >= u64::from(c.low.get());
+			// Clocks are a single bit wide, but drive all lanes in batch mode.
+			let w = if self.batch {
+				Word::new(usize::from(high).wrapping_neg())
+			} else {
+				Word::new(high.into()).andi(1)
+			};
+			if self.read[c.out] != w {
+				(self.read[c.out], self.write[c.out]) = (w, w);
+				c.nodes.iter().for_each(|&n| {
+					self.update_dirty.insert(n);
+				});
+			}
+		}
+	}
+
+	/// Step the circuit exactly n times, even if it is stable, or until a breakpoint fires.
+	///
+	/// Returns the index of the breakpoint that fired, if any.
+	pub fn advance(&mut self, ticks: usize) -> Option<usize> {
+		for _ in 0..ticks {
+			self.step();
+			if self.breakpoint_hit.is_some() {
+				break;
+			}
+		}
+		self.breakpoint_hit
+	}
+
+	/// Step the circuit up to n times, until no more nodes need an update or until a breakpoint
+	/// fires.
+	///
+	/// Circuits that haven't settled after half of the steps are checked for
+	/// [oscillation](super::Oscillation).
+	pub fn run(&mut self, max_iterations: usize) -> RunResult {
+		let mut detector = Detector::default();
+		let mut steps = 0;
+		while steps < max_iterations {
+			if self.is_stable() {
+				return RunResult::Settled { steps };
+			}
+			self.step();
+			steps += 1;
+			if let Some(breakpoint) = self.breakpoint_hit {
+				return RunResult::Break { steps, breakpoint };
+			}
+			if steps > max_iterations / 2 {
+				if let Some(period) = detector.check(self, steps) {
+					if let Some(o) = self.trace_cycle(period) {
+						return RunResult::Oscillated(o);
+					}
+					steps += period;
+				}
+			}
+		}
+		if self.is_stable() {
+			RunResult::Settled { steps }
+		} else {
+			RunResult::Limit
+		}
+	}
+
+	/// Whether no nodes need an update and no writes are pending.
+	fn is_stable(&self) -> bool {
+		self.update_dirty.is_empty() && self.wheel.pending() == 0
+	}
+
+	/// The nodes that need an update, sorted.
+	pub(super) fn sorted_dirty(&self) -> Box<[usize]> {
+		let mut dirty = self.update_dirty.iter().copied().collect::<Box<_>>();
+		dirty.sort_unstable();
+		dirty
+	}
+}
+
+/// The amount of bits in a word of memory.
+pub const WORD_BITS: u16 = usize::BITS as u16;
+
+/// The maximum amount of bits of a nexus.
+pub const MAX_BITS: u16 = 256;
+
+/// The maximum amount of words of a nexus.
+pub const MAX_WORDS: usize = (MAX_BITS / WORD_BITS) as usize;
+
+/// The amount of words needed to store the given amount of bits.
+pub fn words(bits: u16) -> usize {
+	usize::from((bits + WORD_BITS - 1) / WORD_BITS)
+}
+
+/// A mask of the bits in word `k` of a value with the given amount of bits.
+pub fn word_mask(bits: u16, k: usize) -> usize {
+	let rem = u32::from(bits).saturating_sub((k * usize::from(WORD_BITS)) as u32);
+	usize::MAX
+		.checked_shr(usize::BITS.saturating_sub(rem))
+		.unwrap_or(0)
+}
+
+/// The state of an input or output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value {
+	Set(usize),
+	Floating,
+	Short,
+	/// Some bits are floating or shorted while others are set.
+	Mixed {
+		value: usize,
+		floating: usize,
+		short: usize,
+	},
+	/// A value that doesn't fit in a single word, least significant word first.
+	Wide {
+		value: [usize; MAX_WORDS],
+		floating: [usize; MAX_WORDS],
+		short: [usize; MAX_WORDS],
+	},
+}
+
+impl Value {
+	/// Create a value from masks of set, floating and shorted bits. Only bits in `mask` are
+	/// considered.
+	///
+	/// If all bits are of the same kind, `Set`, `Floating` or `Short` is returned.
+	pub fn from_masks(value: usize, floating: usize, short: usize, mask: usize) -> Self {
+		let (floating, short) = (floating & mask, short & mask);
+		let value = value & mask & !floating & !short;
+		if floating | short == 0 {
+			Self::Set(value)
+		} else if floating == mask {
+			Self::Floating
+		} else if short == mask {
+			Self::Short
+		} else {
+			Self::Mixed { value, floating, short }
+		}
+	}
+
+	/// Split this value in masks of set, floating and shorted bits. Only bits in `mask` are
+	/// considered.
+	///
+	/// Only the first word of wide values is considered.
+	pub fn to_masks(self, mask: usize) -> (usize, usize, usize) {
+		match self {
+			Self::Set(v) => (v & mask, 0, 0),
+			Self::Floating => (0, mask, 0),
+			Self::Short => (0, 0, mask),
+			Self::Mixed { value, floating, short } => (value & mask, floating & mask, short & mask),
+			Self::Wide { .. } => self.word(0).to_masks(mask),
+		}
+	}
+
+	/// Get word `k` of this value. Words of values that aren't wide are zero-extended, unless the
+	/// whole value is floating or shorted.
+	pub fn word(self, k: usize) -> Self {
+		match self {
+			Self::Wide { value, floating, short } if k < MAX_WORDS => {
+				Self::from_masks(value[k], floating[k], short[k], usize::MAX)
+			}
+			Self::Wide { .. } => Self::Set(0),
+			v if k == 0 => v,
+			Self::Set(_) | Self::Mixed { .. } => Self::Set(0),
+			v => v,
+		}
+	}
+
+	/// Combine words into a single value. A value that isn't wide is returned if all words past
+	/// the first one are the extension of the first one.
+	///
+	/// # Panics
+	///
+	/// There are more than [`MAX_WORDS`] words.
+	pub fn from_words(words: impl IntoIterator<Item = Self>) -> Self {
+		let mut words = words.into_iter();
+		let first = words.next().unwrap_or(Self::Floating);
+		let (mut value, mut floating, mut short) = ([0; MAX_WORDS], [0; MAX_WORDS], [0; MAX_WORDS]);
+		let mut wide = false;
+		for (k, w) in iter::once(first).chain(words).enumerate() {
+			assert!(k < MAX_WORDS, "too many words");
+			(value[k], floating[k], short[k]) = w.to_masks(usize::MAX);
+			wide |= w != first.word(k);
+		}
+		if wide {
+			Self::Wide { value, floating, short }
+		} else {
+			first
+		}
+	}
+}
+
+impl Word {
+	pub(crate) const FLOATING: Self = Self { value: 0, undefined: usize::MAX };
+	pub(crate) const SHORT: Self = Self { value: usize::MAX, undefined: usize::MAX };
+
+	pub(crate) const fn new(value: usize) -> Self {
+		Self { value, undefined: 0 }
+	}
+
+	/// Convert a value to a word. Bits outside `mask` are floating.
+	pub(super) fn from_value(value: Value, mask: usize) -> Self {
+		let (value, floating, short) = value.to_masks(mask);
+		let floating = floating | !mask;
+		Self {
+			value: (value & !floating) | short,
+			undefined: floating | short,
+		}
+	}
+
+	pub(super) fn to_value(self, mask: usize) -> Value {
+		let floating = self.undefined & !self.value;
+		let short = self.undefined & self.value;
+		Value::from_masks(self.value, floating, short, mask)
+	}
+
+	/// The bits that differ between two words.
+	pub(super) fn changed(self, rhs: Self) -> usize {
+		(self.value ^ rhs.value) | (self.undefined ^ rhs.undefined)
+	}
+
+	/// The bits that are driven, i.e. not floating.
+	fn driven(self) -> usize {
+		!self.undefined | self.value
+	}
+
+	/// Bits that are defined and cleared.
+	fn zeros(self) -> usize {
+		!self.undefined & !self.value
+	}
+
+	/// Bits that are defined and set.
+	fn ones(self) -> usize {
+		!self.undefined & self.value
+	}
+
+	/// Create a word from value bits, marking `undefined` bits as shorted.
+	fn shorted(value: usize, undefined: usize) -> Self {
+		Self { value: value | undefined, undefined }
+	}
+
+	fn and(self, rhs: Self) -> Self {
+		let undefined = (self.undefined | rhs.undefined) & !(self.zeros() | rhs.zeros());
+		Self::shorted(self.value & rhs.value, undefined)
+	}
+
+	fn or(self, rhs: Self) -> Self {
+		let undefined = (self.undefined | rhs.undefined) & !(self.ones() | rhs.ones());
+		Self::shorted(self.value | rhs.value, undefined)
+	}
+
+	fn xor(self, rhs: Self) -> Self {
+		Self::shorted(self.value ^ rhs.value, self.undefined | rhs.undefined)
+	}
+
+	/// The entry of a region of `len` entries this word points to. Only the bits needed to
+	/// address all entries are considered. Returns `None` if any of those is floating or shorted
+	/// or if the entry is out of range.
+	fn index(self, len: usize) -> Option<usize> {
+		let mask = len.next_power_of_two() - 1;
+		(self.undefined & mask == 0)
+			.then(|| self.value & mask)
+			.filter(|&i| i < len)
+	}
+
+	/// The low `bits` bits as an integer, sign-extended if `signed` is set. Returns `None` if any
+	/// of those bits is floating or shorted.
+	fn int(self, bits: u8, signed: bool) -> Option<usize> {
+		let s = usize::BITS - u32::from(bits);
+		let mask = usize::MAX >> s;
+		(self.undefined & mask == 0).then(|| match signed {
+			true => (((self.value << s) as isize) >> s) as usize,
+			false => self.value & mask,
+		})
+	}
+
+	/// Apply an arithmetic op to this word and the low `bits` bits of `rhs`. The result is
+	/// shorted if any of the operands is undefined or if `f` returns `None`.
+	fn arithmetic(
+		self,
+		rhs: Self,
+		bits: u8,
+		signed: bool,
+		f: impl FnOnce(usize, usize) -> Option<usize>,
+	) -> Self {
+		let lhs = (self.undefined == 0).then(|| self.value);
+		match (lhs, rhs.int(bits, signed)) {
+			(Some(x), Some(y)) => f(x, y).map_or(Self::SHORT, Self::new),
+			_ => Self::SHORT,
+		}
+	}
+
+	/// Keep only the bits in the mask. All other bits become floating.
+	fn andi(self, mask: usize) -> Self {
+		Self { value: self.value & mask, undefined: self.undefined | !mask }
+	}
+
+	fn xori(self, i: usize) -> Self {
+		let value = (self.value ^ (i & !self.undefined)) | (i & self.undefined);
+		Self { value, undefined: self.undefined }
+	}
+
+	/// Shift left. The shifted in bits are floating.
+	fn slli(self, i: u8) -> Self {
+		let f = |v: usize| v.checked_shl(i.into()).unwrap_or(0);
+		Self { value: f(self.value), undefined: !f(!self.undefined) }
+	}
+
+	/// Shift right. The shifted in bits are floating.
+	fn srli(self, i: u8) -> Self {
+		let f = |v: usize| v.checked_shr(i.into()).unwrap_or(0);
+		Self { value: f(self.value), undefined: !f(!self.undefined) }
+	}
+
+	/// Shift right, copying the highest bit.
+	fn srai(self, i: u8) -> Self {
+		let f = |v: usize| ((v as isize) >> i.min(63)) as usize;
+		Self { value: f(self.value), undefined: f(self.undefined) }
+	}
+
+	/// Shift or rotate by the low `bits` bits of `amount`. `f` is applied to both the value and
+	/// the undefined bits. The result is shorted if the amount is undefined.
+	fn shift(self, amount: Self, bits: u8, f: impl Fn(usize, usize) -> usize) -> Self {
+		match amount.int(bits, false) {
+			Some(n) => Self { value: f(self.value, n), undefined: f(self.undefined, n) },
+			None => Self::SHORT,
+		}
+	}
+
+	/// The amount of ones in the low `bits` bits, or shorted if any of them is undefined.
+	fn popcnt(self, bits: u8) -> Self {
+		self.int(bits, false)
+			.map_or(Self::SHORT, |v| Self::new(v.count_ones() as usize))
+	}
+
+	/// The amount of leading zeros in the low `bits` bits, or shorted if any of them is
+	/// undefined.
+	fn clz(self, bits: u8) -> Self {
+		let f = |v: usize| (v.leading_zeros() - (usize::BITS - u32::from(bits))) as usize;
+		self.int(bits, false)
+			.map_or(Self::SHORT, |v| Self::new(f(v)))
+	}
+
+	/// Keep this word if bit 0 of `enable` is set, otherwise make it floating. If bit 0 is
+	/// floating or shorted, all driven bits are shorted.
+	fn enable(self, enable: Self) -> Self {
+		match (enable.undefined & 1, enable.value & 1) {
+			(0, 1) => self,
+			(0, _) => Self::FLOATING,
+			_ => Self { value: self.driven(), undefined: usize::MAX },
+		}
+	}
+
+	/// [`Self::enable`] for each lane separately.
+	fn enable_lanes(self, enable: Self) -> Self {
+		let (ones, undefined) = (enable.ones(), enable.undefined);
+		Self {
+			value: (self.value & ones) | (self.driven() & undefined),
+			undefined: (self.undefined & ones) | enable.zeros() | undefined,
+		}
+	}
+
+	/// Combine the bits of another driver of the same nexus with this word.
+	///
+	/// Bits driven by only one of both are copied, bits driven by both are shorted.
+	fn resolve(self, rhs: Self) -> Self {
+		let (a, b) = (self.driven(), rhs.driven());
+		let (only_b, both) = (b & !a, a & b);
+		Self {
+			value: (self.value & !only_b) | (rhs.value & only_b) | both,
+			undefined: (self.undefined & !only_b) | (rhs.undefined & only_b) | both,
+		}
+	}
+}
+
+/// Rotate the low `width` bits of `v` left by `n` bits. All other bits are cleared.
+fn rotl(v: usize, n: usize, width: u8) -> usize {
+	let mask = usize::MAX >> (usize::BITS - u32::from(width));
+	let n = (n % usize::from(width)) as u32;
+	let v = v & mask;
+	((v << n) | v.checked_shr(u32::from(width) - n).unwrap_or(0)) & mask
+}
+
+impl Program {
+	/// Copy the memory of a state of another program with the given `nexus_map` and
+	/// `component_map` to the memory of a state of this program. Nexuses are matched by handle
+	/// and the internal memory of components by node. Memory without a match of the same size is
+	/// left as is.
+	///
+	/// Returns `None` if the maps refer to memory outside of `from`.
+	pub(super) fn remap_memory(
+		&self,
+		nexus_map: &[(usize, u16)],
+		component_map: &[(usize, usize)],
+		from: &[Word],
+		to: &mut [Word],
+	) -> Option<()> {
+		let nexuses = nexus_map.iter().zip(self.nexus_map.iter());
+		let nexuses = nexuses
+			.filter(|(f, t)| f.1 == t.1)
+			.map(|(&(f, bits), &(t, _))| (f, t, words(bits)));
+		let components = component_map.iter().zip(self.component_map.iter());
+		let components = components
+			.filter(|(f, t)| f.1 == t.1)
+			.map(|(&(f, len), &(t, _))| (f, t, len));
+		for (f, t, len) in nexuses.chain(components) {
+			to[t..t + len].copy_from_slice(from.get(f..)?.get(..len)?);
+		}
+		Some(())
+	}
+
+	/// The memory of a new state. Words saved unconditionally by a node, such as the outputs of
+	/// gates, start low so feedback loops like latches can settle. Everything else, such as
+	/// nexuses without a driver and the value of a register, starts floating.
+	pub(super) fn initial_memory(&self) -> Box<[Word]> {
+		let mut memory = vec![Word::FLOATING; self.memory_size];
+		for op in self.nodes.iter().flat_map(|n| n.ir.iter()) {
+			if let IrOp::Save { out } | IrOp::SaveB { out } = *op {
+				memory[out] = Word::new(0);
+			}
+		}
+		memory.into()
+	}
+
+	pub fn new_state(self: Arc<Self>) -> State {
+		State {
+			program: self.clone(),
+			update_dirty: (0..self.nodes.len()).collect(),
+			mark_dirty: Default::default(),
+			write: self.initial_memory(),
+			read: self.initial_memory(),
+			jit: None,
+			threads: 1,
+			batch: false,
+			ticks: 0,
+			history: None,
+			breakpoints: Vec::new(),
+			breakpoint_hit: None,
+			vcd: None,
+			probes: Vec::new(),
+			wheel: Wheel::new(
+				self.nodes
+					.iter()
+					.map(|n| n.delay)
+					.max()
+					.unwrap_or(NonZeroU8::new(1).unwrap()),
+			),
+		}
+	}
+}
+
+/// Receives the results of running a sequence of instructions.
+pub(super) trait Output {
+	/// Write a word to memory.
+	fn save(&mut self, a: usize, w: Word);
+
+	/// Mark a node dirty if any bit in `mask` of the word at `a` changed.
+	fn check_dirty(&mut self, rd: &[Word], a: usize, node: usize, mask: usize);
+}
+
+/// Writes results directly to memory.
+pub(super) struct Direct<'a> {
+	pub(super) wr: &'a mut [Word],
+	pub(super) dirty: &'a mut IntegerSet,
+}
+
+impl Output for Direct<'_> {
+	fn save(&mut self, a: usize, w: Word) {
+		self.wr[a] = w;
+	}
+
+	fn check_dirty(&mut self, rd: &[Word], a: usize, node: usize, mask: usize) {
+		if self.wr[a].changed(rd[a]) & mask != 0 {
+			self.dirty.insert(node);
+		}
+	}
+}
+
+/// Run a sequence of instructions.
+///
+/// If `LANES` is set each bit is simulated as if it were bit 0 of a separate word, which only
+/// gives correct results for programs that [support batch mode](Program::supports_batch).
+pub(super) fn run<const LANES: bool>(ops: &[IrOp], rd: &[Word], out: &mut impl Output) {
+	// Immediates apply bit 0 to all lanes.
+	let imm = |i: usize| if LANES { (i & 1).wrapping_neg() } else { i };
+	let mut acc = Word::new(0);
+	let mut b = Word::FLOATING;
+	for op in ops {
+		match op {
+			&IrOp::CheckDirty { a, node, mask } => out.check_dirty(rd, a, node, imm(mask)),
+			&IrOp::Save { out: a } => out.save(a, acc),
+			&IrOp::And { a } => acc = acc.and(rd[a]),
+			&IrOp::Or { a } => acc = acc.or(rd[a]),
+			&IrOp::Xor { a } => acc = acc.xor(rd[a]),
+			&IrOp::Andi { i } => acc = acc.andi(imm(i)),
+			&IrOp::Xori { i } => acc = acc.xori(imm(i)),
+			&IrOp::Slli { i } => acc = acc.slli(i),
+			&IrOp::Srli { i } => acc = acc.srli(i),
+			&IrOp::Srai { i } => acc = acc.srai(i),
+			&IrOp::Sll { a, bits } => {
+				let f = |v: usize, n: usize| v.checked_shl(n.try_into().unwrap_or(u32::MAX));
+				acc = acc.shift(rd[a], bits, |v, n| f(v, n).unwrap_or(0))
+			}
+			&IrOp::Srl { a, bits } => {
+				let f = |v: usize, n: usize| v.checked_shr(n.try_into().unwrap_or(u32::MAX));
+				acc = acc.shift(rd[a], bits, |v, n| f(v, n).unwrap_or(0))
+			}
+			&IrOp::Sra { a, bits } => {
+				acc = acc.shift(rd[a], bits, |v, n| ((v as isize) >> n.min(63)) as usize)
+			}
+			&IrOp::Rotl { a, bits, width } => {
+				acc = acc.shift(rd[a], bits, |v, n| rotl(v, n, width))
+			}
+			&IrOp::Rotr { a, bits, width } => {
+				let w = usize::from(width);
+				acc = acc.shift(rd[a], bits, |v, n| rotl(v, w - n % w, width))
+			}
+			&IrOp::Popcnt { i } => acc = acc.popcnt(i),
+			&IrOp::Clz { i } => acc = acc.clz(i),
+			&IrOp::Copy { a } => acc = rd[a],
+			&IrOp::Load { value } => acc = Word::new(imm(value)),
+			IrOp::Read { memory } => {
+				// Floating address bits are read as 0.
+				acc = if acc.undefined & acc.value != 0 {
+					Word::SHORT
+				} else {
+					Word::new(*memory.slice.get(acc.value).unwrap_or(&0))
+				}
+			}
+			&IrOp::Fetch { base, len } => {
+				// Floating index bits are read as 0.
+				let mask = len.next_power_of_two() - 1;
+				acc = if acc.undefined & acc.value & mask != 0 {
+					Word::SHORT
+				} else {
+					match acc.value & mask {
+						i if i < len => rd[base + i],
+						_ => Word::new(0),
+					}
+				}
+			}
+			&IrOp::Store { a, index, base, len } => {
+				if acc.ones() & 1 != 0 {
+					if let Some(i) = rd[index].index(len) {
+						out.save(base + i, rd[a]);
+					}
+				}
+			}
+			&IrOp::SaveIf { a, out: o } => {
+				if acc.ones() & 1 != 0 {
+					out.save(o, rd[a]);
+				}
+			}
+			&IrOp::SetIf { out: o, value, mask } => {
+				if acc.ones() & 1 != 0 {
+					out.save(o, Word::new(value).andi(mask));
+				}
+			}
+			&IrOp::Eqi { i, mask } => {
+				let differ = (acc.value ^ i) & !acc.undefined & mask;
+				acc = match (differ, acc.undefined & mask) {
+					(0, 0) => Word::new(1),
+					(0, _) => Word::shorted(0, 1),
+					_ => Word::new(0),
+				}
+			}
+			&IrOp::Edge { a, prev, falling } => {
+				let (old, new) = if falling {
+					(rd[a], rd[prev])
+				} else {
+					(rd[prev], rd[a])
+				};
+				acc = acc.and(Word::new(old.zeros() & new.ones() & 1));
+				out.save(prev, rd[a]);
+			}
+			&IrOp::Add { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| Some(x.wrapping_add(y)))
+			}
+			&IrOp::Sub { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| Some(x.wrapping_sub(y)))
+			}
+			&IrOp::Mul { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| Some(x.wrapping_mul(y)))
+			}
+			&IrOp::Div { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| match signed {
+					_ if y == 0 => None,
+					true => Some((x as isize).wrapping_div(y as isize) as usize),
+					false => Some(x / y),
+				})
+			}
+			&IrOp::Rem { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| match signed {
+					_ if y == 0 => None,
+					true => Some((x as isize).wrapping_rem(y as isize) as usize),
+					false => Some(x % y),
+				})
+			}
+			&IrOp::Lt { a, bits, signed } => {
+				acc = acc.arithmetic(rd[a], bits, signed, |x, y| match signed {
+					true => Some(usize::from((x as isize) < (y as isize))),
+					false => Some(usize::from(x < y)),
+				})
+			}
+			&IrOp::Eq { a, bits } => {
+				acc = acc.arithmetic(rd[a], bits, false, |x, y| Some(usize::from(x == y)))
+			}
+			&IrOp::SaveB { out: a } => out.save(a, b),
+			&IrOp::OrB => b = b.resolve(acc),
+			&IrOp::Resolve { a } => acc = acc.resolve(rd[a]),
+			&IrOp::Enable { a } if LANES => acc = acc.enable_lanes(rd[a]),
+			&IrOp::Enable { a } => acc = acc.enable(rd[a]),
+		}
+	}
+}
+
+/// Run a sequence of instructions, treating each bit as a separate lane if `lanes` is set.
+pub(super) fn run_in(lanes: bool, ops: &[IrOp], rd: &[Word], out: &mut impl Output) {
+	if lanes {
+		run::<true>(ops, rd, out);
+	} else {
+		run::<false>(ops, rd, out);
+	}
+}
+
+#[derive(Clone)]
+pub enum IrOp {
+	/// Mark `node` dirty if any of the bits in `mask` at `a` will change.
+	CheckDirty {
+		a: usize,
+		node: usize,
+		mask: usize,
+	},
+	Save {
+		out: usize,
+	},
+	And {
+		a: usize,
+	},
+	Or {
+		a: usize,
+	},
+	Xor {
+		a: usize,
+	},
+	Andi {
+		i: usize,
+	},
+	Xori {
+		i: usize,
+	},
+	Slli {
+		i: u8,
+	},
+	Srli {
+		i: u8,
+	},
+	/// Shift right, copying the highest bit.
+	Srai {
+		i: u8,
+	},
+	/// Shift the accumulator left by the low `bits` bits of the word at `a`. The shifted in bits
+	/// are low. The dynamic shifts and rotates short the accumulator if the amount is undefined.
+	Sll {
+		a: usize,
+		bits: u8,
+	},
+	/// Shift the accumulator right by the low `bits` bits of the word at `a`. The shifted in bits
+	/// are low.
+	Srl {
+		a: usize,
+		bits: u8,
+	},
+	/// Shift the accumulator right by the low `bits` bits of the word at `a`, copying the highest
+	/// bit.
+	Sra {
+		a: usize,
+		bits: u8,
+	},
+	/// Rotate the low `width` bits of the accumulator left by the low `bits` bits of the word at
+	/// `a`. All other bits are cleared.
+	Rotl {
+		a: usize,
+		bits: u8,
+		width: u8,
+	},
+	/// Rotate the low `width` bits of the accumulator right by the low `bits` bits of the word at
+	/// `a`. All other bits are cleared.
+	Rotr {
+		a: usize,
+		bits: u8,
+		width: u8,
+	},
+	/// Set the accumulator to the amount of ones in its low `i` bits. It is shorted if any of
+	/// those bits is undefined.
+	Popcnt {
+		i: u8,
+	},
+	/// Set the accumulator to the amount of leading zeros in its low `i` bits. It is shorted if
+	/// any of those bits is undefined.
+	Clz {
+		i: u8,
+	},
+	Load {
+		value: usize,
+	},
+	Copy {
+		a: usize,
+	},
+	Read {
+		memory: ThinArc<(), usize>,
+	},
+	/// Load the entry the accumulator points to of the `len` words starting at `base`.
+	Fetch {
+		base: usize,
+		len: usize,
+	},
+	/// Write the word at `a` to the entry `index` points to of the `len` words starting at
+	/// `base` if bit 0 of the accumulator is set. Nothing is written if the entry is undefined.
+	Store {
+		a: usize,
+		index: usize,
+		base: usize,
+		len: usize,
+	},
+	/// Write the word at `a` to `out` if bit 0 of the accumulator is set.
+	SaveIf {
+		a: usize,
+		out: usize,
+	},
+	/// Write `value` to `out` if bit 0 of the accumulator is set. Bits outside `mask` are
+	/// floating.
+	SetIf {
+		out: usize,
+		value: usize,
+		mask: usize,
+	},
+	/// Set the accumulator to 1 if the bits in `mask` equal `i` and to 0 if any defined bit
+	/// differs. Otherwise bit 0 is shorted. All other bits are cleared.
+	Eqi {
+		i: usize,
+		mask: usize,
+	},
+	/// Clear bit 0 of the accumulator unless bit 0 at `a` rose, or fell if `falling` is set,
+	/// since it was last saved to `prev`. The word at `a` is then saved to `prev`. All other bits
+	/// of the accumulator are cleared.
+	Edge {
+		a: usize,
+		prev: usize,
+		falling: bool,
+	},
+	/// Add the low `bits` bits of the word at `a`, sign-extended if `signed` is set, to the
+	/// accumulator. All bits of the accumulator are used. The arithmetic ops short the accumulator
+	/// if any bit used by them is undefined.
+	Add {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// Subtract the low `bits` bits of the word at `a` from the accumulator.
+	Sub {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// Multiply the accumulator with the low `bits` bits of the word at `a`.
+	Mul {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// Divide the accumulator by the low `bits` bits of the word at `a`, rounding towards zero.
+	/// Division by zero shorts the accumulator.
+	Div {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// The remainder of [`Self::Div`], which has the sign of the accumulator.
+	Rem {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// Set the accumulator to 1 if it is less than the low `bits` bits of the word at `a`,
+	/// otherwise to 0.
+	Lt {
+		a: usize,
+		bits: u8,
+		signed: bool,
+	},
+	/// Set the accumulator to 1 if it equals the low `bits` bits of the word at `a`, otherwise to
+	/// 0.
+	Eq {
+		a: usize,
+		bits: u8,
+	},
+	SaveB {
+		out: usize,
+	},
+	OrB,
+	Resolve {
+		a: usize,
+	},
+	Enable {
+		a: usize,
+	},
+}
+
+// `Read` is hashed by the contents of its memory.
+impl Hash for IrOp {
+	fn hash<H: Hasher>(&self, h: &mut H) {
+		mem::discriminant(self).hash(h);
+		match self {
+			IrOp::CheckDirty { a, node, mask } => (a, node, mask).hash(h),
+			IrOp::Save { out } | IrOp::SaveB { out } => out.hash(h),
+			IrOp::And { a }
+			| IrOp::Or { a }
+			| IrOp::Xor { a }
+			| IrOp::Copy { a }
+			| IrOp::Resolve { a }
+			| IrOp::Enable { a } => a.hash(h),
+			IrOp::Andi { i } | IrOp::Xori { i } => i.hash(h),
+			IrOp::Slli { i }
+			| IrOp::Srli { i }
+			| IrOp::Srai { i }
+			| IrOp::Popcnt { i }
+			| IrOp::Clz { i } => i.hash(h),
+			IrOp::Sll { a, bits } | IrOp::Srl { a, bits } | IrOp::Sra { a, bits } => {
+				(a, bits).hash(h)
+			}
+			IrOp::Rotl { a, bits, width } | IrOp::Rotr { a, bits, width } => {
+				(a, bits, width).hash(h)
+			}
+			IrOp::Load { value } => value.hash(h),
+			IrOp::Read { memory } => memory.slice.hash(h),
+			IrOp::Fetch { base, len } => (base, len).hash(h),
+			IrOp::Store { a, index, base, len } => (a, index, base, len).hash(h),
+			IrOp::SaveIf { a, out } => (a, out).hash(h),
+			IrOp::SetIf { out, value, mask } => (out, value, mask).hash(h),
+			IrOp::Eqi { i, mask } => (i, mask).hash(h),
+			IrOp::Edge { a, prev, falling } => (a, prev, falling).hash(h),
+			IrOp::Add { a, bits, signed }
+			| IrOp::Sub { a, bits, signed }
+			| IrOp::Mul { a, bits, signed }
+			| IrOp::Div { a, bits, signed }
+			| IrOp::Rem { a, bits, signed }
+			| IrOp::Lt { a, bits, signed } => (a, bits, signed).hash(h),
+			IrOp::Eq { a, bits } => (a, bits).hash(h),
+			IrOp::OrB => (),
+		}
+	}
+}
+
+impl IrOp {
+	/// The memory read by this op. `CheckDirty` is not considered a read, nor is the
+	/// [region](Self::region) of a `Fetch`.
+	pub(crate) fn reads(&self) -> impl Iterator<Item = usize> {
+		let (a, b) = match *self {
+			IrOp::Copy { a }
+			| IrOp::And { a }
+			| IrOp::Or { a }
+			| IrOp::Xor { a }
+			| IrOp::Resolve { a }
+			| IrOp::Enable { a }
+			| IrOp::SaveIf { a, .. }
+			| IrOp::Add { a, .. }
+			| IrOp::Sub { a, .. }
+			| IrOp::Mul { a, .. }
+			| IrOp::Div { a, .. }
+			| IrOp::Rem { a, .. }
+			| IrOp::Lt { a, .. }
+			| IrOp::Eq { a, .. }
+			| IrOp::Sll { a, .. }
+			| IrOp::Srl { a, .. }
+			| IrOp::Sra { a, .. }
+			| IrOp::Rotl { a, .. }
+			| IrOp::Rotr { a, .. } => (Some(a), None),
+			IrOp::Store { a, index, .. } => (Some(a), Some(index)),
+			IrOp::Edge { a, prev, .. } => (Some(a), Some(prev)),
+			_ => (None, None),
+		};
+		a.into_iter().chain(b)
+	}
+
+	/// The memory written by this op. The [region](Self::region) of a `Store` is not considered
+	/// a write.
+	pub(crate) fn writes(&self) -> Option<usize> {
+		match *self {
+			IrOp::Save { out }
+			| IrOp::SaveB { out }
+			| IrOp::SaveIf { out, .. }
+			| IrOp::SetIf { out, .. }
+			| IrOp::Edge { prev: out, .. } => Some(out),
+			_ => None,
+		}
+	}
+
+	/// The first word & amount of words of the memory this op indexes dynamically.
+	pub(crate) fn region(&self) -> Option<(usize, usize)> {
+		match *self {
+			IrOp::Fetch { base, len } | IrOp::Store { base, len, .. } => Some((base, len)),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Debug for IrOp {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let fmt0 = |f: &mut fmt::Formatter, op| write!(f, "({})", op);
+		let fmt1 = |f: &mut fmt::Formatter, op, a| write!(f, "({:<5} {:>3})", op, a);
+		let fmt_int = |f: &mut fmt::Formatter, op, a, bits, signed: bool| {
+			let s = if signed { 's' } else { 'u' };
+			write!(f, "({:<5} {:>3} {}{})", op, a, s, bits)
+		};
+		match self {
+			IrOp::CheckDirty { a, node, mask } => {
+				write!(f, "({:<5} {:>3} {:>3} {:#x})", "check-dirty", a, node, mask)
+			}
+			IrOp::Save { out } => fmt1(f, "save", out),
+			IrOp::And { a } => fmt1(f, "and", a),
+			IrOp::Or { a } => fmt1(f, "or", a),
+			IrOp::Xor { a } => fmt1(f, "xor", a),
+			IrOp::Andi { i } => fmt1(f, "andi", i),
+			IrOp::Xori { i } => fmt1(f, "xori", i),
+			IrOp::Slli { i } => fmt1(f, "slli", &(*i).into()),
+			IrOp::Srli { i } => fmt1(f, "srli", &(*i).into()),
+			IrOp::Srai { i } => fmt1(f, "srai", &(*i).into()),
+			IrOp::Sll { a, bits } => fmt_int(f, "sll", a, bits, false),
+			IrOp::Srl { a, bits } => fmt_int(f, "srl", a, bits, false),
+			IrOp::Sra { a, bits } => fmt_int(f, "sra", a, bits, false),
+			IrOp::Rotl { a, bits, width } => {
+				write!(f, "({:<5} {:>3} u{} {})", "rotl", a, bits, width)
+			}
+			IrOp::Rotr { a, bits, width } => {
+				write!(f, "({:<5} {:>3} u{} {})", "rotr", a, bits, width)
+			}
+			IrOp::Popcnt { i } => fmt1(f, "popcnt", &(*i).into()),
+			IrOp::Clz { i } => fmt1(f, "clz", &(*i).into()),
+			IrOp::Copy { a } => fmt1(f, "copy", a),
+			IrOp::Load { value } => fmt1(f, "load", value),
+			IrOp::Read { .. } => fmt0(f, "(read [_])"),
+			IrOp::Fetch { base, len } => write!(f, "({:<5} {:>3} {:>3})", "fetch", base, len),
+			IrOp::Store { a, index, base, len } => {
+				write!(
+					f,
+					"({:<5} {:>3} {:>3} {:>3} {:>3})",
+					"store", a, index, base, len
+				)
+			}
+			IrOp::SaveIf { a, out } => write!(f, "({:<5} {:>3} {:>3})", "save-if", a, out),
+			IrOp::SetIf { out, value, mask } => {
+				write!(f, "({:<5} {:>3} {} {:#x})", "set-if", out, value, mask)
+			}
+			IrOp::Eqi { i, mask } => write!(f, "({:<5} {} {:#x})", "eqi", i, mask),
+			IrOp::Edge { a, prev, falling } => {
+				let op = if *falling { "fall" } else { "rise" };
+				write!(f, "({:<5} {:>3} {:>3})", op, a, prev)
+			}
+			IrOp::Add { a, bits, signed } => fmt_int(f, "add", a, bits, *signed),
+			IrOp::Sub { a, bits, signed } => fmt_int(f, "sub", a, bits, *signed),
+			IrOp::Mul { a, bits, signed } => fmt_int(f, "mul", a, bits, *signed),
+			IrOp::Div { a, bits, signed } => fmt_int(f, "div", a, bits, *signed),
+			IrOp::Rem { a, bits, signed } => fmt_int(f, "rem", a, bits, *signed),
+			IrOp::Lt { a, bits, signed } => fmt_int(f, "lt", a, bits, *signed),
+			IrOp::Eq { a, bits } => fmt_int(f, "eq", a, bits, false),
+			IrOp::SaveB { out } => fmt1(f, "save-b", out),
+			IrOp::OrB => fmt0(f, "or-b"),
+			IrOp::Resolve { a } => fmt1(f, "resolve", a),
+			IrOp::Enable { a } => fmt1(f, "enable", a),
+		}
+	}
+}
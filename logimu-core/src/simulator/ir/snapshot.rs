@@ -0,0 +1,308 @@
+//! # Snapshots
+//!
+//! A snapshot holds everything needed to continue a simulation later on: the memory, the nodes
+//! that need an update, the writes of delayed nodes that haven't taken effect yet and the amount
+//! of ticks.
+//!
+//! A snapshot taken from the same program is restored exactly. If the program changed since, e.g.
+//! because the circuit was edited, only the value of each nexus and the internal memory of each
+//! component, such as the contents of a RAM, are restored and all nodes are updated again, like
+//! [`State::adapt`] does. This requires that each nexus of the snapshot still exists and has the
+//! same width. Components of which the amount of memory changed are reset instead.
+
+use super::delay::Wheel;
+use super::program::{Program, State, Word};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// A copy of a [`State`] that can be restored later, see [`State::snapshot`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+	/// A hash of the program the snapshot was taken from.
+	program: u64,
+	/// The first word of memory & amount of bits of each nexus, by index.
+	nexuses: Box<[(usize, u16)]>,
+	/// The first word of memory & amount of words of the internal memory of each component, by
+	/// node index.
+	#[serde(default)]
+	components: Box<[(usize, usize)]>,
+	memory: Box<[Word]>,
+	/// The nodes that need an update, sorted.
+	dirty: Box<[usize]>,
+	wheel: Wheel,
+	ticks: u64,
+	batch: bool,
+}
+
+/// The reason a snapshot couldn't be restored.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RestoreError {
+	/// The snapshot has more nexuses than the program.
+	Nexuses { snapshot: usize, program: usize },
+	/// A nexus has a different width in the snapshot than in the program.
+	Bits { nexus: usize, snapshot: u16, program: u16 },
+	/// The snapshot was taken in batch mode but the state isn't in batch mode, or vice versa.
+	Batch { snapshot: bool },
+	/// The snapshot refers to memory or nodes that don't exist.
+	Malformed,
+}
+
+impl fmt::Display for RestoreError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Nexuses { snapshot, program } => write!(
+				f,
+				"snapshot has {} nexuses but the circuit only has {}",
+				snapshot, program
+			),
+			Self::Bits { nexus, snapshot, program } => write!(
+				f,
+				"nexus {} is {} bits wide in the snapshot but {} bits in the circuit",
+				nexus, snapshot, program
+			),
+			Self::Batch { snapshot: true } => write!(f, "snapshot was taken in batch mode"),
+			Self::Batch { snapshot: false } => write!(f, "snapshot was not taken in batch mode"),
+			Self::Malformed => write!(f, "snapshot is malformed"),
+		}
+	}
+}
+
+impl Error for RestoreError {}
+
+/// FNV-1a, which unlike the hasher of the standard library gives the same hashes across builds.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+	fn write(&mut self, bytes: &[u8]) {
+		for &b in bytes {
+			self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x100_0000_01b3);
+		}
+	}
+
+	fn finish(&self) -> u64 {
+		self.0
+	}
+}
+
+impl Program {
+	/// A hash of everything that affects how a state of this program evolves.
+	pub(super) fn fingerprint(&self) -> u64 {
+		let mut h = Fnv(0xcbf2_9ce4_8422_2325);
+		for n in self.nodes.iter() {
+			n.ir.hash(&mut h);
+			n.delay.hash(&mut h);
+		}
+		self.memory_size.hash(&mut h);
+		self.input_map.hash(&mut h);
+		self.output_map.hash(&mut h);
+		self.nexus_map.hash(&mut h);
+		self.drivers.hash(&mut h);
+		for c in self.clocks.iter() {
+			(c.out, c.high, c.low).hash(&mut h);
+		}
+		h.finish()
+	}
+}
+
+impl State {
+	/// Take a snapshot of this state, which can be restored with [`State::restore`].
+	pub fn snapshot(&self) -> Snapshot {
+		Snapshot {
+			program: self.program.fingerprint(),
+			nexuses: self.program.nexus_map.clone(),
+			components: self.program.component_map.clone(),
+			memory: self.read.clone(),
+			dirty: self.sorted_dirty(),
+			wheel: self.wheel.clone(),
+			ticks: self.ticks,
+			batch: self.batch,
+		}
+	}
+
+	/// Restore a snapshot taken with [`State::snapshot`].
+	///
+	/// If the program changed since the snapshot was taken only the value of each nexus and the
+	/// internal memory of each component are restored and all nodes will be updated in the next
+	/// step. Pending writes of delayed nodes are lost in that case.
+	///
+	/// The history is discarded. The state is left unchanged if the snapshot is incompatible with
+	/// the program.
+	pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), RestoreError> {
+		if snapshot.batch != self.batch {
+			return Err(RestoreError::Batch { snapshot: snapshot.batch });
+		}
+		let program = &self.program;
+		let same = snapshot.program == program.fingerprint();
+		if same && snapshot.memory.len() == self.read.len() {
+			let nodes = program.nodes.len();
+			if snapshot.dirty.iter().any(|&n| n >= nodes) || !snapshot.wheel.fits(&self.wheel) {
+				return Err(RestoreError::Malformed);
+			}
+			self.read.copy_from_slice(&snapshot.memory);
+			self.update_dirty = snapshot.dirty.iter().copied().collect();
+			self.wheel = snapshot.wheel.clone();
+		} else {
+			let (s, p) = (&snapshot.nexuses, &program.nexus_map);
+			if s.len() > p.len() {
+				return Err(RestoreError::Nexuses { snapshot: s.len(), program: p.len() });
+			}
+			for (nexus, (&(_, sb), &(_, pb))) in s.iter().zip(p.iter()).enumerate() {
+				if sb != pb {
+					return Err(RestoreError::Bits { nexus, snapshot: sb, program: pb });
+				}
+			}
+			let mut memory = program.initial_memory();
+			program
+				.remap_memory(s, &snapshot.components, &snapshot.memory, &mut memory)
+				.ok_or(RestoreError::Malformed)?;
+			self.read = memory;
+			self.update_dirty = (0..program.nodes.len()).collect();
+			self.wheel.clear();
+		}
+		self.write.copy_from_slice(&self.read);
+		self.ticks = snapshot.ticks;
+		self.set_history(self.history_capacity());
+		self.sync_breakpoints();
+		self.sample_probes();
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::NonZeroU16;
+	use std::sync::Arc;
+
+	type G = Graph<Box<dyn Component>, (), ()>;
+
+	/// An SR latch made of two NOR gates, with active-high set and reset inputs. Returns the
+	/// graph and the nexus of the `Q` output.
+	fn latch() -> (G, NexusHandle) {
+		let mut graph = G::new();
+		let one = NonZeroU16::new(1).unwrap();
+		let s = graph.add(Box::new(In::new("S", one, 0)), ());
+		let r = graph.add(Box::new(In::new("R", one, 1)), ());
+		let q = graph.add(Box::new(Out::new("Q", one, 0)), ());
+		let [sn, rn, qn, nq] = [(); 4].map(|_| graph.new_nexus(()));
+		let connect = |graph: &mut G, port, nexus| graph.connect(port, Some(nexus)).unwrap();
+		connect(&mut graph, Port::Output { node: s, port: 0 }, sn);
+		connect(&mut graph, Port::Output { node: r, port: 0 }, rn);
+		connect(&mut graph, Port::Input { node: q, port: 0 }, qn);
+		for (a, b, o) in [(rn, nq, qn), (sn, qn, nq)] {
+			let or = graph.add(Box::new(OrGate::new(NonZeroOneU8::new(2).unwrap())), ());
+			let not = graph.add(Box::new(NotGate::new()), ());
+			let n = graph.new_nexus(());
+			connect(&mut graph, Port::Input { node: or, port: 0 }, a);
+			connect(&mut graph, Port::Input { node: or, port: 1 }, b);
+			connect(&mut graph, Port::Output { node: or, port: 0 }, n);
+			connect(&mut graph, Port::Input { node: not, port: 0 }, n);
+			connect(&mut graph, Port::Output { node: not, port: 0 }, o);
+		}
+		(graph, qn)
+	}
+
+	fn set(state: &mut State, s: usize, r: usize) {
+		state.write_inputs(&[ir::Value::Set(s), ir::Value::Set(r)]);
+		assert!(state.run(1024).is_settled());
+	}
+
+	fn q(state: &State) -> ir::Value {
+		let mut out = [ir::Value::Floating];
+		state.read_outputs(&mut out);
+		out[0]
+	}
+
+	#[test]
+	fn restore() {
+		let mut state = Arc::new(latch().0.generate_ir()).new_state();
+		set(&mut state, 1, 0);
+		set(&mut state, 0, 0);
+		let snapshot = state.snapshot();
+		set(&mut state, 0, 1);
+		set(&mut state, 0, 0);
+		assert_eq!(q(&state), ir::Value::Set(0));
+
+		state.restore(&snapshot).unwrap();
+		assert_eq!(state.snapshot(), snapshot);
+		assert_eq!(q(&state), ir::Value::Set(1));
+		let mut inputs = [ir::Value::Floating; 2];
+		state.read_inputs(&mut inputs);
+		assert_eq!(inputs, [ir::Value::Set(0); 2]);
+
+		// Snapshots survive being written to disk.
+		let s = ron::to_string(&snapshot).unwrap();
+		assert_eq!(ron::from_str::<Snapshot>(&s).unwrap(), snapshot);
+	}
+
+	#[test]
+	fn changed_program() {
+		let (mut graph, qn) = latch();
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		set(&mut state, 1, 0);
+		set(&mut state, 0, 0);
+		let snapshot = state.snapshot();
+		set(&mut state, 0, 1);
+
+		// Add a second output, which changes the program.
+		let one = NonZeroU16::new(1).unwrap();
+		let o = graph.add(Box::new(Out::new("P", one, 1)), ());
+		graph
+			.connect(Port::Input { node: o, port: 0 }, Some(qn))
+			.unwrap();
+		let mut state = state.adapt(Arc::new(graph.generate_ir()));
+		state.restore(&snapshot).unwrap();
+		assert!(state.run(1024).is_settled());
+		let mut out = [ir::Value::Floating; 2];
+		state.read_outputs(&mut out);
+		assert_eq!(out, [ir::Value::Set(1); 2]);
+
+		// Nexuses that changed width are rejected.
+		let wide = NonZeroU16::new(100).unwrap();
+		let c = graph.add(Box::new(Constant::new(wide, 0)), ());
+		graph
+			.connect(Port::Output { node: c, port: 0 }, Some(qn))
+			.unwrap();
+		let mut state = state.adapt(Arc::new(graph.generate_ir()));
+		let e = state.restore(&snapshot).unwrap_err();
+		let (nexus, snapshot, program) = (qn.index(), 32, 100);
+		assert_eq!(e, RestoreError::Bits { nexus, snapshot, program });
+		assert_eq!(
+			e.to_string(),
+			"nexus 2 is 32 bits wide in the snapshot but 100 bits in the circuit"
+		);
+	}
+
+	#[test]
+	fn changed_program_memory() {
+		let mut graph = G::new();
+		let ram = RandomAccessMemory::new(NonZeroU16::new(2).unwrap(), NonZeroU16::new(8).unwrap());
+		let ram = graph.add(Box::new(ram), ());
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		state.write_memory(ram, 1, ir::Value::Set(0x12));
+		let snapshot = state.snapshot();
+		state.write_memory(ram, 1, ir::Value::Set(0x34));
+
+		// A wide nexus moves the memory of the RAM, which must keep its contents.
+		let wide = NonZeroU16::new(100).unwrap();
+		let c = graph.add(Box::new(Constant::new(wide, 0)), ());
+		let n = graph.new_nexus(());
+		graph
+			.connect(Port::Output { node: c, port: 0 }, Some(n))
+			.unwrap();
+		let mut state = state.adapt(Arc::new(graph.generate_ir()));
+		assert_eq!(state.read_memory(ram, 1), ir::Value::Set(0x34));
+		state.restore(&snapshot).unwrap();
+		assert_eq!(state.read_memory(ram, 1), ir::Value::Set(0x12));
+
+		// RAM of which the size changed is reset.
+		let (c, _) = graph.get_mut(ram).unwrap();
+		c.set_property("address bits", SetProperty::Int(3)).unwrap();
+		let mut state = state.adapt(Arc::new(graph.generate_ir()));
+		state.restore(&snapshot).unwrap();
+		assert_eq!(state.read_memory(ram, 1), ir::Value::Floating);
+	}
+}
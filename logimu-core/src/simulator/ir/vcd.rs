@@ -0,0 +1,293 @@
+//! # Value Change Dump
+//!
+//! Traces of a simulation can be written to VCD files, which can be viewed with e.g. GTKWave.
+//! Each tick is written as one nanosecond. Components with a delay take multiple ticks to update
+//! their outputs, so their delays show up as is.
+//!
+//! Values are sampled after each step. Changes made in between steps, e.g. by writing inputs, show
+//! up at the time of the next step. Time can't go back in a VCD file, so steps done after going
+//! back in time are only recorded once the state gets past the last recorded time again.
+
+use super::breakpoint::Watch;
+use super::program::{State, Value, WORD_BITS};
+use core::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// A VCD file being written, see [`State::set_vcd`].
+pub struct Vcd {
+	out: Box<dyn Write + Send>,
+	signals: Box<[Signal]>,
+	/// The last time written, if the header has been written.
+	time: Option<u64>,
+	/// The first error that occured while writing. Nothing is written after an error.
+	error: Option<io::Error>,
+}
+
+#[derive(Debug)]
+struct Signal {
+	name: Box<str>,
+	watch: Watch,
+	id: Box<str>,
+	bits: u16,
+	/// The last value written.
+	value: Option<Value>,
+}
+
+impl Vcd {
+	/// Write a trace of the given signals to a writer. Whitespace in names is replaced with `_`.
+	pub fn new<N>(
+		out: impl Write + Send + 'static,
+		signals: impl IntoIterator<Item = (N, Watch)>,
+	) -> Self
+	where
+		N: AsRef<str>,
+	{
+		let signals = signals
+			.into_iter()
+			.enumerate()
+			.map(|(i, (name, watch))| Signal {
+				name: name.as_ref().replace(char::is_whitespace, "_").into(),
+				watch,
+				id: identifier(i),
+				bits: 1,
+				value: None,
+			});
+		Self {
+			out: Box::new(out),
+			signals: signals.collect(),
+			time: None,
+			error: None,
+		}
+	}
+
+	/// Write a trace of the given signals to a new file.
+	pub fn create<N>(
+		path: impl AsRef<Path>,
+		signals: impl IntoIterator<Item = (N, Watch)>,
+	) -> io::Result<Self>
+	where
+		N: AsRef<str>,
+	{
+		Ok(Self::new(BufWriter::new(File::create(path)?), signals))
+	}
+
+	/// Flush the trace, returning the first error that occured while writing, if any.
+	pub fn finish(mut self) -> io::Result<()> {
+		match self.error.take() {
+			Some(e) => Err(e),
+			None => self.out.flush(),
+		}
+	}
+
+	/// Write the header and the initial value of each signal if that wasn't done yet, otherwise
+	/// the values that changed since.
+	fn begin(&mut self, state: &State) {
+		if self.time.is_some() {
+			return self.sample(state);
+		}
+		for s in self.signals.iter_mut() {
+			s.bits = state.watch_bits(s.watch);
+		}
+		let mut header = String::from("$version logimu $end\n$timescale 1ns $end\n");
+		header += "$scope module circuit $end\n";
+		for s in self.signals.iter() {
+			header += &format!("$var wire {} {} {} $end\n", s.bits, s.id, s.name);
+		}
+		header += "$upscope $end\n$enddefinitions $end\n";
+		header += &format!("#{}\n$dumpvars\n", state.ticks());
+		self.write(|out| out.write_all(header.as_bytes()));
+		self.time = Some(state.ticks());
+		self.write_changes(state);
+		self.write(|out| writeln!(out, "$end"));
+	}
+
+	/// Write the values that changed since the last sample.
+	fn sample(&mut self, state: &State) {
+		let time = self.time.expect("header not written");
+		if state.ticks() < time {
+			return;
+		}
+		if self
+			.signals
+			.iter()
+			.all(|s| s.value == Some(state.read_watch(s.watch)))
+		{
+			return;
+		}
+		if state.ticks() > time {
+			self.write(|out| writeln!(out, "#{}", state.ticks()));
+			self.time = Some(state.ticks());
+		}
+		self.write_changes(state);
+	}
+
+	fn write_changes(&mut self, state: &State) {
+		for i in 0..self.signals.len() {
+			let s = &mut self.signals[i];
+			let value = state.read_watch(s.watch);
+			if s.value == Some(value) {
+				continue;
+			}
+			s.value = Some(value);
+			let (id, bits) = (s.id.clone(), s.bits);
+			self.write(|out| match bits {
+				1 => writeln!(out, "{}{}", bit(value, 0), id),
+				_ => {
+					let value = (0..bits).rev().map(|i| bit(value, i)).collect::<String>();
+					writeln!(out, "b{} {}", value, id)
+				}
+			});
+		}
+	}
+
+	/// Write to the output unless an error occured before.
+	fn write(&mut self, f: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+		if self.error.is_none() {
+			self.error = f(&mut self.out).err();
+		}
+	}
+}
+
+impl fmt::Debug for Vcd {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct(stringify!(Vcd))
+			.field("signals", &self.signals)
+			.field("time", &self.time)
+			.field("error", &self.error)
+			.finish()
+	}
+}
+
+/// A short identifier made of printable ASCII characters.
+fn identifier(mut i: usize) -> Box<str> {
+	let mut s = String::new();
+	loop {
+		s.push(char::from(b'!' + (i % 94) as u8));
+		i /= 94;
+		if i == 0 {
+			break s.into();
+		}
+	}
+}
+
+/// The character of bit `i` of a value: `z` if it is floating and `x` if it is shorted.
+fn bit(value: Value, i: u16) -> char {
+	let mask = 1 << (i % WORD_BITS);
+	match value.word(usize::from(i / WORD_BITS)).to_masks(mask) {
+		(_, _, s) if s != 0 => 'x',
+		(_, f, _) if f != 0 => 'z',
+		(v, ..) if v != 0 => '1',
+		_ => '0',
+	}
+}
+
+impl State {
+	/// Start writing a trace of each step to the given VCD file, or stop if `None`. The previous
+	/// file is returned, which should be finished with [`Vcd::finish`].
+	pub fn set_vcd(&mut self, vcd: Option<Vcd>) -> Option<Vcd> {
+		let prev = self.vcd.take();
+		self.vcd = vcd.map(|mut v| {
+			v.begin(self);
+			v
+		});
+		prev
+	}
+
+	/// Whether a trace is being written.
+	pub fn has_vcd(&self) -> bool {
+		self.vcd.is_some()
+	}
+
+	/// Write the values after a step to the VCD file, if any.
+	pub(super) fn trace(&mut self) {
+		if let Some(mut v) = self.vcd.take() {
+			v.sample(self);
+			self.vcd = Some(v);
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::simulator::*;
+	use core::num::NonZeroU16;
+	use std::sync::{Arc, Mutex};
+
+	/// A writer whose output can be inspected after it was moved.
+	#[derive(Clone, Default)]
+	struct Shared(Arc<Mutex<Vec<u8>>>);
+
+	impl Write for Shared {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock().unwrap().write(buf)
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn trace() {
+		let mut graph = Graph::<Box<dyn Component>, (), ()>::new();
+		let (one, two) = (NonZeroU16::new(1).unwrap(), NonZeroU16::new(2).unwrap());
+		let i = graph.add(Box::new(In::new("a", one, 0)), ());
+		let not = graph.add(Box::new(NotGate::new()), ());
+		let o = graph.add(Box::new(Out::new("y", one, 0)), ());
+		let c = graph.add(Box::new(Constant::new(two, 2)), ());
+		let [a, y, n] = [(); 3].map(|_| graph.new_nexus(()));
+		for (port, nexus) in [
+			(Port::Output { node: i, port: 0 }, a),
+			(Port::Input { node: not, port: 0 }, a),
+			(Port::Output { node: not, port: 0 }, y),
+			(Port::Input { node: o, port: 0 }, y),
+			(Port::Output { node: c, port: 0 }, n),
+		] {
+			graph.connect(port, Some(nexus)).unwrap();
+		}
+
+		let mut state = Arc::new(graph.generate_ir()).new_state();
+		let out = Shared::default();
+		let signals = [
+			("in a", Watch::Input(0)),
+			("y", Watch::Output(0)),
+			("n", Watch::Nexus(n)),
+		];
+		assert!(state
+			.set_vcd(Some(Vcd::new(out.clone(), signals)))
+			.is_none());
+		state.write_inputs(&[Value::Set(1)]);
+		assert!(state.run(1024).is_settled());
+		state.write_inputs(&[Value::Set(0)]);
+		assert!(state.run(1024).is_settled());
+		state.set_vcd(None).unwrap().finish().unwrap();
+
+		let out = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+		let expected = "\
+$version logimu $end
+$timescale 1ns $end
+$scope module circuit $end
+$var wire 1 ! in_a $end
+$var wire 1 \" y $end
+$var wire 2 # n $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+z!
+0\"
+b00 #
+$end
+#1
+1!
+b10 #
+#2
+0!
+1\"
+";
+		assert_eq!(out, expected);
+	}
+}
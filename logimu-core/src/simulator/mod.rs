@@ -1,9 +1,13 @@
 mod base;
+#[cfg(test)]
+mod bench;
+mod builder;
 mod component;
 pub mod graph;
 pub mod ir;
 
 pub use base::*;
+pub use builder::*;
 pub use component::*;
 pub use graph::*;
 pub use ir::{IrOp, Program, State};
@@ -0,0 +1,211 @@
+//! # Command line
+//!
+//! `logimu test [--junit <file>] [--tap <file>] <files...>` runs the tests of each circuit without
+//! opening a window and exits with a non-zero status if any test failed or a circuit couldn't be
+//! loaded. The results can also be written as JUnit XML or TAP.
+
+use core::fmt;
+use logimu_core::circuit::{
+	self, Aabb, Circuit, CircuitComponent, LoadError, Outcome, Report, Suite,
+};
+use logimu_core::script::ParseError;
+use logimu_core::simulator::ir;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub enum TestFileError {
+	Load(LoadError),
+	Parse(ParseError),
+}
+
+impl fmt::Display for TestFileError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Load(e) => e.fmt(f),
+			Self::Parse(e) => e.fmt(f),
+		}
+	}
+}
+
+const USAGE: &str = "usage: logimu test [--junit <file>] [--tap <file>] <files...>";
+
+/// Run the subcommand in the arguments, if any. Returns the exit status.
+pub fn run(args: &[String]) -> Option<i32> {
+	if args.first().map(String::as_str) != Some("test") {
+		return None;
+	}
+	let (mut files, mut junit, mut tap) = (Vec::new(), None, None);
+	let mut args = args[1..].iter();
+	while let Some(a) = args.next() {
+		let report = match a.as_str() {
+			"--junit" => &mut junit,
+			"--tap" => &mut tap,
+			o if o.starts_with("--") => {
+				eprintln!("error: unknown option {}\n{}", o, USAGE);
+				return Some(2);
+			}
+			_ => {
+				files.push(a);
+				continue;
+			}
+		};
+		match args.next() {
+			Some(f) => *report = Some(Path::new(f)),
+			None => {
+				eprintln!("error: missing file after {}\n{}", a, USAGE);
+				return Some(2);
+			}
+		}
+	}
+	if files.is_empty() {
+		eprintln!("{}", USAGE);
+		return Some(2);
+	}
+	let files = files.iter().map(|f| (*f, Path::new(f).canonicalize()));
+	let report = test(files.collect());
+
+	let failed = report
+		.suites
+		.iter()
+		.any(|s| s.error.is_some() || s.failures() > 0);
+	let mut status = i32::from(failed);
+	for (path, junit) in [(junit, true), (tap, false)] {
+		let path = match path {
+			Some(path) => path,
+			None => continue,
+		};
+		let r = File::create(path).and_then(|f| match junit {
+			true => report.write_junit(BufWriter::new(f)),
+			false => report.write_tap(BufWriter::new(f)),
+		});
+		if let Err(e) = r {
+			eprintln!("error: failed to write {}: {}", path.display(), e);
+			status = 1;
+		}
+	}
+	Some(status)
+}
+
+/// Run the tests of all circuits, printing the results.
+fn test(files: Vec<(&String, std::io::Result<PathBuf>)>) -> Report {
+	let (mut passed, mut failed) = (0, 0);
+	let mut report = Report::default();
+	for (name, path) in files {
+		let path = path.map_err(|e| TestFileError::Load(LoadError::Io(e)));
+		let mut suite = Suite { name: name.clone(), outcomes: Vec::new(), error: None };
+		match path.and_then(|p| run_file(&p)) {
+			Ok(outcomes) => {
+				for o in outcomes.iter() {
+					match &o.result {
+						Ok(()) => println!("test {} '{}' ... ok", name, o.name),
+						Err(e) => println!("test {} '{}' ... FAILED: {}", name, o.name, e),
+					}
+					o.log.lines().for_each(|l| println!("    {}", l));
+				}
+				suite.outcomes = outcomes;
+				failed += suite.failures();
+				passed += suite.outcomes.len() - suite.failures();
+			}
+			Err(e) => {
+				println!("error: failed to load {}: {}", name, e);
+				suite.error = Some(e.to_string());
+				failed += 1;
+			}
+		}
+		report.suites.push(suite);
+	}
+	println!("{} passed; {} failed", passed, failed);
+	report
+}
+
+/// Load a circuit and run all its tests. Relative paths are resolved against the directory of
+/// the circuit, as ICs are referred to relative to it.
+pub fn run_file(path: &Path) -> Result<Vec<Outcome>, TestFileError> {
+	let file = File::open(path).map_err(|e| TestFileError::Load(LoadError::Io(e)))?;
+	let dir = path.parent().unwrap_or_else(|| Path::new(""));
+	circuit::with_directory(dir, || {
+		let mut circuit: Circuit<Box<dyn CircuitComponent>> =
+			ron::de::from_reader(file).map_err(|e| TestFileError::Load(LoadError::Serde(e)))?;
+		run_circuit(&mut circuit).map_err(TestFileError::Parse)
+	})
+}
+
+/// Run all tests of a circuit, each with a new simulation state.
+pub fn run_circuit<C>(circuit: &mut Circuit<C>) -> Result<Vec<Outcome>, ParseError>
+where
+	C: CircuitComponent,
+{
+	let (mut inputs, mut outputs) = (0, 0);
+	for (c, ..) in circuit.components(Aabb::ALL) {
+		inputs = inputs.max(c.external_input().map_or(0, |i| i + 1));
+		outputs = outputs.max(c.external_output().map_or(0, |o| o + 1));
+	}
+	let program = Arc::new(circuit.generate_ir());
+	let tests = circuit.tests()?;
+	let run = |t: &logimu_core::circuit::Test<C>| {
+		let mut state = program.clone().new_state();
+		let mut inputs = vec![ir::Value::Floating; inputs];
+		let mut outputs = vec![ir::Value::Floating; outputs];
+		let mut log = String::new();
+		let start = Instant::now();
+		let result = t.run(&mut state, &mut inputs, &mut outputs, &mut log);
+		let duration = start.elapsed();
+		Outcome { name: t.name().into(), result, log, duration }
+	};
+	Ok(tests.iter().map(run).collect())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use core::num::NonZeroU16;
+	use logimu_core::circuit::{Direction, Point, Wire};
+	use logimu_core::simulator::{In, NotGate, Out};
+
+	#[test]
+	fn run_tests() {
+		let mut circuit = Circuit::<Box<dyn CircuitComponent>>::default();
+		let mut not = NotGate::new();
+		not.bits = NonZeroU16::new(1).unwrap();
+		let bits = NonZeroU16::new(1).unwrap();
+		circuit.add_component(
+			Box::new(In::new("a", bits, 0)),
+			Point::new(0, 0),
+			Direction::Right,
+		);
+		circuit.add_component(Box::new(not), Point::new(2, 0), Direction::Right);
+		circuit.add_component(
+			Box::new(Out::new("y", bits, 0)),
+			Point::new(4, 0),
+			Direction::Right,
+		);
+		circuit.add_wire(Wire::new(Point::new(0, 0), Point::new(1, 0)));
+		circuit.add_wire(Wire::new(Point::new(3, 0), Point::new(4, 0)));
+		circuit.script_source = r#"
+			(test "invert" (in "a" 1) (run) (print (out "y")) (assert (= (out "y") 0)))
+			(test "broken" (in "a" 0) (run) (assert (= (out "y") 0)))
+		"#
+		.into();
+
+		let outcomes = run_circuit(&mut circuit).unwrap();
+		let names = outcomes.iter().map(|o| &*o.name).collect::<Vec<_>>();
+		assert_eq!(names, ["invert", "broken"]);
+		assert!(outcomes[0].result.is_ok());
+		assert_eq!(outcomes[0].log, "0\n");
+		assert!(outcomes[1].result.is_err());
+	}
+
+	#[test]
+	fn bad_arguments() {
+		let run = |args: &[&str]| run(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>());
+		assert_eq!(run(&["edit"]), None);
+		assert_eq!(run(&["test"]), Some(2));
+		assert_eq!(run(&["test", "a.logimu", "--junit"]), Some(2));
+		assert_eq!(run(&["test", "--tap"]), Some(2));
+		assert_eq!(run(&["test", "--junt", "r.xml", "a.logimu"]), Some(2));
+	}
+}
@@ -1,23 +1,21 @@
-use crate::circuit::{CircuitComponent, Direction, PointOffset, RelativeAabb};
-use crate::impl_dyn;
-use crate::simulator::{
-	ir::Value, Component, ExternalType, GenerateIr, InputType, OutputType, Property, SetProperty,
-};
 use core::any::Any;
-use core::ops::Mul;
+use core::num::NonZeroU8;
 use eframe::egui::{Painter, Pos2, Vec2};
+use logimu_core::circuit::{CircuitComponent, Direction, PointOffset, RelativeAabb};
+use logimu_core::impl_dyn;
+use logimu_core::simulator::{
+	ir::{Value, MAX_WORDS},
+	Component, ExternalType, GenerateIr, InputType, OutputType, Property, SetProperty,
+};
 use std::error::Error;
 
-impl Mul<Vec2> for Direction {
-	type Output = Vec2;
-
-	fn mul(self, rhs: Vec2) -> Self::Output {
-		self.rotate_vec2(rhs)
-	}
+/// Rotate GUI vectors the same way [`Direction`] rotates [`PointOffset`]s.
+pub trait Rotate {
+	fn rotate_vec2(self, v: Vec2) -> Vec2;
 }
 
-impl Direction {
-	pub fn rotate_vec2(self, v: Vec2) -> Vec2 {
+impl Rotate for Direction {
+	fn rotate_vec2(self, v: Vec2) -> Vec2 {
 		match self {
 			Self::Right => Vec2::new(v.x, v.y),
 			Self::Down => Vec2::new(-v.y, v.x),
@@ -54,6 +52,8 @@ impl_dyn! {
 		ref generate_ir(gen: GenerateIr) -> usize;
 		ref properties() -> Box<[Property]>;
 		ref external_type() -> Option<ExternalType>;
+		ref delay() -> NonZeroU8;
+		ref input_masks() -> Box<[[usize; MAX_WORDS]]>;
 		mut set_property(name: &str, property: SetProperty) -> Result<(), Box<dyn Error>>;
 	}
 }
@@ -1,6 +1,6 @@
 use super::{Log, PropertyValue, SetProperty};
-use crate::circuit::CircuitComponent;
 use eframe::egui;
+use logimu_core::circuit::CircuitComponent;
 
 /// Show properties of one or more components.
 #[derive(Default)]
@@ -65,21 +65,21 @@ impl ComponentsInfo {
 							changed.push((prop.name, SetProperty::Str(value.into())));
 						}
 					}
-					PropertyValue::Mask { value } => {
+					PropertyValue::Range { value } => {
 						let (mut text, modify) = match prop_buf.take() {
 							Some((name, buf)) if name == prop.name => (buf, true),
 							pb => {
 								prop_buf = pb;
-								(mask_to_string(value), false)
+								(value.to_string(), false)
 							}
 						};
 						let te = egui::TextEdit::singleline(&mut text).hint_text(name);
 						if ui.add(te).has_focus() {
 							self.property_value_buffer = Some((prop.name, text));
 						} else if modify {
-							match string_to_mask(&text) {
-								Ok(mask) => changed.push((prop.name, SetProperty::Mask(mask))),
-								Err(e) => log.error(e),
+							match text.parse() {
+								Ok(range) => changed.push((prop.name, SetProperty::Range(range))),
+								Err(e) => log.error(e.to_string()),
 							}
 						}
 					}
@@ -96,46 +96,3 @@ impl ComponentsInfo {
 		changed
 	}
 }
-
-/// Convert a mask to a human-readable string.
-pub fn mask_to_string(mut mask: usize) -> String {
-	let mut s = "".to_string();
-	let (mut offt, mut comma) = (0, false);
-	while mask > 0 {
-		if mask & 1 > 0 {
-			comma.then(|| s.push(','));
-			s.extend(offt.to_string().chars());
-			let o = offt;
-			while mask & 2 > 0 {
-				mask >>= 1;
-				offt += 1;
-			}
-			if offt != o {
-				s.push('-');
-				s.extend(offt.to_string().chars());
-			}
-			comma = true;
-		}
-		mask >>= 1;
-		offt += 1;
-	}
-	s
-}
-
-/// Convert a human-readable mask string to an actual mask.
-fn string_to_mask(s: &str) -> Result<usize, String> {
-	let mut mask = 0;
-	for r in s.split(',').map(str::trim) {
-		if let Some((min, max)) = r.split_once('-') {
-			let (min, max) = (min.trim_end(), max.trim_start());
-			let min = min.parse::<u8>().map_err(|e| e.to_string())?;
-			let max = max.parse::<u8>().map_err(|e| e.to_string())?;
-			for i in min..=max {
-				mask |= 1 << i;
-			}
-		} else {
-			mask |= 1 << r.parse::<u8>().map_err(|e| e.to_string())?;
-		}
-	}
-	Ok(mask)
-}
@@ -1,4 +1,4 @@
-use crate::simulator::{Component, Property, PropertyValue, SetProperty};
+use logimu_core::simulator::{Component, Property, PropertyValue, SetProperty};
 
 /// Copied properties of a component
 #[derive(Default)]
@@ -11,7 +11,7 @@ impl CopiedProperties {
 		for p in &*self.properties {
 			let v = match &p.value {
 				PropertyValue::Int { value, .. } => SetProperty::Int(value.clone()),
-				PropertyValue::Mask { value, .. } => SetProperty::Mask(value.clone()),
+				PropertyValue::Range { value } => SetProperty::Range(*value),
 				PropertyValue::Str { value } => SetProperty::Str(value.clone()),
 			};
 			let _ = to.set_property(&p.name, v);
@@ -1,53 +1,11 @@
-use super::{ComponentPlacer, Draw};
-use crate::circuit::{CircuitComponent, Direction, PointOffset, RelativeAabb};
+use super::{ComponentPlacer, Draw, Rotate};
+use logimu_core::circuit::{in_out_size, CircuitComponent, Direction, PointOffset, RelativeAabb};
 
-use crate::simulator::{ir::Value, *};
-use core::num::NonZeroU8;
+use core::iter;
+use core::num::NonZeroU16;
 use eframe::egui::paint::{CircleShape, Mesh, RectShape, Rgba};
 use eframe::egui::{Align2, Color32, Pos2, Rect, Shape, Stroke, TextStyle, Vec2};
-
-const IN_NOT: &[PointOffset] = &[PointOffset::new(-1, 0)];
-const OUT: &[PointOffset] = &[PointOffset::new(1, 0)];
-const CENTER: &[PointOffset] = &[PointOffset::new(0, 0)];
-
-macro_rules! impl_cc {
-	($name:ident, $in:expr, $out:expr, (($min_x:literal, $min_y:literal), ($max_x:literal, $max_y:literal))) => {
-		#[typetag::serde]
-		impl CircuitComponent for $name {
-			fn input_points(&self) -> Box<[PointOffset]> {
-				let i = i16::from(self.inputs.get());
-				(-i / 2..0)
-					.chain((i % 2 != 0).then(|| 0))
-					.chain(1..=i / 2)
-					.map(|y| PointOffset::new(-1, y.try_into().unwrap()))
-					.collect()
-			}
-
-			fn output_points(&self) -> Box<[PointOffset]> {
-				$out.into()
-			}
-
-			fn input_name(&self, index: usize) -> Box<str> {
-				assert!(index < usize::from(self.inputs.get()));
-				format!("Input {}", index).into()
-			}
-
-			fn output_name(&self, index: usize) -> Box<str> {
-				assert!(index < 1);
-				"Output".into()
-			}
-
-			fn aabb(&self, dir: Direction) -> RelativeAabb {
-				dir * RelativeAabb::new(
-					PointOffset::new($min_x, $min_y),
-					PointOffset::new($max_x, $max_y),
-				)
-			}
-		}
-	};
-}
-
-impl_cc!(AndGate, IN, OUT, ((-1, -1), (1, 1)));
+use logimu_core::simulator::{ir::Value, *};
 
 #[typetag::serde]
 impl ComponentPlacer for AndGate {
@@ -73,8 +31,6 @@ impl ComponentPlacer for AndGate {
 	}
 }
 
-impl_cc!(OrGate, IN, OUT, ((-1, -1), (1, 1)));
-
 #[typetag::serde]
 impl ComponentPlacer for OrGate {
 	fn name(&self) -> Box<str> {
@@ -135,8 +91,6 @@ impl ComponentPlacer for OrGate {
 	}
 }
 
-impl_cc!(XorGate, IN, OUT, ((-1, -1), (1, 1)));
-
 #[typetag::serde]
 impl ComponentPlacer for XorGate {
 	fn name(&self) -> Box<str> {
@@ -209,31 +163,6 @@ impl ComponentPlacer for XorGate {
 	}
 }
 
-#[typetag::serde]
-impl CircuitComponent for NotGate {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		IN_NOT.into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		OUT.into()
-	}
-
-	fn input_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Input".into()
-	}
-
-	fn output_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Output".into()
-	}
-
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		dir * RelativeAabb::new(PointOffset::new(-1, 0), PointOffset::new(1, 0))
-	}
-}
-
 #[typetag::serde]
 impl ComponentPlacer for NotGate {
 	fn name(&self) -> Box<str> {
@@ -262,30 +191,26 @@ impl ComponentPlacer for NotGate {
 }
 
 #[typetag::serde]
-impl CircuitComponent for In {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		[].into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		CENTER.into()
-	}
-
-	fn external_input(&self) -> Option<usize> {
-		Some(self.index)
+impl ComponentPlacer for TriStateBuffer {
+	fn name(&self) -> Box<str> {
+		"tri-state buffer".into()
 	}
 
-	fn input_name(&self, _: usize) -> Box<str> {
-		panic!()
-	}
+	fn draw(&self, draw: Draw) {
+		let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
+		let stroke = stroke(alpha);
 
-	fn output_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		self.name.clone()
-	}
+		let top = pos + dir.rotate_vec2(Vec2::new(0.0, 16.0));
+		let bottom = pos + dir.rotate_vec2(Vec2::new(0.0, 4.0));
+		painter.line_segment([top, bottom], stroke);
 
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		aabb_in_out(self.bits, dir)
+		let verts = [(-16.0, 7.0), (-16.0, -7.0), (14.0, 0.0)];
+		let v = verts
+			.into_iter()
+			.rev()
+			.map(|(x, y)| pos + dir.rotate_vec2(Vec2::new(x, y)))
+			.collect();
+		painter.add(Shape::convex_polygon(v, fill(alpha), stroke));
 	}
 }
 
@@ -301,34 +226,6 @@ impl ComponentPlacer for In {
 	}
 }
 
-#[typetag::serde]
-impl CircuitComponent for Out {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		CENTER.into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		[].into()
-	}
-
-	fn external_output(&self) -> Option<usize> {
-		Some(self.index)
-	}
-
-	fn input_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		self.name.clone()
-	}
-
-	fn output_name(&self, _: usize) -> Box<str> {
-		panic!()
-	}
-
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		aabb_in_out(self.bits, dir)
-	}
-}
-
 #[typetag::serde]
 impl ComponentPlacer for Out {
 	fn name(&self) -> Box<str> {
@@ -341,57 +238,24 @@ impl ComponentPlacer for Out {
 	}
 }
 
-fn aabb_in_out(bits: NonZeroU8, dir: Direction) -> RelativeAabb {
-	let w = (bits.get() > 1).then(|| 4).unwrap_or(1);
-	let h = match dir {
-		Direction::Left | Direction::Right => [0, 1, 1, 2][usize::from(bits.get() - 1) / 8],
-		Direction::Up | Direction::Down => [1, 2, 3, 4][usize::from(bits.get() - 1) / 8],
-	};
-	let ((ax, ay), (bx, by)) = match dir {
-		Direction::Right => ((0, -h), (w, h)),
-		Direction::Left => ((-w, -h), (0, h)),
-		Direction::Up => ((-w / 2, -h), (w / 2, 0)),
-		Direction::Down => ((-w / 2, 0), (w / 2, h)),
-	};
-	RelativeAabb::new(PointOffset::new(ax, ay), PointOffset::new(bx, by))
-}
-
-fn draw_in_out(draw: Draw, value: Value, bits: u8, corner_radius: f32) {
+fn draw_in_out(draw: Draw, value: Value, bits: u16, corner_radius: f32) {
 	let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
 	let stroke = stroke(alpha);
+	let (w, rows) = in_out_size(bits);
 	if bits == 1 {
 		let rect = Rect::from_center_size(pos, Vec2::new(16.0, 16.0))
 			.translate(dir.rotate_vec2(Vec2::new(8.0, 0.0)));
-		let fill = match value {
-			Value::Set(i) => [Color32::DARK_GREEN, Color32::GREEN][i & 1],
-			Value::Floating => Color32::BLUE,
-			Value::Short => Color32::RED,
-		};
-		let fill = color_alpha(fill, alpha);
+		let fill = color_alpha(value_color(value), alpha);
 		painter.add(Shape::Rect(RectShape { corner_radius, fill, rect, stroke }));
 	} else {
-		let s: String = (0..bits)
-			.flat_map(|i| {
-				let a = (i != 0 && i % 8 == 0)
-					.then(|| ["\n0", "\n1", "\nx", "\nE"])
-					.unwrap_or(["0", "1", "x", "E"]);
-				match value {
-					Value::Set(n) => a[(n >> i) & 1],
-					Value::Floating => a[2],
-					Value::Short => a[3],
-				}
-				.chars()
-			})
-			.chain(
-				"        "[..(bits > 8)
-					.then(|| (64 - usize::from(bits)) % 8)
-					.unwrap_or(0)]
-					.chars(),
-			)
-			.collect();
-		let x = 16.0 * 4.0;
-		let y = 16.0 * [1.0, 2.0, 3.0, 4.0][usize::from(bits - 1) >> 3];
-		let mut offt = dir.rotate_vec2(Vec2::new(8.0 * 4.0, 0.0));
+		let s = if bits > 32 {
+			hex_digits(value, bits)
+		} else {
+			bit_digits(value, bits)
+		};
+		let x = 16.0 * f32::from(w);
+		let y = 16.0 * [1.0, 2.0, 3.0, 4.0][rows - 1];
+		let mut offt = dir.rotate_vec2(Vec2::new(8.0 * f32::from(w), 0.0));
 		let mut rect = Rect::from_center_size(pos, Vec2::new(x, y));
 		match dir {
 			Direction::Left | Direction::Right => (),
@@ -415,30 +279,59 @@ fn draw_in_out(draw: Draw, value: Value, bits: u8, corner_radius: f32) {
 	}
 }
 
-#[typetag::serde]
-impl CircuitComponent for Splitter {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		IN_NOT.into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		(0..self.outputs().len().try_into().unwrap())
-			.map(|y| PointOffset::new(1, y))
-			.collect()
-	}
-
-	fn input_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Input".into()
-	}
-
-	fn output_name(&self, index: usize) -> Box<str> {
-		format!("{}", super::mask_to_string(self.outputs[index].get())).into()
+/// The bits of a value with 8 bits per row, least significant bit first.
+fn bit_digits(value: Value, bits: u16) -> String {
+	{
+		let (n, floating, short) = value.to_masks((1 << bits) - 1);
+		let s: String = (0..bits)
+			.flat_map(|i| {
+				let a = (i != 0 && i % 8 == 0)
+					.then(|| ["\n0", "\n1", "\nx", "\nE"])
+					.unwrap_or(["0", "1", "x", "E"]);
+				if (floating >> i) & 1 > 0 {
+					a[2]
+				} else if (short >> i) & 1 > 0 {
+					a[3]
+				} else {
+					a[(n >> i) & 1]
+				}
+				.chars()
+			})
+			.chain(
+				"        "[..(bits > 8)
+					.then(|| (64 - usize::from(bits)) % 8)
+					.unwrap_or(0)]
+					.chars(),
+			)
+			.collect();
+		s
 	}
+}
 
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		aabb_merger_splitter(&*self.input_points(), &*self.output_points(), dir)
-	}
+/// The hexadecimal digits of a value with 16 digits per row, least significant digit first.
+/// Digits with floating or shorted bits are shown as `x` or `E`.
+fn hex_digits(value: Value, bits: u16) -> String {
+	let digits = usize::from((bits + 3) / 4);
+	let s = (0..digits).flat_map(|i| {
+		let (shift, mask) = (
+			i % 16 * 4,
+			ir::word_mask(bits, i / 16) >> (i % 16 * 4) & 0xf,
+		);
+		let (n, floating, short) = value.word(i / 16).to_masks(usize::MAX);
+		let c = if (floating >> shift) & mask != 0 {
+			'x'
+		} else if (short >> shift) & mask != 0 {
+			'E'
+		} else {
+			char::from_digit(((n >> shift) & mask) as u32, 16).unwrap()
+		};
+		(i != 0 && i % 16 == 0)
+			.then(|| '\n')
+			.into_iter()
+			.chain(Some(c))
+	});
+	let pad = (digits > 16).then(|| (16 - digits % 16) % 16).unwrap_or(0);
+	s.chain(iter::repeat(' ').take(pad)).collect()
 }
 
 #[typetag::serde]
@@ -448,33 +341,7 @@ impl ComponentPlacer for Splitter {
 	}
 
 	fn draw(&self, draw: Draw) {
-		draw_merger_splitter(draw, &*self.input_points(), &*self.output_points())
-	}
-}
-
-#[typetag::serde]
-impl CircuitComponent for Merger {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		(0..self.inputs().len().try_into().unwrap())
-			.map(|y| PointOffset::new(-1, y))
-			.collect()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		OUT.into()
-	}
-
-	fn input_name(&self, index: usize) -> Box<str> {
-		format!("{}", super::mask_to_string(self.inputs[index].get())).into()
-	}
-
-	fn output_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Output".into()
-	}
-
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		aabb_merger_splitter(&*self.input_points(), &*self.output_points(), dir)
+		draw_merger_splitter(draw, self)
 	}
 }
 
@@ -485,26 +352,16 @@ impl ComponentPlacer for Merger {
 	}
 
 	fn draw(&self, draw: Draw) {
-		draw_merger_splitter(draw, &*self.input_points(), &*self.output_points())
+		draw_merger_splitter(draw, self)
 	}
 }
 
-fn aabb_merger_splitter(
-	inputs: &[PointOffset],
-	outputs: &[PointOffset],
-	dir: Direction,
-) -> RelativeAabb {
-	let mut aabb = RelativeAabb::new(inputs[0], outputs[0]);
-	inputs.iter().for_each(|&o| aabb = aabb.expand(o));
-	outputs.iter().for_each(|&o| aabb = aabb.expand(o));
-	dir * aabb
-}
-
-fn draw_merger_splitter(draw: Draw, in_pos: &[PointOffset], out_pos: &[PointOffset]) {
+fn draw_merger_splitter(draw: Draw, component: &dyn CircuitComponent) {
 	let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
 	let stroke = Stroke::new(3.0, color_alpha(Color32::WHITE, alpha));
 
-	let aabb = aabb_merger_splitter(in_pos, out_pos, Direction::Right);
+	let aabb = component.aabb(Direction::Right);
+	let (in_pos, out_pos) = (component.input_points(), component.output_points());
 
 	let (top, btm) = (
 		dir * PointOffset::new(0, aabb.max.y),
@@ -521,31 +378,7 @@ fn draw_merger_splitter(draw: Draw, in_pos: &[PointOffset], out_pos: &[PointOffs
 	{
 		let y = f32::from(p.y);
 		let (a, b) = (Vec2::new(0.0 - dx, y) * 16.0, Vec2::new(1.0 - dx, y) * 16.0);
-		painter.line_segment([pos + dir * a, pos + dir * b], stroke);
-	}
-}
-
-#[typetag::serde]
-impl CircuitComponent for Constant {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		[].into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		CENTER.into()
-	}
-
-	fn input_name(&self, _: usize) -> Box<str> {
-		panic!()
-	}
-
-	fn output_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Output".into()
-	}
-
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		dir * RelativeAabb::new(PointOffset::new(0, 0), PointOffset::new(0, 0))
+		painter.line_segment([pos + dir.rotate_vec2(a), pos + dir.rotate_vec2(b)], stroke);
 	}
 }
 
@@ -569,27 +402,33 @@ impl ComponentPlacer for Constant {
 }
 
 #[typetag::serde]
-impl CircuitComponent for ReadOnlyMemory {
-	fn input_points(&self) -> Box<[PointOffset]> {
-		[PointOffset::new(-4, 0)].into()
-	}
-
-	fn output_points(&self) -> Box<[PointOffset]> {
-		[PointOffset::new(4, 0)].into()
-	}
-
-	fn input_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Address".into()
+impl ComponentPlacer for Clock {
+	fn name(&self) -> Box<str> {
+		"clock".into()
 	}
 
-	fn output_name(&self, index: usize) -> Box<str> {
-		assert!(index < 1);
-		"Value".into()
-	}
+	fn draw(&self, draw: Draw) {
+		let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
+		let stroke = stroke(alpha);
+		let v = [(-30.0, -12.0), (-2.0, -12.0), (-2.0, 12.0), (-30.0, 12.0)]
+			.into_iter()
+			.map(|(x, y)| pos + dir.rotate_vec2(Vec2::new(x, y)))
+			.collect();
+		painter.add(Shape::convex_polygon(v, fill(alpha), stroke));
 
-	fn aabb(&self, dir: Direction) -> RelativeAabb {
-		dir * RelativeAabb::new(PointOffset::new(-4, -3), PointOffset::new(4, 3))
+		// A single period of a square wave.
+		let v = [
+			(-26.0, 6.0),
+			(-20.0, 6.0),
+			(-20.0, -6.0),
+			(-12.0, -6.0),
+			(-12.0, 6.0),
+			(-6.0, 6.0),
+		]
+		.into_iter()
+		.map(|(x, y)| pos + dir.rotate_vec2(Vec2::new(x, y)))
+		.collect();
+		painter.add(Shape::line(v, Stroke::new(2.0, stroke.color)));
 	}
 }
 
@@ -642,6 +481,235 @@ impl ComponentPlacer for ReadOnlyMemory {
 	}
 }
 
+#[typetag::serde]
+impl ComponentPlacer for RandomAccessMemory {
+	fn name(&self) -> Box<str> {
+		"ram".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
+		let RelativeAabb { min, max } = self.aabb(dir);
+		let min = Vec2::new(f32::from(min.x) * 16.0, f32::from(min.y) * 16.0);
+		let max = Vec2::new(f32::from(max.x) * 16.0, f32::from(max.y) * 16.0);
+		let (min, max) = (pos + min, pos + max);
+
+		let rect = Rect::from_min_max(min, max);
+		let stroke = stroke(alpha);
+		painter.add(RectShape { rect, corner_radius: 0.0, fill: Color32::WHITE, stroke });
+		let rect = rect.shrink(16.0);
+		painter.add(RectShape { rect, corner_radius: 0.0, fill: Color32::DARK_GRAY, stroke });
+
+		let size = format!("{}x{}", self.entries(), self.bits);
+		for (d, text) in [(-6.0, "RAM"), (6.0, size.as_str())] {
+			painter.text(
+				pos + Vec2::new(0.0, d),
+				Align2::CENTER_CENTER,
+				text,
+				TextStyle::Monospace,
+				Color32::WHITE,
+			);
+		}
+	}
+}
+
+macro_rules! impl_flip_flop_cp {
+	($name:ident, $id:literal, $label:literal) => {
+		#[typetag::serde]
+		impl ComponentPlacer for $name {
+			fn name(&self) -> Box<str> {
+				$id.into()
+			}
+
+			fn draw(&self, draw: Draw) {
+				let aabb = self.aabb(draw.direction);
+				let clock = self.input_points()[self.inputs().len() - 3];
+				draw_clocked(draw, aabb, clock, &[$label]);
+			}
+		}
+	};
+}
+
+impl_flip_flop_cp!(DFlipFlop, "d flip-flop", "D");
+
+impl_flip_flop_cp!(TFlipFlop, "t flip-flop", "T");
+
+impl_flip_flop_cp!(JkFlipFlop, "jk flip-flop", "JK");
+
+impl_flip_flop_cp!(SrFlipFlop, "sr flip-flop", "SR");
+
+#[typetag::serde]
+impl ComponentPlacer for Register {
+	fn name(&self) -> Box<str> {
+		"register".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		let aabb = self.aabb(draw.direction);
+		let bits = self.bits.to_string();
+		draw_clocked(draw, aabb, self.input_points()[1], &["REG", &bits]);
+	}
+}
+
+/// Draw a box with a triangle marking the clock input and lines of text in the center.
+fn draw_clocked(draw: Draw, aabb: RelativeAabb, clock: PointOffset, text: &[&str]) {
+	let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
+	let RelativeAabb { min, max } = aabb;
+	let min = Vec2::new(f32::from(min.x) * 16.0, f32::from(min.y) * 16.0);
+	let max = Vec2::new(f32::from(max.x) * 16.0, f32::from(max.y) * 16.0);
+	let rect = Rect::from_min_max(pos + min, pos + max);
+	let stroke = stroke(alpha);
+	painter.add(RectShape { rect, corner_radius: 0.0, fill: fill(alpha), stroke });
+
+	let clock = Vec2::new(f32::from(clock.x) * 16.0, f32::from(clock.y) * 16.0);
+	let v = [(0.0, -6.0), (8.0, 0.0), (0.0, 6.0)]
+		.into_iter()
+		.map(|(x, y)| pos + dir.rotate_vec2(clock + Vec2::new(x, y)))
+		.collect();
+	painter.add(Shape::line(v, Stroke::new(2.0, stroke.color)));
+
+	let top = (text.len() as f32 - 1.0) * -6.0;
+	for (i, text) in text.iter().enumerate() {
+		painter.text(
+			pos + Vec2::new(0.0, top + i as f32 * 12.0),
+			Align2::CENTER_CENTER,
+			text,
+			TextStyle::Monospace,
+			color_alpha(Color32::BLACK, alpha),
+		);
+	}
+}
+
+#[typetag::serde]
+impl ComponentPlacer for Multiplexer {
+	fn name(&self) -> Box<str> {
+		"multiplexer".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		draw_plexer(draw, self.data_inputs(), 16.0, "MUX");
+	}
+}
+
+#[typetag::serde]
+impl ComponentPlacer for Demultiplexer {
+	fn name(&self) -> Box<str> {
+		"demultiplexer".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		draw_plexer(draw, self.data_outputs(), -16.0, "DMX");
+	}
+}
+
+#[typetag::serde]
+impl ComponentPlacer for Decoder {
+	fn name(&self) -> Box<str> {
+		"decoder".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		draw_plexer(draw, self.outputs().len(), 0.0, "DEC");
+	}
+}
+
+#[typetag::serde]
+impl ComponentPlacer for PriorityEncoder {
+	fn name(&self) -> Box<str> {
+		"priority encoder".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		draw_plexer(draw, self.inputs().len(), 0.0, "PRI");
+	}
+}
+
+macro_rules! impl_arithmetic_cp {
+	($name:ident, $id:literal, $label:literal) => {
+		#[typetag::serde]
+		impl ComponentPlacer for $name {
+			fn name(&self) -> Box<str> {
+				$id.into()
+			}
+
+			fn draw(&self, draw: Draw) {
+				draw_plexer(draw, 2, 0.0, $label);
+			}
+		}
+	};
+}
+
+impl_arithmetic_cp!(Adder, "adder", "+");
+
+impl_arithmetic_cp!(Subtractor, "subtractor", "-");
+
+impl_arithmetic_cp!(Multiplier, "multiplier", "×");
+
+impl_arithmetic_cp!(Divider, "divider", "÷");
+
+impl_arithmetic_cp!(Comparator, "comparator", "<=>");
+
+#[typetag::serde]
+impl ComponentPlacer for Shifter {
+	fn name(&self) -> Box<str> {
+		"shifter".into()
+	}
+
+	fn draw(&self, draw: Draw) {
+		let text = match self.kind {
+			ShiftKind::LogicalLeft => "<<",
+			ShiftKind::LogicalRight => ">>",
+			ShiftKind::ArithmeticRight => ">>>",
+			ShiftKind::RotateLeft => "ROL",
+			ShiftKind::RotateRight => "ROR",
+		};
+		draw_plexer(draw, 2, 0.0, text);
+	}
+}
+
+macro_rules! impl_unary_cp {
+	($name:ident, $id:literal, $label:literal) => {
+		#[typetag::serde]
+		impl ComponentPlacer for $name {
+			fn name(&self) -> Box<str> {
+				$id.into()
+			}
+
+			fn draw(&self, draw: Draw) {
+				draw_plexer(draw, 0, 0.0, $label);
+			}
+		}
+	};
+}
+
+impl_unary_cp!(BitReverse, "bit reverse", "REV");
+
+impl_unary_cp!(PopCount, "popcount", "POP");
+
+impl_unary_cp!(LeadingZeros, "leading zeros", "CLZ");
+
+impl_unary_cp!(Extender, "extender", "EXT");
+
+/// Draw a plexer with `n` data ports. The right side is `slope` shorter than the left side at both
+/// the top and the bottom, or the left side if `slope` is negative.
+fn draw_plexer(draw: Draw, n: usize, slope: f32, text: &str) {
+	let Draw { painter, alpha, position: pos, direction: dir, .. } = draw;
+	let h = (n / 2 + 1) as f32 * 16.0;
+	let (l, r) = (h + slope.min(0.0), h - slope.max(0.0));
+	let v = [(-32.0, -l), (32.0, -r), (32.0, r), (-32.0, l)]
+		.into_iter()
+		.map(|(x, y)| pos + dir.rotate_vec2(Vec2::new(x, y)))
+		.collect();
+	painter.add(Shape::convex_polygon(v, fill(alpha), stroke(alpha)));
+	painter.text(
+		pos,
+		Align2::CENTER_CENTER,
+		text,
+		TextStyle::Monospace,
+		color_alpha(Color32::BLACK, alpha),
+	);
+}
+
 pub(super) fn color_alpha<C>(color: C, alpha: f32) -> C
 where
 	C: From<Rgba> + Into<Rgba>,
@@ -650,6 +718,15 @@ where
 	c.multiply(alpha).into()
 }
 
+/// The color of a value, based on the state of the lowest bit.
+pub(super) fn value_color(value: Value) -> Color32 {
+	match value.to_masks(1) {
+		(_, 1, _) => Color32::BLUE,
+		(_, _, 1) => Color32::RED,
+		(v, ..) => [Color32::DARK_GREEN, Color32::GREEN][v],
+	}
+}
+
 pub(super) fn fill(alpha: f32) -> Color32 {
 	color_alpha(Color32::WHITE, alpha)
 }
@@ -0,0 +1,152 @@
+use core::fmt;
+use eframe::egui;
+use logimu_core::simulator::ir::{Value, MAX_WORDS, WORD_BITS};
+
+/// List of circuit inputs & outputs, each with a numeric input to change it.
+#[derive(Default)]
+pub struct InputsOutputs {
+	/// The text of the wide input that is being edited.
+	buffer: Option<(usize, String)>,
+}
+
+impl InputsOutputs {
+	pub fn show(
+		&mut self,
+		ctx: &egui::CtxRef,
+		inputs: &[(impl fmt::Display, u16, usize)],
+		outputs: &[(impl fmt::Display, usize)],
+		input_values: &mut [Value],
+		output_values: &[Value],
+	) {
+		if inputs.is_empty() && outputs.is_empty() {
+			return;
+		}
+
+		egui::Window::new("Inputs & outputs").show(ctx, |ui| {
+			egui::ScrollArea::vertical()
+				.max_width(f32::INFINITY)
+				.show(ui, |ui| {
+					ui.horizontal(|ui| {
+						if !inputs.is_empty() {
+							ui.vertical(|ui| {
+								for (l, bits, i) in inputs.iter() {
+									ui.horizontal(|ui| {
+										match input_values[*i] {
+											// Values wider than 32 bits are edited as hex text.
+											v if *bits > 32 => {
+												let mut text = match self.buffer.take() {
+													Some((j, text)) if j == *i => text,
+													b => {
+														self.buffer = b;
+														value_to_hex(v)
+													}
+												};
+												let te = egui::TextEdit::singleline(&mut text)
+													.desired_width(16.0 * 8.0);
+												if ui.add(te).has_focus() {
+													self.buffer = Some((*i, text));
+												} else if let Some(v) = hex_to_value(&text) {
+													input_values[*i] = v;
+												}
+											}
+											Value::Set(mut v) => {
+												let d = egui::DragValue::new(&mut v)
+													.clamp_range(0..=(1u64 << bits) - 1);
+												ui.add(d);
+												input_values[*i] = Value::Set(v);
+											}
+											// Set the input to a known value when clicked.
+											v => {
+												if ui.button(value_to_string(v)).clicked() {
+													input_values[*i] = Value::Set(0);
+												}
+											}
+										}
+										ui.label(l);
+									});
+								}
+							});
+						}
+						// FIXME separator doesn't go entirely to the bottom
+						(!inputs.is_empty() && !outputs.is_empty()).then(|| ui.separator());
+						if !outputs.is_empty() {
+							ui.vertical(|ui| {
+								for (l, i) in outputs.iter() {
+									let v = value_to_string(output_values[*i]);
+									ui.horizontal(|ui| {
+										ui.add(egui::Label::new(v).monospace());
+										ui.label(l);
+									});
+								}
+							});
+						}
+					})
+				});
+		});
+	}
+}
+
+/// Convert a value to a string. Values that are partially floating or shorted are shown bit
+/// per bit and wide values are shown in hexadecimal.
+pub(super) fn value_to_string(value: Value) -> String {
+	match value {
+		Value::Wide { floating, short, .. } if floating == short && short == [0; MAX_WORDS] => {
+			format!("0x{}", value_to_hex(value))
+		}
+		Value::Wide { value, floating, short } => {
+			let words = (0..MAX_WORDS).rev().map(|k| {
+				let s = value_to_string(Value::Mixed {
+					value: value[k],
+					floating: floating[k],
+					short: short[k],
+				});
+				format!("{:0>1$}", s, usize::from(WORD_BITS))
+			});
+			words.collect::<String>().trim_start_matches('0').into()
+		}
+		Value::Set(i) => i.to_string(),
+		Value::Floating => "x".to_string(),
+		Value::Short => "E".to_string(),
+		Value::Mixed { value, floating, short } => {
+			let bits = usize::BITS - (value | floating | short).leading_zeros();
+			(0..bits)
+				.rev()
+				.map(
+					|i| match ((value >> i) & 1, (floating >> i) & 1, (short >> i) & 1) {
+						(_, 1, _) => 'x',
+						(_, _, 1) => 'E',
+						(v, ..) => ['0', '1'][v],
+					},
+				)
+				.collect()
+		}
+	}
+}
+
+/// Convert the set bits of a value to hexadecimal digits.
+pub(super) fn value_to_hex(value: Value) -> String {
+	let digits = usize::from(WORD_BITS / 4);
+	let words = (0..MAX_WORDS)
+		.rev()
+		.map(|k| value.word(k).to_masks(usize::MAX).0);
+	let s = words
+		.map(|w| format!("{:01$x}", w, digits))
+		.collect::<String>();
+	let s = s.trim_start_matches('0');
+	if s.is_empty() { "0" } else { s }.into()
+}
+
+/// Parse hexadecimal digits, optionally prefixed with `0x`.
+pub(super) fn hex_to_value(s: &str) -> Option<Value> {
+	let s = s.trim();
+	let s = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+	let digits = usize::from(WORD_BITS / 4);
+	if s.is_empty() || s.len() > digits * MAX_WORDS {
+		return None;
+	}
+	let words = s.rchunks(digits).map(|w| {
+		let w = core::str::from_utf8(w).ok()?;
+		usize::from_str_radix(w, 16).ok().map(Value::Set)
+	});
+	Some(Value::from_words(words.collect::<Option<Vec<_>>>()?))
+}
@@ -0,0 +1,67 @@
+use super::inputs_outputs::{hex_to_value, value_to_hex, value_to_string};
+use eframe::egui;
+use logimu_core::simulator::{self, ir::Value, GraphNodeHandle};
+
+/// The contents of the memory of a component, e.g. a RAM, which can be edited while simulating.
+#[derive(Default)]
+pub struct MemoryEditor {
+	/// The first entry shown.
+	start: usize,
+	/// The entry that is being edited and its text.
+	buffer: Option<(usize, String)>,
+}
+
+impl MemoryEditor {
+	/// The amount of entries shown at once.
+	const ROWS: usize = 16;
+
+	pub fn show(
+		&mut self,
+		ctx: &egui::CtxRef,
+		state: &mut simulator::State,
+		component: GraphNodeHandle,
+	) {
+		let len = match state.memory_len(component) {
+			Some(len) => len,
+			None => return,
+		};
+		let digits = format!("{:x}", len - 1).len();
+
+		egui::Window::new("Memory").show(ctx, |ui| {
+			self.start = self.start.min(len - 1);
+			let d = egui::DragValue::new(&mut self.start)
+				.clamp_range(0..=len - 1)
+				.speed(Self::ROWS as f64)
+				.prefix("Start: ");
+			ui.add(d);
+			ui.separator();
+			for i in self.start..(self.start + Self::ROWS).min(len) {
+				let value = state.read_memory(component, i);
+				let (mut text, modify) = match self.buffer.take() {
+					Some((j, text)) if j == i => (text, true),
+					b => {
+						self.buffer = b;
+						let text = match value {
+							Value::Set(_) | Value::Wide { .. } => value_to_hex(value),
+							v => value_to_string(v),
+						};
+						(text, false)
+					}
+				};
+				ui.horizontal(|ui| {
+					ui.add(egui::Label::new(format!("{:01$x}", i, digits)).monospace());
+					let te = egui::TextEdit::singleline(&mut text).desired_width(16.0 * 8.0);
+					if ui.add(te).has_focus() {
+						self.buffer = Some((i, text));
+					} else if modify {
+						// Values are entered in hexadecimal, anything else is ignored.
+						match hex_to_value(&text) {
+							Some(v) if v != value => state.write_memory(component, i, v),
+							_ => (),
+						}
+					}
+				});
+			}
+		});
+	}
+}
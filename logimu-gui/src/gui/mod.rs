@@ -7,7 +7,9 @@ mod gates;
 mod ic;
 mod inputs_outputs;
 mod log;
+mod memory;
 mod script;
+mod waveform;
 
 use component::*;
 use components_info::*;
@@ -16,13 +18,15 @@ use dialog::Dialog;
 use file::OpenDialog;
 use inputs_outputs::*;
 use log::*;
+use memory::*;
 use script::*;
+use waveform::*;
 
-use crate::circuit;
-use crate::circuit::{Aabb, CircuitComponent, Direction, Ic, PointOffset, WireHandle};
-use crate::simulator;
+use logimu_core::circuit;
+use logimu_core::circuit::{Aabb, CircuitComponent, Direction, Ic, PointOffset, WireHandle};
+use logimu_core::simulator;
 
-use crate::simulator::{ir, GraphNodeHandle, PropertyValue, SetProperty};
+use logimu_core::simulator::{ir, GraphNodeHandle, NexusHandle, PropertyValue, SetProperty};
 
 use core::any::TypeId;
 use core::{fmt, mem};
@@ -33,22 +37,47 @@ use std::io;
 use std::path::{Path, PathBuf};
 
 const COMPONENTS: &[(&'static str, fn() -> Box<dyn ComponentPlacer>)] = {
-	fn a() -> core::num::NonZeroU8 {
-		core::num::NonZeroU8::new(1).unwrap()
+	fn a() -> core::num::NonZeroU16 {
+		core::num::NonZeroU16::new(1).unwrap()
 	}
 	fn b() -> simulator::NonZeroOneU8 {
 		simulator::NonZeroOneU8::new(2).unwrap()
 	}
+	fn c() -> core::num::NonZeroU32 {
+		core::num::NonZeroU32::new(1).unwrap()
+	}
 	use simulator::*;
 	&[
 		("and", || Box::new(AndGate::new(b()))),
 		("or", || Box::new(OrGate::new(b()))),
 		("not", || Box::new(NotGate::new())),
+		("tri-state buffer", || Box::new(TriStateBuffer::new(a()))),
 		("xor", || Box::new(XorGate::new(b()))),
 		("splitter", || Box::new(Splitter::new())),
 		("merger", || Box::new(Merger::new(a()))),
 		("constant", || Box::new(Constant::new(a(), 0))),
 		("rom", || Box::new(ReadOnlyMemory::default())),
+		("ram", || Box::new(RandomAccessMemory::default())),
+		("clock", || Box::new(Clock::new(c(), c()))),
+		("d flip-flop", || Box::new(DFlipFlop::new())),
+		("t flip-flop", || Box::new(TFlipFlop::new())),
+		("jk flip-flop", || Box::new(JkFlipFlop::new())),
+		("sr flip-flop", || Box::new(SrFlipFlop::new())),
+		("register", || Box::new(Register::new(a()))),
+		("multiplexer", || Box::new(Multiplexer::new(a(), a()))),
+		("demultiplexer", || Box::new(Demultiplexer::new(a(), a()))),
+		("decoder", || Box::new(Decoder::new(a()))),
+		("priority encoder", || Box::new(PriorityEncoder::new(a()))),
+		("adder", || Box::new(Adder::new(a()))),
+		("subtractor", || Box::new(Subtractor::new(a()))),
+		("multiplier", || Box::new(Multiplier::new(a()))),
+		("divider", || Box::new(Divider::new(a()))),
+		("comparator", || Box::new(Comparator::new(a()))),
+		("shifter", || Box::new(Shifter::new(a()))),
+		("bit reverse", || Box::new(BitReverse::new(a()))),
+		("popcount", || Box::new(PopCount::new(a()))),
+		("leading zeros", || Box::new(LeadingZeros::new(a()))),
+		("extender", || Box::new(Extender::new(a(), a()))),
 	]
 };
 
@@ -73,6 +102,23 @@ impl fmt::Display for LoadCircuitError {
 	}
 }
 
+#[derive(Debug)]
+pub enum LoadSnapshotError {
+	Io(io::Error),
+	Serde(ron::Error),
+	Restore(ir::RestoreError),
+}
+
+impl fmt::Display for LoadSnapshotError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Io(e) => e.fmt(f),
+			Self::Serde(e) => e.fmt(f),
+			Self::Restore(e) => e.fmt(f),
+		}
+	}
+}
+
 pub struct App {
 	dialog: Option<Box<dyn Dialog>>,
 	component: Option<Box<dyn ComponentPlacer>>,
@@ -96,16 +142,31 @@ pub struct App {
 	log: Log,
 	components_info: ComponentsInfo,
 	io_editor: InputsOutputs,
+	waveform: Waveform,
+	memory_editor: MemoryEditor,
 
 	logged_parse_error: bool,
+	/// The last oscillation that was logged.
+	logged_oscillation: Option<ir::Oscillation>,
 
 	drag_component: Option<(GraphNodeHandle, PointOffset, Direction)>,
 	copied_properties: CopiedProperties,
 	circuit_offset: egui::Vec2,
 
 	enable_simulation: bool,
+	/// Whether simulation time advances on its own, which drives clocks.
+	run_clock: bool,
+	/// The amount of ticks per second while the clock is running.
+	frequency: f64,
+	/// Ticks that should have been done but didn't fit in a whole frame.
+	pending_ticks: f64,
 }
 
+/// The maximum amount of ticks per frame, so a slow frame doesn't make the next one even slower.
+const MAX_TICKS_PER_FRAME: f64 = 100_000.0;
+/// The amount of steps that can be undone.
+const HISTORY_STEPS: usize = 10_000;
+
 impl App {
 	pub fn new() -> Self {
 		let mut s = Self {
@@ -130,15 +191,22 @@ impl App {
 			log: Default::default(),
 			components_info: Default::default(),
 			io_editor: Default::default(),
+			waveform: Default::default(),
+			memory_editor: Default::default(),
 
 			logged_parse_error: false,
+			logged_oscillation: None,
 
 			drag_component: None,
 			copied_properties: Default::default(),
 			circuit_offset: Default::default(),
 
 			enable_simulation: true,
+			run_clock: false,
+			frequency: 10.0,
+			pending_ticks: 0.0,
 		};
+		s.program_state.set_history(HISTORY_STEPS);
 		let f = std::env::args().skip(1).next();
 		let f = PathBuf::from(f.as_deref().unwrap_or("/tmp/ok.logimu"));
 		match s.load_from_file(f.clone().into()) {
@@ -174,7 +242,7 @@ impl App {
 		for (c, ..) in self.circuit.components(circuit::Aabb::ALL) {
 			c.external_input().map(|i| {
 				self.inputs
-					.resize((i + 1).max(self.inputs.len()), ir::Value::Set(0)) // FIXME use Floating
+					.resize((i + 1).max(self.inputs.len()), ir::Value::Floating)
 			});
 			c.external_output().map(|i| {
 				self.outputs
@@ -193,12 +261,85 @@ impl App {
 		Ok(())
 	}
 
+	/// The file the snapshot of the simulation is saved to, next to the circuit.
+	fn snapshot_path(&self) -> PathBuf {
+		self.file_path.with_extension("snapshot")
+	}
+
+	pub fn save_snapshot(&self) -> Result<(), SaveCircuitError> {
+		let f = fs::File::create(self.snapshot_path()).map_err(SaveCircuitError::Io)?;
+		let snapshot = self.program_state.snapshot();
+		ron::ser::to_writer(f, &snapshot).map_err(SaveCircuitError::Serde)?;
+		Ok(())
+	}
+
+	/// Restore the simulation from the saved snapshot, including the inputs.
+	pub fn load_snapshot(&mut self) -> Result<(), LoadSnapshotError> {
+		let f = fs::File::open(self.snapshot_path()).map_err(LoadSnapshotError::Io)?;
+		let snapshot: ir::Snapshot = ron::de::from_reader(f).map_err(LoadSnapshotError::Serde)?;
+		self.program_state
+			.restore(&snapshot)
+			.map_err(LoadSnapshotError::Restore)?;
+		self.program_state.read_inputs(&mut self.inputs);
+		Ok(())
+	}
+
+	/// The file traces of the simulation are written to, next to the circuit.
+	fn vcd_path(&self) -> PathBuf {
+		self.file_path.with_extension("vcd")
+	}
+
+	/// Start tracing the simulation to a VCD file, or finish the current trace.
+	fn toggle_vcd(&mut self) {
+		let path = self.vcd_path();
+		let r = match self.program_state.set_vcd(None) {
+			Some(vcd) => vcd.finish().map(|()| format!("Wrote trace to {:?}", path)),
+			None => ir::Vcd::create(&path, self.circuit.vcd_signals()).map(|vcd| {
+				self.program_state.set_vcd(Some(vcd));
+				format!("Tracing to {:?}", path)
+			}),
+		};
+		match r {
+			Ok(s) => self.log.debug(s),
+			Err(e) => self
+				.log
+				.error(format!("Failed to write trace to {:?}: {}", path, e)),
+		}
+	}
+
+	/// Stop the simulation because the breakpoint with the given index fired.
+	fn pause_at_breakpoint(&mut self, index: usize) {
+		if let Some(b) = self.program_state.breakpoints().nth(index) {
+			self.log.success(format!(
+				"Breakpoint hit at tick {}: {}",
+				self.program_state.ticks(),
+				self.circuit.describe_breakpoint(b)
+			));
+		}
+		self.enable_simulation = false;
+		self.run_clock = false;
+		self.log.open = true;
+	}
+
 	pub fn load_ic(&mut self, path: Box<Path>) -> Result<(), circuit::LoadError> {
 		self.ic_components.insert(path.clone(), Ic::get_ic(path)?);
 		Ok(())
 	}
 }
 
+/// Add a breakpoint for changes of the given nexus, or remove all its breakpoints if it has
+/// any.
+fn toggle_breakpoint(state: &mut simulator::State, nexus: NexusHandle) {
+	let watch = ir::Watch::Nexus(nexus);
+	let mut breakpoints = state.breakpoints().copied().collect::<Vec<_>>();
+	let len = breakpoints.len();
+	breakpoints.retain(|b| b.watch != watch);
+	if breakpoints.len() == len {
+		breakpoints.push(ir::Breakpoint::new(watch, ir::Condition::Changes));
+	}
+	state.set_breakpoints(breakpoints);
+}
+
 impl epi::App for App {
 	fn name(&self) -> &str {
 		"Logimu"
@@ -236,7 +377,12 @@ impl epi::App for App {
 		}
 
 		let mut save = ctx.input().key_pressed(Key::S) && ctx.input().modifiers.ctrl;
-		let mut step_simulation = ctx.input().key_pressed(Key::I);
+		let shift = ctx.input().modifiers.shift;
+		let mut step_simulation = ctx.input().key_pressed(Key::I) && !shift;
+		let mut step_back = ctx.input().key_pressed(Key::I) && shift;
+		let mut seek = None;
+		let (mut save_snapshot, mut load_snapshot) = (false, false);
+		let mut toggle_vcd = false;
 
 		TopBottomPanel::top("top_panel").show(ctx, |ui| {
 			menu::bar(ui, |ui| {
@@ -289,24 +435,199 @@ impl epi::App for App {
 					}
 				});
 				self.log.open |= ui.button("Log").clicked();
+				self.waveform.open |= ui.button("Waveform").clicked();
+				menu::menu(ui, "Breakpoints", |ui| {
+					let mut breakpoints = self
+						.program_state
+						.breakpoints()
+						.copied()
+						.collect::<Vec<_>>();
+					if breakpoints.is_empty() {
+						ui.label("Middle-click a wire to add a breakpoint");
+					}
+					let (mut changed, mut remove) = (false, None);
+					for (i, b) in breakpoints.iter_mut().enumerate() {
+						ui.horizontal(|ui| {
+							ui.label(self.circuit.describe_breakpoint(b));
+							let conditions = [
+								(ir::Condition::Changes, "changes"),
+								(ir::Condition::Rises, "rises"),
+								(ir::Condition::Falls, "falls"),
+							];
+							for (c, name) in conditions {
+								changed |= ui.selectable_value(&mut b.condition, c, name).changed();
+							}
+							let mut v = match b.condition {
+								ir::Condition::Equals(ir::Value::Set(v)) => Some(v),
+								_ => None,
+							};
+							if ui.selectable_label(v.is_some(), "equals").clicked() {
+								v = Some(v.unwrap_or(0));
+								changed = true;
+							}
+							if let Some(mut v) = v {
+								changed |= ui.add(egui::DragValue::new(&mut v)).changed();
+								b.condition = ir::Condition::Equals(ir::Value::Set(v));
+							}
+							if ui.button("Remove").clicked() {
+								remove = Some(i);
+							}
+						});
+					}
+					if let Some(i) = remove {
+						breakpoints.remove(i);
+						changed = true;
+					}
+					if changed {
+						self.program_state.set_breakpoints(breakpoints);
+					}
+				});
 				menu::menu(ui, "Simulation", |ui| {
 					ui.checkbox(&mut self.enable_simulation, "Enabled");
+					let mut jit = self.program_state.backend() == ir::Backend::Jit;
+					if ui.checkbox(&mut jit, "JIT").changed() {
+						let backend = if jit {
+							ir::Backend::Jit
+						} else {
+							ir::Backend::Interpreter
+						};
+						if !self.program_state.set_backend(backend) {
+							self.log.error(if backend.is_available() {
+								"JIT does not support some operations used by this circuit"
+							} else {
+								"JIT is not supported on this platform"
+							});
+						}
+					}
+					let mut threads = self.program_state.threads().get();
+					let d = egui::DragValue::new(&mut threads)
+						.clamp_range(1..=64)
+						.prefix("Threads: ");
+					if ui.add(d).changed() {
+						self.program_state
+							.set_threads(core::num::NonZeroUsize::new(threads).unwrap());
+					}
 					step_simulation |= ui.button("Step").clicked();
+					step_back |= ui.button("Step back").clicked();
+					let nexus = self.selected_wires.first();
+					let nexus = nexus.and_then(|&w| self.circuit.wire(w)).map(|(_, n)| n);
+					let last_change = egui::Button::new("Last change");
+					if ui.add_enabled(nexus.is_some(), last_change).clicked() {
+						match self.program_state.last_change(nexus.unwrap()) {
+							Some(p) => seek = Some(p),
+							None => self.log.debug("no change in history"),
+						}
+					}
+					ui.separator();
+					save_snapshot |= ui.button("Save snapshot").clicked();
+					load_snapshot |= ui.button("Load snapshot").clicked();
+					let vcd = if self.program_state.has_vcd() {
+						"Stop VCD trace"
+					} else {
+						"Start VCD trace"
+					};
+					toggle_vcd |= ui.button(vcd).clicked();
 				});
+				let run = if self.run_clock { "Pause" } else { "Run" };
+				if ui.button(run).clicked() {
+					self.run_clock = !self.run_clock;
+					self.pending_ticks = 0.0;
+				}
+				let d = egui::DragValue::new(&mut self.frequency)
+					.clamp_range(0.1..=MAX_TICKS_PER_FRAME * 60.0)
+					.speed(0.1)
+					.suffix(" Hz");
+				ui.add(d);
+				ui.label(format!("Ticks: {}", self.program_state.ticks()));
+				let mut position = self.program_state.history_position();
+				let len = self.program_state.history_len();
+				let slider = egui::Slider::new(&mut position, 0..=len).text("History");
+				if ui.add(slider).changed() {
+					seek = Some(position);
+				}
 			});
 		});
 
 		// Run circuit
 		let program = std::sync::Arc::new(self.circuit.generate_ir());
 		self.program_state = mem::take(&mut self.program_state).adapt(program.clone());
+		// Changing an input while looking at the past continues the simulation from there.
+		let mut inputs = self.inputs.clone();
+		self.program_state.read_inputs(&mut inputs);
+		let input_changed = inputs != self.inputs;
 		self.program_state.write_inputs(&self.inputs);
-		if self.enable_simulation {
-			self.program_state.run(1024);
+		if let Some(p) = seek {
+			self.program_state.seek(p);
+			self.program_state.read_inputs(&mut self.inputs);
+		} else if step_back {
+			self.program_state.step_back();
+			self.program_state.read_inputs(&mut self.inputs);
+		}
+		let paused = !input_changed
+			&& self.program_state.history_position() < self.program_state.history_len();
+		if save_snapshot {
+			match self.save_snapshot() {
+				Ok(()) => self
+					.log
+					.debug(format!("Saved snapshot to {:?}", self.snapshot_path())),
+				Err(e) => self.log.error(format!(
+					"Failed to save snapshot to {:?}: {:?}",
+					self.snapshot_path(),
+					e
+				)),
+			}
+		}
+		if load_snapshot {
+			match self.load_snapshot() {
+				Ok(()) => self
+					.log
+					.debug(format!("Loaded snapshot from {:?}", self.snapshot_path())),
+				Err(e) => {
+					self.log.error(format!("Failed to load snapshot: {}", e));
+					self.log.open = true;
+				}
+			}
+		}
+		if toggle_vcd {
+			self.toggle_vcd();
+		}
+		if paused {
+			if step_simulation {
+				self.program_state.step_forward();
+				self.program_state.read_inputs(&mut self.inputs);
+			}
+		} else if self.run_clock {
+			self.pending_ticks += self.frequency * f64::from(ctx.input().unstable_dt);
+			let ticks = self.pending_ticks.floor().min(MAX_TICKS_PER_FRAME);
+			self.pending_ticks = (self.pending_ticks - ticks).min(1.0);
+			if let Some(i) = self.program_state.advance(ticks as usize) {
+				self.pause_at_breakpoint(i);
+			}
+			ctx.request_repaint();
+		} else if self.enable_simulation {
+			match self.program_state.run(1024) {
+				ir::RunResult::Oscillated(o) => {
+					// Only log each oscillation once instead of every frame.
+					if self.logged_oscillation.as_ref() != Some(&o) {
+						self.log.error(self.circuit.describe_oscillation(&o));
+						self.log.open = true;
+						self.logged_oscillation = Some(o);
+					}
+				}
+				ir::RunResult::Settled { .. } => self.logged_oscillation = None,
+				// Large circuits may need more steps than a single frame allows.
+				ir::RunResult::Limit => (),
+				ir::RunResult::Break { breakpoint, .. } => self.pause_at_breakpoint(breakpoint),
+			}
 		} else if step_simulation {
 			self.log.debug("stepping simulation");
 			self.program_state.step();
+			if let Some(i) = self.program_state.breakpoint_hit() {
+				self.pause_at_breakpoint(i);
+			}
 		}
 		self.program_state.read_outputs(&mut self.outputs);
+		self.waveform.record(&mut self.program_state);
 
 		if save {
 			match self.save_to_file(None) {
@@ -330,13 +651,41 @@ impl epi::App for App {
 		self.script_editor.show(ctx, &mut self.circuit);
 		self.log.show(ctx);
 
+		// Selected wires, inputs & outputs can be added to the waveform.
+		let mut selected = Vec::new();
+		for &w in self.selected_wires.iter() {
+			if let Some((w, n)) = self.circuit.wire(w) {
+				let label = self.circuit.wire_labels().find(|&(.., m)| m == n);
+				let name = label.map_or_else(
+					|| {
+						let (p, _): (circuit::Point, circuit::Point) = w.into();
+						format!("({}, {})", p.x, p.y)
+					},
+					|(_, l, _)| l.into(),
+				);
+				selected.push((name, ir::Watch::Nexus(n)));
+			}
+		}
+		for &h in self.selected_components.iter() {
+			let (c, ..) = self.circuit.component(h).unwrap();
+			let label = c.label().filter(|l| !l.is_empty());
+			if let Some(i) = c.external_input() {
+				let name = label.map_or_else(|| format!("in {}", i), String::from);
+				selected.push((name, ir::Watch::Input(i)));
+			}
+			if let Some(o) = c.external_output() {
+				let name = label.map_or_else(|| format!("out {}", o), String::from);
+				selected.push((name, ir::Watch::Output(o)));
+			}
+		}
+		self.waveform.show(ctx, &selected);
+
 		// If one of the selected components has an external input, allow modifying it.
 		let (mut ei, mut eo) = (Vec::new(), Vec::new());
 		for (c, ..) in self.circuit.components(circuit::Aabb::ALL) {
 			if let Some(i) = c.external_input() {
 				let bits = c.outputs()[0].bits.get();
-				let range = 0..=((1 << bits) - 1);
-				ei.push((c.label().unwrap_or_default(), range, i));
+				ei.push((c.label().unwrap_or_default(), bits, i));
 			}
 			if let Some(o) = c.external_output() {
 				eo.push((c.label().unwrap_or_default(), o));
@@ -354,7 +703,7 @@ impl epi::App for App {
 			ui.separator();
 
 			// Built in components
-			let bits = core::num::NonZeroU8::new(1).unwrap();
+			let bits = core::num::NonZeroU16::new(1).unwrap();
 			if ui.button("wire").clicked() {
 				self.component = None;
 			}
@@ -409,6 +758,22 @@ impl epi::App for App {
 			}
 		}
 
+		// Edit the contents of the selected RAM while the simulation runs.
+		if let [h] = self.selected_components[..] {
+			self.memory_editor.show(ctx, &mut self.program_state, h);
+		}
+
+		// Label the selected wire, which names its nexus in traces.
+		if let [w] = self.selected_wires[..] {
+			let mut label = self.circuit.wire_label(w).unwrap_or_default().to_string();
+			Window::new("Wire").show(ctx, |ui| {
+				let te = TextEdit::singleline(&mut label).hint_text("Label");
+				if ui.add(te).changed() {
+					self.circuit.set_wire_label(w, &label);
+				}
+			});
+		}
+
 		CentralPanel::default().show(ctx, |ui| {
 			// Scroll the window
 			let mut d = ctx.input().scroll_delta / 50.0 * 16.0;
@@ -530,7 +895,10 @@ impl epi::App for App {
 						if let Some(i) = c.external_input() {
 							self.inputs[i] = match self.inputs[i] {
 								ir::Value::Set(i) => ir::Value::Set(i.wrapping_add(1)),
-								ir::Value::Short | ir::Value::Floating => ir::Value::Set(0),
+								ir::Value::Short
+								| ir::Value::Floating
+								| ir::Value::Mixed { .. }
+								| ir::Value::Wide { .. } => ir::Value::Set(0),
 							};
 						}
 					}
@@ -583,18 +951,16 @@ impl epi::App for App {
 
 			// Draw existing wires
 			let mut endpoints = HashSet::new();
+			let watched = self.program_state.breakpoints().map(|b| b.watch);
+			let watched = watched.collect::<Vec<_>>();
+			let mut breakpoint_wire = None;
 			for (w, wh, h) in self.circuit.wires(aabb) {
 				let radius = 1.5;
 				let intersects = wires.contains(&h);
 				let color = if intersects {
 					Color32::YELLOW
 				} else {
-					pub use crate::simulator::ir::Value;
-					match self.program_state.read_nexus(h) {
-						Value::Set(v) => [Color32::DARK_GREEN, Color32::GREEN][v & 1],
-						Value::Floating => Color32::BLUE,
-						Value::Short => Color32::RED,
-					}
+					gates::value_color(self.program_state.read_nexus(h))
 				};
 				let stroke = Stroke::new(radius * 2.0, color);
 				let intersects = hover_pos.map_or(false, |p| w.intersect_point(pos2point(p)));
@@ -607,7 +973,20 @@ impl epi::App for App {
 						self.selected_wires.push(wh);
 					}
 				}
+				if intersects && e.clicked_by(PointerButton::Middle) {
+					breakpoint_wire = Some(h);
+				}
 				let (min, max) = w.into();
+				// Highlight wires with a breakpoint.
+				if watched.contains(&ir::Watch::Nexus(h)) {
+					let stroke = Stroke::new(radius * 4.0, Color32::LIGHT_BLUE);
+					paint.line_segment([point2pos(min), point2pos(max)], stroke);
+				}
+				// Highlight wires that are driven by multiple components at the same time.
+				if self.program_state.is_contended(h) {
+					let stroke = Stroke::new(radius * 6.0, Color32::RED.linear_multiply(0.5));
+					paint.line_segment([point2pos(min), point2pos(max)], stroke);
+				}
 				paint.line_segment([point2pos(min), point2pos(max)], stroke);
 
 				// Draw a circle to avoid disjoint-looking wires.
@@ -620,6 +999,19 @@ impl epi::App for App {
 					}
 				}
 			}
+			if let Some(h) = breakpoint_wire {
+				toggle_breakpoint(&mut self.program_state, h);
+			}
+			for (p, label, _) in self.circuit.wire_labels() {
+				let pos = point2pos(p) - Vec2::new(0.0, 6.0);
+				paint.text(
+					pos,
+					Align2::CENTER_BOTTOM,
+					label,
+					TextStyle::Small,
+					Color32::LIGHT_GRAY,
+				);
+			}
 
 			// Draw interaction objects (pointer, component, wire ...)
 			if let Some(pos) = hover_pos {
@@ -639,7 +1031,7 @@ impl epi::App for App {
 
 					if e.clicked_by(PointerButton::Primary) {
 						(c.type_id() == TypeId::of::<simulator::In>())
-							.then(|| self.inputs.push(ir::Value::Set(0))); // FIXME use Floating
+							.then(|| self.inputs.push(ir::Value::Floating));
 						(c.type_id() == TypeId::of::<simulator::Out>())
 							.then(|| self.outputs.push(ir::Value::Floating));
 						self.circuit
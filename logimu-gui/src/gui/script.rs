@@ -1,10 +1,10 @@
-use crate::circuit::{Circuit, CircuitComponent};
 use core::mem;
 use eframe::egui::{
 	self,
 	text::{LayoutJob, LayoutSection, TextFormat},
 	Align, Color32, TextStyle,
 };
+use logimu_core::circuit::{Circuit, CircuitComponent};
 
 #[derive(Default)]
 pub struct ScriptEditor {
@@ -0,0 +1,294 @@
+use super::gates;
+use eframe::egui::{self, Align2, Color32, PointerButton, Pos2, Sense, Stroke, TextStyle, Vec2};
+use logimu_core::simulator::{self, ir};
+use std::collections::VecDeque;
+
+/// A timing diagram of signals recorded from the simulation.
+pub struct Waveform {
+	pub open: bool,
+	signals: Vec<Signal>,
+	/// The width of a tick in points.
+	scale: f32,
+	/// The tick at the left edge of the plot.
+	start: f32,
+	/// Whether to keep the current tick in view.
+	follow: bool,
+	/// The ticks marked with the primary & secondary button.
+	cursors: [Option<u64>; 2],
+	/// The current tick of the simulation.
+	now: u64,
+}
+
+struct Signal {
+	name: String,
+	watch: ir::Watch,
+	bits: u16,
+	/// The tick at which each value was first seen, oldest first.
+	samples: VecDeque<(u64, ir::Value)>,
+}
+
+impl Default for Waveform {
+	fn default() -> Self {
+		Self {
+			open: false,
+			signals: Vec::new(),
+			scale: 16.0,
+			start: 0.0,
+			follow: true,
+			cursors: [None; 2],
+			now: 0,
+		}
+	}
+}
+
+impl Waveform {
+	const MAX_SAMPLES: usize = 1 << 16;
+	const ROW_HEIGHT: f32 = 24.0;
+	const NAME_WIDTH: f32 = 96.0;
+
+	/// Take the values recorded by the probes of the state since the last call. The probes are
+	/// replaced if they don't match the shown signals.
+	pub fn record(&mut self, state: &mut simulator::State) {
+		self.now = state.ticks();
+		if !state.probes().eq(self.signals.iter().map(|s| s.watch)) {
+			state.set_probes(self.signals.iter().map(|s| s.watch));
+		}
+		for (s, samples) in self.signals.iter_mut().zip(state.take_samples()) {
+			for (t, v) in samples {
+				// Going back in time replaces the recorded future.
+				while matches!(s.samples.back(), Some(&(b, _)) if b >= t) {
+					s.samples.pop_back();
+				}
+				(s.samples.len() >= Self::MAX_SAMPLES).then(|| s.samples.pop_front());
+				s.samples.push_back((t, v));
+			}
+			// Steps after the current tick were undone.
+			while s.samples.len() > 1 && matches!(s.samples.back(), Some(&(b, _)) if b > self.now) {
+				s.samples.pop_back();
+			}
+			s.bits = state.watch_bits(s.watch);
+		}
+	}
+
+	/// Add a signal, unless it is shown already.
+	pub fn add(&mut self, name: String, watch: ir::Watch) {
+		if self.signals.iter().all(|s| s.watch != watch) {
+			let samples = VecDeque::new();
+			self.signals.push(Signal { name, watch, bits: 1, samples });
+		}
+	}
+
+	/// Show the diagram. `selected` are the signals that can be added.
+	pub fn show(&mut self, ctx: &egui::CtxRef, selected: &[(String, ir::Watch)]) {
+		if !self.open {
+			return;
+		}
+		let mut open = self.open;
+		egui::Window::new("Waveform")
+			.open(&mut open)
+			.show(ctx, |ui| {
+				ui.horizontal(|ui| {
+					let add = egui::Button::new("Add selected");
+					if ui.add_enabled(!selected.is_empty(), add).clicked() {
+						for (name, watch) in selected {
+							self.add(name.clone(), *watch);
+						}
+					}
+					if ui.button("Clear").clicked() {
+						self.signals.clear();
+						self.cursors = [None; 2];
+					}
+					if ui.button("-").clicked() {
+						self.scale = (self.scale / 2.0).max(1.0 / 64.0);
+					}
+					if ui.button("+").clicked() {
+						self.scale = (self.scale * 2.0).min(64.0);
+					}
+					ui.checkbox(&mut self.follow, "Follow");
+				});
+				if self.signals.is_empty() {
+					ui.label("Select wires, inputs or outputs to add them");
+					return;
+				}
+				self.plot(ui);
+				let text = match self.cursors {
+					[Some(a), Some(b)] => format!(
+						"Cursors at {} and {}, {} ticks apart",
+						a,
+						b,
+						a.max(b) - a.min(b)
+					),
+					[Some(a), None] | [None, Some(a)] => format!("Cursor at {}", a),
+					[None, None] => "Click to place a cursor, right-click for a second one".into(),
+				};
+				ui.label(text);
+				ui.collapsing("Signals", |ui| {
+					let mut remove = None;
+					for (i, s) in self.signals.iter().enumerate() {
+						ui.horizontal(|ui| {
+							ui.label(&s.name);
+							ui.button("Remove").clicked().then(|| remove = Some(i));
+						});
+					}
+					remove.map(|i| self.signals.remove(i));
+				});
+			});
+		self.open = open;
+	}
+
+	fn plot(&mut self, ui: &mut egui::Ui) {
+		let rows = self.signals.len() + 1;
+		let size = Vec2::new(
+			ui.available_width().max(256.0),
+			Self::ROW_HEIGHT * rows as f32,
+		);
+		let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+		let paint = ui.painter_at(rect);
+		let left = rect.min.x + Self::NAME_WIDTH;
+		let width = rect.max.x - left;
+
+		// The end of the current tick.
+		let end = self.now as f32 + 1.0;
+		if response.dragged_by(PointerButton::Primary) {
+			self.start -= response.drag_delta().x / self.scale;
+			self.follow = false;
+		} else if self.follow {
+			self.start = end - width / self.scale;
+		}
+		self.start = self.start.max(0.0);
+		let (start, scale) = (self.start, self.scale);
+		let stop = start + width / scale;
+		let x = |t: f32| left + (t - start) * scale;
+
+		// Place a cursor, snapping to the nearest edge of the signal that was clicked on.
+		let button = [PointerButton::Primary, PointerButton::Secondary]
+			.iter()
+			.position(|&b| response.clicked_by(b));
+		if let Some((b, p)) = button.zip(response.interact_pointer_pos()) {
+			let t = (start + (p.x - left) / scale).max(0.0);
+			let row = ((p.y - rect.min.y) / Self::ROW_HEIGHT) as usize;
+			let signal = row.checked_sub(1).and_then(|r| self.signals.get(r));
+			let edges = signal.into_iter().flat_map(|s| s.samples.iter().skip(1));
+			let nearest = edges.map(|&(e, _)| e).min_by(|&a, &b| {
+				let (a, b) = ((a as f32 - t).abs(), (b as f32 - t).abs());
+				a.partial_cmp(&b).unwrap()
+			});
+			self.cursors[b] = match nearest {
+				Some(e) if (e as f32 - t).abs() * scale < 8.0 => Some(e),
+				_ => Some(t.round() as u64),
+			};
+		}
+
+		// Draw the time axis, with at least 48 points between labels.
+		let step = [1, 2, 5]
+			.iter()
+			.flat_map(|&m| (0..19).map(move |e| m * 10u64.pow(e)))
+			.filter(|&s| s as f32 * scale >= 48.0)
+			.min()
+			.unwrap_or(1);
+		let grid = Stroke::new(1.0, Color32::from_gray(48));
+		let mut t = (start / step as f32).ceil() as u64 * step;
+		while (t as f32) < stop {
+			let pos = Pos2::new(x(t as f32), rect.min.y);
+			paint.line_segment([pos, Pos2::new(pos.x, rect.max.y)], grid);
+			paint.text(pos, Align2::LEFT_TOP, t, TextStyle::Small, Color32::GRAY);
+			t += step;
+		}
+		let now = Pos2::new(x(end), rect.min.y);
+		let stroke = Stroke::new(1.0, Color32::GRAY);
+		paint.line_segment([now, Pos2::new(now.x, rect.max.y)], stroke);
+
+		for (i, s) in self.signals.iter().enumerate() {
+			let top = rect.min.y + Self::ROW_HEIGHT * (i + 1) as f32 + 4.0;
+			let bottom = top + Self::ROW_HEIGHT - 8.0;
+			let mid = (top + bottom) / 2.0;
+			let name = Pos2::new(rect.min.x + 4.0, mid);
+			let white = Color32::WHITE;
+			paint.text(name, Align2::LEFT_CENTER, &s.name, TextStyle::Body, white);
+			// Don't draw values over the names.
+			let plot = egui::Rect { min: Pos2::new(left, rect.min.y), max: rect.max };
+			let paint = paint.sub_region(plot);
+			let mut prev = None;
+			for (k, &(t, v)) in s.samples.iter().enumerate() {
+				let next = s.samples.get(k + 1).map_or(end, |&(n, _)| n as f32);
+				let t = t as f32;
+				if next <= start || t >= stop {
+					prev = Some(v);
+					continue;
+				}
+				let (x0, x1) = (x(t), x(next.min(end)));
+				let color = gates::value_color(v);
+				let stroke = Stroke::new(2.0, color);
+				if s.bits == 1 {
+					let y = |v: ir::Value| match v.to_masks(1) {
+						(1, 0, 0) => top,
+						(0, 0, 0) => bottom,
+						_ => mid,
+					};
+					paint.line_segment([Pos2::new(x0, y(v)), Pos2::new(x1, y(v))], stroke);
+					if let Some(p) = prev {
+						paint.line_segment([Pos2::new(x0, y(p)), Pos2::new(x0, y(v))], stroke);
+					}
+				} else {
+					// Buses are drawn as hexagons with the value in the middle.
+					let d = 2.0f32.min((x1 - x0) / 2.0);
+					for y in [top, bottom] {
+						let points = [Pos2::new(x0, mid), Pos2::new(x0 + d, y)];
+						paint.line_segment(points, stroke);
+						let points = [Pos2::new(x0 + d, y), Pos2::new(x1 - d, y)];
+						paint.line_segment(points, stroke);
+						let points = [Pos2::new(x1 - d, y), Pos2::new(x1, mid)];
+						paint.line_segment(points, stroke);
+					}
+					let text = hex(v, s.bits);
+					if x1 - x0 > 8.0 * (text.len() + 1) as f32 {
+						let pos = Pos2::new((x0.max(left) + x1) / 2.0, mid);
+						paint.text(
+							pos,
+							Align2::CENTER_CENTER,
+							text,
+							TextStyle::Monospace,
+							color,
+						);
+					}
+				}
+				prev = Some(v);
+			}
+		}
+
+		for (c, color) in self
+			.cursors
+			.iter()
+			.zip([Color32::YELLOW, Color32::LIGHT_BLUE])
+		{
+			if let Some(c) = c.filter(|&c| (start..stop).contains(&(c as f32))) {
+				let pos = Pos2::new(x(c as f32), rect.min.y);
+				let stroke = Stroke::new(1.0, color);
+				paint.line_segment([pos, Pos2::new(pos.x, rect.max.y)], stroke);
+			}
+		}
+	}
+}
+
+/// The digits of a value in hexadecimal. Digits with shorted bits are `X`, digits with floating
+/// bits `Z`.
+fn hex(value: ir::Value, bits: u16) -> String {
+	let bit = |i: u16| {
+		let word = value.word(usize::from(i / ir::WORD_BITS));
+		word.to_masks(1 << (i % ir::WORD_BITS))
+	};
+	let digit = |d: u16| {
+		let (mut digit, mut floating, mut short) = (0, false, false);
+		for i in d * 4..bits.min(d * 4 + 4) {
+			let (v, f, s) = bit(i);
+			digit |= u32::from(v != 0) << (i - d * 4);
+			(floating, short) = (floating | (f != 0), short | (s != 0));
+		}
+		match (short, floating) {
+			(true, _) => 'X',
+			(_, true) => 'Z',
+			_ => char::from_digit(digit, 16).unwrap().to_ascii_uppercase(),
+		}
+	};
+	(0..(bits + 3) / 4).rev().map(digit).collect()
+}
@@ -0,0 +1,24 @@
+#![feature(destructuring_assignment)]
+
+mod cli;
+mod gui;
+
+fn main() {
+	let args = std::env::args().skip(1).collect::<Vec<_>>();
+	if let Some(status) = cli::run(&args) {
+		std::process::exit(status);
+	}
+	eframe::run_native(
+		Box::new(gui::App::new()),
+		eframe::NativeOptions {
+			always_on_top: false,
+			decorated: true,
+			drag_and_drop_support: false,
+			icon_data: None,
+			initial_window_size: None,
+			maximized: false,
+			resizable: true,
+			transparent: false,
+		},
+	)
+}